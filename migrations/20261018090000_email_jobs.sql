@@ -0,0 +1,24 @@
+-- Jobs created by the email executor (scheduled and recurring sends)
+DROP TYPE IF EXISTS email_job_status CASCADE;
+CREATE TYPE email_job_status AS ENUM ('scheduled', 'running', 'completed', 'failed', 'cancelled');
+
+CREATE TABLE IF NOT EXISTS email_jobs (
+    id               SERIAL PRIMARY KEY,
+    strategy         JSONB NOT NULL,
+    requests         JSONB NOT NULL DEFAULT '[]',
+    messenger        TEXT NULL,
+    status           email_job_status NOT NULL DEFAULT 'scheduled',
+    next_run_at      TIMESTAMP WITH TIME ZONE,
+
+    -- Successful runs so far, compared to the strategy repeat_count
+    runs_completed   INT NOT NULL DEFAULT 0,
+    -- Consecutive failed attempts of the current run, compared to max_attempts
+    attempts         INT NOT NULL DEFAULT 0,
+    last_error       TEXT NULL,
+    last_result      JSONB NULL,
+
+    created_at       TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at       TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_email_jobs_due; CREATE INDEX idx_email_jobs_due ON email_jobs(next_run_at) WHERE status = 'scheduled';
+DROP INDEX IF EXISTS idx_email_jobs_status; CREATE INDEX idx_email_jobs_status ON email_jobs(status);
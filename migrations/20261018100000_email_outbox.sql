@@ -0,0 +1,29 @@
+-- Outbound messages waiting to be sent by the outbox workers
+DROP TYPE IF EXISTS outbox_status CASCADE;
+CREATE TYPE outbox_status AS ENUM ('queued', 'sending', 'sent', 'failed');
+
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id                 BIGSERIAL PRIMARY KEY,
+    job_id             INTEGER REFERENCES email_jobs(id) ON DELETE CASCADE,
+    campaign_id        INTEGER REFERENCES campaigns(id) ON DELETE SET NULL,
+    sequence_email_id  INTEGER NULL,
+    subscriber_id      INTEGER REFERENCES subscribers(id) ON DELETE SET NULL,
+    messenger          TEXT NULL,
+
+    -- Serialized EmailRequest, the tracking pixel is added when the message is sent
+    request            JSONB NOT NULL,
+
+    status             outbox_status NOT NULL DEFAULT 'queued',
+    attempts           INT NOT NULL DEFAULT 0,
+    available_at       TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    locked_at          TIMESTAMP WITH TIME ZONE NULL,
+    message_id         TEXT NULL,
+    last_error         TEXT NULL,
+    sent_at            TIMESTAMP WITH TIME ZONE NULL,
+    created_at         TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at         TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_outbox_available; CREATE INDEX idx_outbox_available ON email_outbox(available_at) WHERE status = 'queued';
+DROP INDEX IF EXISTS idx_outbox_locked; CREATE INDEX idx_outbox_locked ON email_outbox(locked_at) WHERE status = 'sending';
+DROP INDEX IF EXISTS idx_outbox_job; CREATE INDEX idx_outbox_job ON email_outbox(job_id, status);
+DROP INDEX IF EXISTS idx_outbox_campaign; CREATE INDEX idx_outbox_campaign ON email_outbox(campaign_id);
@@ -0,0 +1,17 @@
+-- Failure classification of outbox messages
+ALTER TABLE email_outbox ADD COLUMN IF NOT EXISTS last_error_code INT NULL;
+ALTER TABLE email_outbox ADD COLUMN IF NOT EXISTS failure_kind TEXT NULL CHECK (failure_kind IN ('transient', 'permanent'));
+
+-- Messages that could not be delivered to a subscriber, permanently or after the last retry
+CREATE TABLE IF NOT EXISTS subscriber_failures (
+    id             SERIAL PRIMARY KEY,
+    subscriber_id  INTEGER NOT NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    outbox_id      BIGINT NULL REFERENCES email_outbox(id) ON DELETE SET NULL,
+    campaign_id    INTEGER NULL REFERENCES campaigns(id) ON DELETE SET NULL,
+    failure_kind   TEXT NOT NULL CHECK (failure_kind IN ('transient', 'permanent')),
+    error          TEXT NOT NULL,
+    smtp_code      INT NULL,
+    attempts       INT NOT NULL DEFAULT 1,
+    created_at     TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_sub_failures_subscriber; CREATE INDEX idx_sub_failures_subscriber ON subscriber_failures(subscriber_id);
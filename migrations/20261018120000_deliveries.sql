@@ -0,0 +1,28 @@
+-- One row per message sent to a subscriber for a campaign / sequence email
+DROP TYPE IF EXISTS delivery_status CASCADE;
+CREATE TYPE delivery_status AS ENUM ('queued', 'sent', 'failed', 'bounced');
+
+CREATE TABLE IF NOT EXISTS deliveries (
+    id                 BIGSERIAL PRIMARY KEY,
+    campaign_id        INTEGER NOT NULL REFERENCES campaigns(id) ON DELETE CASCADE,
+    -- 0 when the campaign was sent without a sequence email
+    sequence_email_id  INTEGER NOT NULL DEFAULT 0,
+    subscriber_id      INTEGER NOT NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    messenger          TEXT NULL,
+    status             delivery_status NOT NULL DEFAULT 'queued',
+    attempts           INT NOT NULL DEFAULT 0,
+    message_id         TEXT NULL,
+    smtp_response      TEXT NULL,
+    queued_at          TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    sent_at            TIMESTAMP WITH TIME ZONE NULL,
+    failed_at          TIMESTAMP WITH TIME ZONE NULL,
+    bounced_at         TIMESTAMP WITH TIME ZONE NULL,
+    updated_at         TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (campaign_id, sequence_email_id, subscriber_id)
+);
+DROP INDEX IF EXISTS idx_deliveries_subscriber; CREATE INDEX idx_deliveries_subscriber ON deliveries(subscriber_id);
+DROP INDEX IF EXISTS idx_deliveries_status; CREATE INDEX idx_deliveries_status ON deliveries(status);
+DROP INDEX IF EXISTS idx_deliveries_message_id; CREATE INDEX idx_deliveries_message_id ON deliveries(message_id);
+DROP INDEX IF EXISTS idx_deliveries_queued_at; CREATE INDEX idx_deliveries_queued_at ON deliveries(queued_at);
+
+ALTER TABLE email_outbox ADD COLUMN IF NOT EXISTS delivery_id BIGINT NULL REFERENCES deliveries(id) ON DELETE SET NULL;
@@ -0,0 +1,5 @@
+-- Route or messenger that accepted each message, several providers can serve one messenger
+ALTER TABLE deliveries ADD COLUMN IF NOT EXISTS provider TEXT NULL;
+ALTER TABLE email_outbox ADD COLUMN IF NOT EXISTS provider TEXT NULL;
+
+DROP INDEX IF EXISTS idx_deliveries_provider; CREATE INDEX idx_deliveries_provider ON deliveries(provider);
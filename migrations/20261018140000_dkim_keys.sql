@@ -0,0 +1,14 @@
+-- DKIM signing key per sending domain
+CREATE TABLE IF NOT EXISTS dkim_keys (
+    id           SERIAL PRIMARY KEY,
+    domain       TEXT NOT NULL UNIQUE,
+    selector     TEXT NOT NULL,
+    -- rsa (PKCS#1 PEM) or ed25519 (base64 of the 32 byte secret)
+    algorithm    TEXT NOT NULL DEFAULT 'rsa' CHECK (algorithm IN ('rsa', 'ed25519')),
+    private_key  TEXT NOT NULL,
+    -- Signed headers, the default list when NULL
+    headers      TEXT[] NULL,
+    enabled      BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at   TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at   TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
@@ -0,0 +1,21 @@
+-- Every change of a subscription, voluntary ones (one_click, preference_center) feed the unsubscribe stats
+DROP TYPE IF EXISTS subscription_event_type CASCADE;
+CREATE TYPE subscription_event_type AS ENUM ('subscribed', 'unsubscribed');
+
+DROP TYPE IF EXISTS subscription_event_source CASCADE;
+CREATE TYPE subscription_event_source AS ENUM ('one_click', 'preference_center', 'api');
+
+CREATE TABLE IF NOT EXISTS subscription_events (
+    id             BIGSERIAL PRIMARY KEY,
+    subscriber_id  INTEGER NOT NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    list_id        INTEGER NULL REFERENCES lists(id) ON DELETE SET NULL,
+    -- Campaign the subscriber came from, e.g. through its List-Unsubscribe link
+    campaign_id    INTEGER NULL REFERENCES campaigns(id) ON DELETE SET NULL,
+    event          subscription_event_type NOT NULL,
+    source         subscription_event_source NOT NULL,
+    created_at     TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_subscription_events_subscriber; CREATE INDEX idx_subscription_events_subscriber ON subscription_events(subscriber_id);
+DROP INDEX IF EXISTS idx_subscription_events_list; CREATE INDEX idx_subscription_events_list ON subscription_events(list_id);
+DROP INDEX IF EXISTS idx_subscription_events_campaign; CREATE INDEX idx_subscription_events_campaign ON subscription_events(campaign_id);
+DROP INDEX IF EXISTS idx_subscription_events_created_at; CREATE INDEX idx_subscription_events_created_at ON subscription_events(created_at);
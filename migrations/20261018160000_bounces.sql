@@ -0,0 +1,22 @@
+-- Bounces reported for sent messages, hard ones count towards blocklisting the subscriber
+DROP TYPE IF EXISTS bounce_type CASCADE;
+CREATE TYPE bounce_type AS ENUM ('hard', 'soft');
+
+CREATE TABLE IF NOT EXISTS bounces (
+    id             BIGSERIAL PRIMARY KEY,
+    -- NULL when the bounced address matches no subscriber
+    subscriber_id  INTEGER NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    delivery_id    BIGINT NULL REFERENCES deliveries(id) ON DELETE SET NULL,
+    campaign_id    INTEGER NULL REFERENCES campaigns(id) ON DELETE SET NULL,
+    email          TEXT NOT NULL,
+    bounce_type    bounce_type NOT NULL,
+    -- RFC 3463 enhanced status code, e.g. 5.1.1
+    status_code    TEXT NULL,
+    diagnostic     TEXT NULL,
+    -- Where the report came from: dsn (HTTP or Maildir), or an ESP
+    source         TEXT NOT NULL DEFAULT 'dsn',
+    created_at     TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_bounces_subscriber; CREATE INDEX idx_bounces_subscriber ON bounces(subscriber_id);
+DROP INDEX IF EXISTS idx_bounces_campaign; CREATE INDEX idx_bounces_campaign ON bounces(campaign_id);
+DROP INDEX IF EXISTS idx_bounces_created_at; CREATE INDEX idx_bounces_created_at ON bounces(created_at);
@@ -0,0 +1,19 @@
+-- Spam complaints from feedback loops, each one blocklists the subscriber and unsubscribes them everywhere
+ALTER TYPE subscription_event_source ADD VALUE IF NOT EXISTS 'complaint';
+
+CREATE TABLE IF NOT EXISTS complaints (
+    id             BIGSERIAL PRIMARY KEY,
+    -- NULL when the report matches no subscriber (redacted recipient and unknown message)
+    subscriber_id  INTEGER NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    delivery_id    BIGINT NULL REFERENCES deliveries(id) ON DELETE SET NULL,
+    campaign_id    INTEGER NULL REFERENCES campaigns(id) ON DELETE SET NULL,
+    email          TEXT NULL,
+    feedback_type  TEXT NOT NULL DEFAULT 'abuse',
+    user_agent     TEXT NULL,
+    -- Where the report came from: arf (HTTP or Maildir), or an ESP
+    source         TEXT NOT NULL DEFAULT 'arf',
+    created_at     TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_complaints_subscriber; CREATE INDEX idx_complaints_subscriber ON complaints(subscriber_id);
+DROP INDEX IF EXISTS idx_complaints_campaign; CREATE INDEX idx_complaints_campaign ON complaints(campaign_id);
+DROP INDEX IF EXISTS idx_complaints_created_at; CREATE INDEX idx_complaints_created_at ON complaints(created_at);
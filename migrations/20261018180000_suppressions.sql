@@ -0,0 +1,18 @@
+-- Addresses and whole domains never sent to, whether or not they belong to a subscriber
+DROP TYPE IF EXISTS suppression_reason CASCADE;
+CREATE TYPE suppression_reason AS ENUM ('bounce', 'complaint', 'manual', 'legal');
+
+CREATE TABLE IF NOT EXISTS suppressions (
+    id          BIGSERIAL PRIMARY KEY,
+    -- Lowercased address (user@example.com) or domain (example.com)
+    value       TEXT NOT NULL UNIQUE,
+    is_domain   BOOLEAN NOT NULL DEFAULT FALSE,
+    reason      suppression_reason NOT NULL DEFAULT 'manual',
+    note        TEXT NULL,
+    -- NULL never expires
+    expires_at  TIMESTAMP WITH TIME ZONE NULL,
+    created_at  TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at  TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_suppressions_reason; CREATE INDEX idx_suppressions_reason ON suppressions(reason);
+DROP INDEX IF EXISTS idx_suppressions_expires_at; CREATE INDEX idx_suppressions_expires_at ON suppressions(expires_at);
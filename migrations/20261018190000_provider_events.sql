@@ -0,0 +1,29 @@
+-- Delivery events posted by provider webhooks (SES through SNS, SendGrid, Mailgun)
+DROP TYPE IF EXISTS provider_event_type CASCADE;
+CREATE TYPE provider_event_type AS ENUM ('delivered', 'deferred', 'bounce', 'complaint', 'open', 'click', 'unsubscribe', 'dropped');
+
+-- Unsubscribed through the provider's own link
+ALTER TYPE subscription_event_source ADD VALUE IF NOT EXISTS 'provider';
+
+-- Reported delivered to the mailbox by the provider
+ALTER TABLE deliveries ADD COLUMN IF NOT EXISTS delivered_at TIMESTAMP WITH TIME ZONE NULL;
+
+CREATE TABLE IF NOT EXISTS provider_events (
+    id             BIGSERIAL PRIMARY KEY,
+    provider       TEXT NOT NULL,
+    event_type     provider_event_type NOT NULL,
+    email          TEXT NULL,
+    message_id     TEXT NULL,
+    -- NULL when the event matches no delivery (message sent outside campaigns)
+    delivery_id    BIGINT NULL REFERENCES deliveries(id) ON DELETE SET NULL,
+    subscriber_id  INTEGER NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    campaign_id    INTEGER NULL REFERENCES campaigns(id) ON DELETE SET NULL,
+    url            TEXT NULL,
+    diagnostic     TEXT NULL,
+    occurred_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    created_at     TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_provider_events_delivery; CREATE INDEX idx_provider_events_delivery ON provider_events(delivery_id);
+DROP INDEX IF EXISTS idx_provider_events_campaign; CREATE INDEX idx_provider_events_campaign ON provider_events(campaign_id, event_type);
+DROP INDEX IF EXISTS idx_provider_events_subscriber; CREATE INDEX idx_provider_events_subscriber ON provider_events(subscriber_id);
+DROP INDEX IF EXISTS idx_provider_events_created_at; CREATE INDEX idx_provider_events_created_at ON provider_events(created_at);
@@ -0,0 +1,39 @@
+-- User-configured endpoints notified of subscriber events, and the log of every delivery to them
+DROP TYPE IF EXISTS webhook_event_type CASCADE;
+CREATE TYPE webhook_event_type AS ENUM ('open', 'click', 'unsubscribe', 'bounce', 'complaint', 'sequence_completed', 'test');
+
+DROP TYPE IF EXISTS webhook_delivery_status CASCADE;
+CREATE TYPE webhook_delivery_status AS ENUM ('pending', 'sending', 'success', 'failed');
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    id           SERIAL PRIMARY KEY,
+    name         TEXT NOT NULL,
+    url          TEXT NOT NULL,
+    -- HMAC-SHA256 key of the X-Webhook-Signature header
+    secret       TEXT NOT NULL,
+    -- Empty for every event type
+    event_types  webhook_event_type[] NOT NULL DEFAULT '{}',
+    enabled      BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at   TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at   TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id                BIGSERIAL PRIMARY KEY,
+    webhook_id        INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event_type        webhook_event_type NOT NULL,
+    payload           JSONB NOT NULL,
+    status            webhook_delivery_status NOT NULL DEFAULT 'pending',
+    attempts          INT NOT NULL DEFAULT 0,
+    available_at      TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    locked_at         TIMESTAMP WITH TIME ZONE NULL,
+    -- HTTP status and (truncated) body of the last attempt, error when no response came back
+    last_status_code  INT NULL,
+    last_response     TEXT NULL,
+    last_error        TEXT NULL,
+    delivered_at      TIMESTAMP WITH TIME ZONE NULL,
+    created_at        TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at        TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+DROP INDEX IF EXISTS idx_webhook_deliveries_claim; CREATE INDEX idx_webhook_deliveries_claim ON webhook_deliveries(status, available_at);
+DROP INDEX IF EXISTS idx_webhook_deliveries_webhook; CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, created_at);
@@ -0,0 +1,3 @@
+-- Layout template the sequence emails of a campaign are wrapped in, at its {{ content }} placeholder
+-- Sequence emails may pick their own (sequence_emails.template_id), the default template is used otherwise
+ALTER TABLE campaigns ADD COLUMN IF NOT EXISTS layout_template_id INTEGER NULL REFERENCES templates(id) ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- Id of the event at the provider (SNS MessageId, SendGrid sg_event_id, Mailgun event id)
+-- Providers retry whole requests, events already recorded are skipped on redelivery
+ALTER TABLE provider_events ADD COLUMN IF NOT EXISTS event_id TEXT NULL;
+DROP INDEX IF EXISTS idx_provider_events_event_id; CREATE UNIQUE INDEX idx_provider_events_event_id ON provider_events(provider, event_id);
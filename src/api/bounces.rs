@@ -0,0 +1,51 @@
+use crate::{
+    models::bounce::BounceFilter,
+    models::delivery::DeliveryPagination,
+    services::bounce_service::BounceService,
+    error::ApiError,
+    monitoring::Metrics,
+    api::reports,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+
+//
+//** Bounce ingestion
+//** POST takes a raw RFC 3464 report (the full MIME message as the request body)
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_bounces_requests_total", "Total number of requests to bounces endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register bounces counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    reports::scope("/bounces")
+        .route("", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<BounceService>, body: web::Bytes| {
+                counter.with_label_values(&["ingest"]).inc();
+                async move {
+                    let bounces = service.ingest(&body).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(bounces))
+                }
+            }
+        }))
+        .route("", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<BounceService>, filter: web::Query<BounceFilter>, pagination: web::Query<DeliveryPagination>| {
+                counter.with_label_values(&["get_bounces"]).inc();
+                async move {
+                    let bounces = service.get_bounces(filter.into_inner(), pagination.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(bounces))
+                }
+            }
+        }))
+}
@@ -1,6 +1,6 @@
 use actix_web::{web, HttpResponse, post, get, delete, put, HttpRequest};
 use crate::{
-    models::campaign::{Campaign, CreateCampaignDto, DeleteCampaignDto, UpdateCampaignDto, CampaignParams, PaginationParams, CampaignFilter},
+    models::campaign::{Campaign, CreateCampaignDto, DeleteCampaignDto, UpdateCampaignDto, CampaignParams, PaginationParams, CampaignFilter, SetCampaignLayoutDto},
     services::campaign_service::CampaignService,
     error::ApiError,
     monitoring::Metrics,
@@ -85,4 +85,26 @@ pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
                 }
             }))
         )
+        .service(web::resource("/{id}/layout")
+            .route(web::get().to({
+                let counter = counter_arc.clone();
+                move |service: web::Data<CampaignService>, id: web::Path<i32>| {
+                    counter.with_label_values(&["get_campaign_layout"]).inc();
+                    async move {
+                        let layout = service.get_layout(id.into_inner()).await?;
+                        Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(layout))
+                    }
+                }
+            }))
+            .route(web::put().to({
+                let counter = counter_arc.clone();
+                move |service: web::Data<CampaignService>, id: web::Path<i32>, dto: web::Json<SetCampaignLayoutDto>| {
+                    counter.with_label_values(&["set_campaign_layout"]).inc();
+                    async move {
+                        let layout = service.set_layout(id.into_inner(), dto.into_inner().template_id).await?;
+                        Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(layout))
+                    }
+                }
+            }))
+        )
 }
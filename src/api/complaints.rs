@@ -0,0 +1,51 @@
+use crate::{
+    models::complaint::ComplaintFilter,
+    models::delivery::DeliveryPagination,
+    services::complaint_service::ComplaintService,
+    error::ApiError,
+    monitoring::Metrics,
+    api::reports,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+
+//
+//** Spam complaint ingestion
+//** POST takes a raw ARF (RFC 5965) report (the full MIME message as the request body)
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_complaints_requests_total", "Total number of requests to complaints endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register complaints counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    reports::scope("/complaints")
+        .route("", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<ComplaintService>, body: web::Bytes| {
+                counter.with_label_values(&["ingest"]).inc();
+                async move {
+                    let complaint = service.ingest(&body).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(complaint))
+                }
+            }
+        }))
+        .route("", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<ComplaintService>, filter: web::Query<ComplaintFilter>, pagination: web::Query<DeliveryPagination>| {
+                counter.with_label_values(&["get_complaints"]).inc();
+                async move {
+                    let complaints = service.get_complaints(filter.into_inner(), pagination.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(complaints))
+                }
+            }
+        }))
+}
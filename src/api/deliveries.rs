@@ -0,0 +1,45 @@
+use crate::{
+    models::delivery::{DeliveryFilter, DeliveryPagination},
+    services::delivery_service::DeliveryService,
+    error::ApiError,
+    monitoring::Metrics,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_deliveries_requests_total", "Total number of requests to deliveries endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register deliveries counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    web::scope("/deliveries")
+        .route("", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<DeliveryService>, filter: web::Query<DeliveryFilter>, pagination: web::Query<DeliveryPagination>| {
+                counter.with_label_values(&["get_deliveries"]).inc();
+                async move {
+                    let deliveries = service.get_deliveries(filter.into_inner(), pagination.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(deliveries))
+                }
+            }
+        }))
+        .route("/{id}", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<DeliveryService>, id: web::Path<i64>| {
+                counter.with_label_values(&["get_delivery"]).inc();
+                async move {
+                    let delivery = service.get_delivery(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(delivery))
+                }
+            }
+        }))
+}
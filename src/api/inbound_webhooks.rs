@@ -0,0 +1,52 @@
+use crate::{
+    email_service::inbound::sendgrid::{SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    services::inbound_webhook_service::InboundWebhookService,
+    error::ApiError,
+    monitoring::Metrics,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use prometheus::IntCounterVec;
+
+// SendGrid posts events in batches of up to a few thousand
+const MAX_EVENTS_SIZE: usize = 10 * 1024 * 1024;
+
+//
+//** Delivery events posted by sending providers: ses (SNS), sendgrid, mailgun
+//** Public, each request is authenticated by its provider signature
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_inbound_webhooks_requests_total", "Total number of provider webhook requests"),
+        &["provider"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register inbound webhooks counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    web::scope("/webhooks/inbound")
+        .app_data(web::PayloadConfig::new(MAX_EVENTS_SIZE))
+        .route("/{provider}", web::post().to({
+            let counter = counter_arc.clone();
+            move |req: HttpRequest, service: web::Data<InboundWebhookService>, provider: web::Path<String>, body: web::Bytes| {
+                let counter = counter.clone();
+                async move {
+                    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok());
+
+                    let recorded = match provider.as_str() {
+                        "ses" | "sns" => service.ingest_sns(&body).await?,
+                        "sendgrid" => service.ingest_sendgrid(header(SIGNATURE_HEADER), header(TIMESTAMP_HEADER), &body).await?,
+                        "mailgun" => service.ingest_mailgun(&body).await?,
+                        _ => return Err(ApiError::NotFound),
+                    };
+                    counter.with_label_values(&[provider.as_str()]).inc();
+
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(serde_json::json!({ "recorded": recorded })))
+                }
+            }
+        }))
+}
@@ -12,6 +12,16 @@ pub mod users;
 pub mod auth;
 pub mod tasks;
 pub mod subscriber_sequence;
+pub mod deliveries;
+pub mod unsubscribe;
+pub mod preferences;
+pub mod optin;
+pub mod bounces;
+pub mod complaints;
+pub mod reports;
+pub mod suppressions;
+pub mod inbound_webhooks;
+pub mod webhooks;
 use actix_web::web;
 use std::sync::Arc;
 use crate::monitoring::Metrics;
@@ -32,6 +42,15 @@ pub fn config(cfg: &mut web::ServiceConfig, metrics: Arc<Metrics>) {
     cfg.service(tasks::config(metrics.clone()));
     cfg.configure(send_email::configure(metrics.clone()));
     cfg.service(subscriber_sequence::config(metrics.clone()));
+    cfg.service(deliveries::config(metrics.clone()));
+    cfg.service(unsubscribe::config(metrics.clone()));
+    cfg.service(preferences::config(metrics.clone()));
+    cfg.service(optin::config(metrics.clone()));
+    cfg.service(bounces::config(metrics.clone()));
+    cfg.service(complaints::config(metrics.clone()));
+    cfg.service(suppressions::config(metrics.clone()));
+    cfg.service(inbound_webhooks::config(metrics.clone()));
+    cfg.service(webhooks::config(metrics.clone()));
 }
 
 
@@ -0,0 +1,74 @@
+use crate::{
+    services::double_optin_service::DoubleOptinService,
+    error::ApiError,
+    monitoring::Metrics,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+
+const CONFIRMED_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width"><title>Subscription confirmed</title></head>
+<body style="font-family:sans-serif;text-align:center;padding:40px">
+<p>Your subscription is confirmed, thank you.</p>
+</body>
+</html>"#;
+
+const INVALID_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width"><title>Invalid link</title></head>
+<body style="font-family:sans-serif;text-align:center;padding:40px">
+<p>This confirmation link is invalid or has expired. Please subscribe again to get a new one.</p>
+</body>
+</html>"#;
+
+//
+//** Public double opt-in confirmation, authenticated by the signed token only
+//** GET is the link followed from the confirmation email, POST the JSON variant
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_optin_requests_total", "Total number of requests to opt-in confirmation endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register opt-in counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    web::scope("/optin")
+        .route("/{token}", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<DoubleOptinService>, token: web::Path<String>| {
+                counter.with_label_values(&["confirm_page"]).inc();
+                async move {
+                    match service.confirm(&token.into_inner()).await {
+                        Ok(_) => Ok::<HttpResponse, ApiError>(
+                            HttpResponse::Ok().content_type("text/html; charset=utf-8").body(CONFIRMED_PAGE)
+                        ),
+                        Err(ApiError::BadRequest(_)) => Ok(
+                            HttpResponse::BadRequest().content_type("text/html; charset=utf-8").body(INVALID_PAGE)
+                        ),
+                        Err(e) => Err(e),
+                    }
+                }
+            }
+        }))
+        .route("/{token}", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<DoubleOptinService>, token: web::Path<String>| {
+                counter.with_label_values(&["confirm"]).inc();
+                async move {
+                    let sequences = service.confirm(&token.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(serde_json::json!({
+                        "confirmed": true,
+                        "sequences": sequences,
+                    })))
+                }
+            }
+        }))
+}
@@ -0,0 +1,242 @@
+use crate::{
+    services::preference_service::PreferenceService,
+    models::preference::{SubscriberPreferences, UpdatePreferencesDto},
+    error::ApiError,
+    monitoring::Metrics,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+use sqlx::types::JsonValue;
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn attrib_value(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(value) => value.clone(),
+        JsonValue::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn render_page(preferences: &SubscriberPreferences, editable_attribs: &[String], page_url: &str, notice: Option<&str>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(page_html(preferences, editable_attribs, page_url, notice))
+}
+
+//
+//** Server-rendered preference center
+//** Form actions are absolute, the page is also rendered under /page/unsubscribe
+//** Params : preferences , editable_attribs , page_url (signed /preferences/{token}/page URL) , notice
+//
+fn page_html(preferences: &SubscriberPreferences, editable_attribs: &[String], page_url: &str, notice: Option<&str>) -> String {
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width"><title>Email preferences</title></head>
+<body style="font-family:sans-serif;max-width:560px;margin:0 auto;padding:40px 20px">
+<h1>Email preferences</h1>
+"#,
+    );
+
+    if let Some(notice) = notice {
+        html.push_str(&format!("<p><strong>{}</strong></p>\n", escape_html(notice)));
+    }
+    html.push_str(&format!(
+        "<p>{}</p>\n<form method=\"post\" action=\"{}\">\n",
+        escape_html(&preferences.email),
+        escape_html(page_url)
+    ));
+    html.push_str(&format!(
+        "<p><label>Name<br><input type=\"text\" name=\"name\" value=\"{}\"></label></p>\n",
+        escape_html(preferences.name.as_deref().unwrap_or_default())
+    ));
+
+    for key in editable_attribs {
+        let value = preferences.attribs.get(key).map(attrib_value).unwrap_or_default();
+        html.push_str(&format!(
+            "<p><label>{}<br><input type=\"text\" name=\"attrib_{}\" value=\"{}\"></label></p>\n",
+            escape_html(key), escape_html(key), escape_html(&value)
+        ));
+    }
+
+    if !preferences.lists.is_empty() {
+        html.push_str("<h2>Lists</h2>\n");
+    }
+    for list in &preferences.lists {
+        html.push_str(&format!(
+            "<p><label><input type=\"checkbox\" name=\"list_{}\"{}> {}</label><br><small>{}</small></p>\n",
+            list.list_id,
+            if list.subscribed() { " checked" } else { "" },
+            escape_html(&list.name),
+            escape_html(&list.description)
+        ));
+    }
+
+    html.push_str(&format!(
+        r#"<p><button type="submit">Save</button></p>
+</form>
+<form method="post" action="{}/unsubscribe"><p><button type="submit">Unsubscribe from all emails</button></p></form>
+</body>
+</html>"#,
+        escape_html(page_url)
+    ));
+
+    html
+}
+
+/// Turn the HTML form fields (name, attrib_<key>, list_<id>) into an update
+fn form_update(form: HashMap<String, String>) -> UpdatePreferencesDto {
+    let mut attribs = serde_json::Map::new();
+    let mut list_ids = Vec::new();
+
+    for (field, value) in &form {
+        if let Some(key) = field.strip_prefix("attrib_") {
+            attribs.insert(key.to_string(), JsonValue::String(value.clone()));
+        } else if let Some(id) = field.strip_prefix("list_").and_then(|id| id.parse().ok()) {
+            // Unchecked boxes are not submitted
+            list_ids.push(id);
+        }
+    }
+
+    UpdatePreferencesDto {
+        name: form.get("name").cloned(),
+        attribs: Some(attribs),
+        list_ids: Some(list_ids),
+    }
+}
+
+//
+//** Public preference center, authenticated by the signed token only
+//** JSON under /preferences/{token}, HTML under /preferences/{token}/page
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_preferences_requests_total", "Total number of requests to preference center endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register preferences counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    web::scope("/preferences")
+        .route("/{token}", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<PreferenceService>, token: web::Path<String>| {
+                counter.with_label_values(&["get"]).inc();
+                async move {
+                    let preferences = service.get(&token.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(preferences))
+                }
+            }
+        }))
+        .route("/{token}", web::put().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<PreferenceService>, token: web::Path<String>, update: web::Json<UpdatePreferencesDto>| {
+                counter.with_label_values(&["update"]).inc();
+                async move {
+                    let preferences = service.update(&token.into_inner(), update.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(preferences))
+                }
+            }
+        }))
+        .route("/{token}/unsubscribe", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<PreferenceService>, token: web::Path<String>| {
+                counter.with_label_values(&["unsubscribe_all"]).inc();
+                async move {
+                    let lists = service.unsubscribe_all(&token.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(serde_json::json!({
+                        "unsubscribed": true,
+                        "list_ids": lists,
+                    })))
+                }
+            }
+        }))
+        .route("/{token}/page", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<PreferenceService>, token: web::Path<String>| {
+                counter.with_label_values(&["page"]).inc();
+                async move {
+                    let token = token.into_inner();
+                    let preferences = service.get(&token).await?;
+                    Ok::<HttpResponse, ApiError>(render_page(
+                        &preferences,
+                        service.editable_attribs(),
+                        &service.page_url(&token)?,
+                        None,
+                    ))
+                }
+            }
+        }))
+        .route("/{token}/page", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<PreferenceService>, token: web::Path<String>, form: web::Form<HashMap<String, String>>| {
+                counter.with_label_values(&["page_update"]).inc();
+                async move {
+                    let token = token.into_inner();
+                    let preferences = service.update(&token, form_update(form.into_inner())).await?;
+                    Ok::<HttpResponse, ApiError>(render_page(
+                        &preferences,
+                        service.editable_attribs(),
+                        &service.page_url(&token)?,
+                        Some("Your preferences have been saved."),
+                    ))
+                }
+            }
+        }))
+        .route("/{token}/page/unsubscribe", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<PreferenceService>, token: web::Path<String>| {
+                counter.with_label_values(&["page_unsubscribe_all"]).inc();
+                async move {
+                    let token = token.into_inner();
+                    service.unsubscribe_all(&token).await?;
+                    let preferences = service.get(&token).await?;
+                    Ok::<HttpResponse, ApiError>(render_page(
+                        &preferences,
+                        service.editable_attribs(),
+                        &service.page_url(&token)?,
+                        Some("You have been unsubscribed from all emails."),
+                    ))
+                }
+            }
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email_service::unsubscribe::UnsubscribeLinks;
+
+    #[test]
+    fn page_posts_to_absolute_urls_after_an_unsubscribe() {
+        let links = UnsubscribeLinks::new("https://example.com/unsubscribe", "https://example.com/preferences", "secret");
+        let page_url = links.preferences_url(7);
+        let preferences = SubscriberPreferences {
+            email: "ada@example.com".to_string(),
+            name: None,
+            attribs: serde_json::Map::new(),
+            lists: Vec::new(),
+        };
+
+        let html = page_html(&preferences, &[], &page_url, Some("You have been unsubscribed from all emails."));
+
+        assert!(page_url.starts_with("https://example.com/preferences/") && page_url.ends_with("/page"));
+        assert!(html.contains(&format!("<form method=\"post\" action=\"{}\">", page_url)));
+        assert!(html.contains(&format!("<form method=\"post\" action=\"{}/unsubscribe\">", page_url)));
+    }
+}
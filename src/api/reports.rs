@@ -0,0 +1,13 @@
+use actix_web::web;
+
+// Reports may quote the whole original message
+const MAX_REPORT_SIZE: usize = 10 * 1024 * 1024;
+
+//
+//** Scope of an endpoint ingesting raw MIME reports (RFC 3464 bounces, ARF complaints)
+//** The full message is posted as the request body and read with the web::Bytes extractor,
+//** allowed up to MAX_REPORT_SIZE instead of the default payload limit
+//
+pub fn scope(path: &str) -> actix_web::Scope {
+    web::scope(path).app_data(web::PayloadConfig::new(MAX_REPORT_SIZE))
+}
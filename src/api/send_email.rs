@@ -1,8 +1,9 @@
 use actix_web::{web, HttpResponse, HttpRequest};
 use crate::email_service::{
     EmailService,
-    models::{EmailRequest, BulkEmailRequest, EmailResponse, ListEmailRequest, BulkEmailStats, CampaignEmailRequest},
+    models::{EmailRequest, BulkEmailRequest, EmailResponse, ListEmailRequest, CampaignEmailRequest},
 };
+use crate::repositories::email_outbox_repository::EmailOutboxRepository;
 use sqlx::PgPool;
 use chrono::Utc;
 use prometheus::IntCounterVec;
@@ -71,7 +72,8 @@ pub fn config(cfg: &mut web::ServiceConfig, counter: Arc<IntCounterVec>) {
                 move |_req: HttpRequest, email_service: web::Data<EmailService>, pool: web::Data<PgPool>, request: web::Json<ListEmailRequest>| {
                     counter.with_label_values(&["send_to_lists"]).inc();
                     async move {
-                        match email_service.send_emails_to_lists(
+                        // Messages are sent by the outbox workers, follow them with /email/jobs/{id}
+                        match email_service.queue_emails_to_lists(
                             &pool,
                             &request.list_ids,
                             &request.subject,
@@ -79,9 +81,9 @@ pub fn config(cfg: &mut web::ServiceConfig, counter: Arc<IntCounterVec>) {
                             request.campaign_id,
                             request.sequence_email_id,
                         ).await {
-                            Ok(stats) => HttpResponse::Ok().json(stats),
+                            Ok(job) => HttpResponse::Accepted().json(job),
                             Err(e) => HttpResponse::InternalServerError().json(EmailResponse {
-                                message: format!("Failed to send emails: {}", e),
+                                message: format!("Failed to queue emails: {}", e),
                                 success: false,
                             }),
                         }
@@ -119,16 +121,16 @@ pub fn config(cfg: &mut web::ServiceConfig, counter: Arc<IntCounterVec>) {
                                 }),
                             }
                         } else {
-                            // Send immediately
-                            match email_service.send_campaign_emails(
+                            // Queue for immediate sending
+                            match email_service.queue_campaign_emails(
                                 &pool,
                                 request.campaign_id,
                                 &request.list_ids,
                                 request.template_id,
                             ).await {
-                                Ok(stats) => HttpResponse::Ok().json(stats),
+                                Ok(job) => HttpResponse::Accepted().json(job),
                                 Err(e) => HttpResponse::InternalServerError().json(EmailResponse {
-                                    message: format!("Failed to send campaign emails: {}", e),
+                                    message: format!("Failed to queue campaign emails: {}", e),
                                     success: false,
                                 }),
                             }
@@ -136,6 +138,25 @@ pub fn config(cfg: &mut web::ServiceConfig, counter: Arc<IntCounterVec>) {
                     }
                 }
             }))
+            .route("/jobs/{id}", web::get().to({
+                let counter = counter.clone();
+                move |pool: web::Data<PgPool>, id: web::Path<i32>| {
+                    counter.with_label_values(&["get_job"]).inc();
+                    async move {
+                        match EmailOutboxRepository::new(pool.get_ref().clone()).job_progress(id.into_inner()).await {
+                            Ok(Some(progress)) => HttpResponse::Ok().json(progress),
+                            Ok(None) => HttpResponse::NotFound().json(EmailResponse {
+                                message: "Job not found".to_string(),
+                                success: false,
+                            }),
+                            Err(e) => HttpResponse::InternalServerError().json(EmailResponse {
+                                message: format!("Failed to get job: {}", e),
+                                success: false,
+                            }),
+                        }
+                    }
+                }
+            }))
     );
 }
 
@@ -1,7 +1,9 @@
 use actix_web::{web, HttpResponse, post, get, delete, put, HttpRequest};
 use crate::{
     models::sequence_email::{SequenceEmail, CreateSequenceEmailDto, UpdateSequenceEmailDto, PaginationDto},
+    models::preview::{PreviewDto, TestSendDto},
     services::sequence_email_service::SequenceEmailService,
+    services::preview_service::PreviewService,
     error::ApiError,
     monitoring::Metrics,
 };
@@ -92,4 +94,24 @@ pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
                 }
             }
         }))
+        .route("/{id}/preview", web::post().to({
+            let counter = counter_arc.clone();
+            move |_req: HttpRequest, service: web::Data<PreviewService>, path: web::Path<i32>, dto: web::Json<PreviewDto>| {
+                counter.with_label_values(&["preview_sequence_email"]).inc();
+                async move {
+                    let preview = service.preview_sequence_email(path.into_inner(), &dto).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(preview))
+                }
+            }
+        }))
+        .route("/{id}/test-send", web::post().to({
+            let counter = counter_arc.clone();
+            move |_req: HttpRequest, service: web::Data<PreviewService>, path: web::Path<i32>, dto: web::Json<TestSendDto>| {
+                counter.with_label_values(&["test_send_sequence_email"]).inc();
+                async move {
+                    let response = service.test_send_sequence_email(path.into_inner(), &dto).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(response))
+                }
+            }
+        }))
 }
\ No newline at end of file
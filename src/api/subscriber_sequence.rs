@@ -1,10 +1,9 @@
 use actix_web::{web, HttpResponse, HttpRequest};
 use crate::error::ApiError;
-use crate::services::sequence_optin_service::SequenceOptinService;
-use crate::services::subscriber_list_service::SubscriberListService;
+use crate::services::double_optin_service::DoubleOptinService;
 use crate::services::subscriber_service::SubscriberService;
-use crate::models::subscriber_list::{CreateSubscriberListDto, SubscriptionStatus};
-use serde_json::Value as JsonValue;
+use crate::models::subscriber_list::SubscriptionStatus;
+use crate::models::subscription_event::SubscriptionEventSource;
 use std::sync::Arc;
 use prometheus::IntCounterVec;
 use crate::monitoring::Metrics;
@@ -27,9 +26,8 @@ pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
             let counter = counter.clone();
             move |_req: HttpRequest, 
                   path: web::Path<(String, i32)>, 
-                  sequence_service: web::Data<SequenceOptinService>,
-                  subscriber_service: web::Data<SubscriberService>,
-                  subscriber_list_service: web::Data<SubscriberListService>| {
+                  optin_service: web::Data<DoubleOptinService>,
+                  subscriber_service: web::Data<SubscriberService>| {
                 counter.with_label_values(&["add_subscriber_to_list_with_sequence"]).inc();
                 async move {
                     let (subscriber_email, list_id) = path.into_inner();
@@ -52,23 +50,13 @@ pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
                         Err(e) => return Err(e)
                     };
                     
-                    // Add subscriber to list
-                    let adding_to_list = subscriber_list_service.create_subscriber_list(CreateSubscriberListDto {
-                        subscriber_id: subscriber.id,
-                        list_id: list_id,
-                        meta: JsonValue::Null,
-                        status: SubscriptionStatus::Confirmed,
-                    }).await;
-                    
-                    if let Err(e) = adding_to_list {
-                        tracing::error!("Failed to add subscriber to list: {}", e);
-                        return Err(ApiError::BadRequest("Failed to add subscriber to list".to_string()));
-                    }
-                    
-                    // Initialize the sequences opt-in
-                    match sequence_service.initialize_sequences_for_subscriber(subscriber.id, list_id).await {
-                        Ok(progress) => Ok::<HttpResponse, ApiError>(HttpResponse::Created().json(progress)),
-                        Err(e) => Err(e)
+                    // Add subscriber to list, sequences start once the subscription is confirmed
+                    let outcome = optin_service.subscribe(&subscriber, list_id, SubscriptionEventSource::Api).await?;
+
+                    match outcome.status {
+                        // Double opt-in, waiting for the confirmation link to be followed
+                        SubscriptionStatus::Unconfirmed => Ok::<HttpResponse, ApiError>(HttpResponse::Accepted().json(outcome)),
+                        _ => Ok::<HttpResponse, ApiError>(HttpResponse::Created().json(outcome.sequences)),
                     }
                 }
             }
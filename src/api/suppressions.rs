@@ -0,0 +1,96 @@
+use crate::{
+    models::suppression::{CreateSuppressionDto, ImportSuppressionsDto, SuppressionFilter, UpdateSuppressionDto},
+    models::delivery::DeliveryPagination,
+    services::suppression_service::SuppressionService,
+    error::ApiError,
+    monitoring::Metrics,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+
+// Bulk imports can hold a few hundred thousand addresses
+const MAX_IMPORT_SIZE: usize = 16 * 1024 * 1024;
+
+//
+//** Global suppression list, addresses and domains no message is ever sent to
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_suppressions_requests_total", "Total number of requests to suppressions endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register suppressions counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    web::scope("/suppressions")
+        .route("", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<SuppressionService>, filter: web::Query<SuppressionFilter>, pagination: web::Query<DeliveryPagination>| {
+                counter.with_label_values(&["get_suppressions"]).inc();
+                async move {
+                    let suppressions = service.get_suppressions(filter.into_inner(), pagination.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(suppressions))
+                }
+            }
+        }))
+        .route("", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<SuppressionService>, dto: web::Json<CreateSuppressionDto>| {
+                counter.with_label_values(&["create_suppression"]).inc();
+                async move {
+                    let suppression = service.create_suppression(dto.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Created().json(suppression))
+                }
+            }
+        }))
+        .service(
+            web::resource("/import")
+                .app_data(web::JsonConfig::default().limit(MAX_IMPORT_SIZE))
+                .route(web::post().to({
+                    let counter = counter_arc.clone();
+                    move |service: web::Data<SuppressionService>, dto: web::Json<ImportSuppressionsDto>| {
+                        counter.with_label_values(&["import_suppressions"]).inc();
+                        async move {
+                            let result = service.import_suppressions(dto.into_inner()).await?;
+                            Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(result))
+                        }
+                    }
+                }))
+        )
+        .route("/{id}", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<SuppressionService>, id: web::Path<i64>| {
+                counter.with_label_values(&["get_suppression"]).inc();
+                async move {
+                    let suppression = service.get_suppression(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(suppression))
+                }
+            }
+        }))
+        .route("/{id}", web::put().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<SuppressionService>, id: web::Path<i64>, dto: web::Json<UpdateSuppressionDto>| {
+                counter.with_label_values(&["update_suppression"]).inc();
+                async move {
+                    let suppression = service.update_suppression(id.into_inner(), dto.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(suppression))
+                }
+            }
+        }))
+        .route("/{id}", web::delete().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<SuppressionService>, id: web::Path<i64>| {
+                counter.with_label_values(&["delete_suppression"]).inc();
+                async move {
+                    let suppression = service.delete_suppression(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(suppression))
+                }
+            }
+        }))
+}
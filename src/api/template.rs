@@ -1,7 +1,9 @@
 use actix_web::{web, HttpResponse, HttpRequest};
 use crate::{
     models::template::{CreateTemplateDto, UpdateTemplateDto, PaginationDto},
+    models::preview::{PreviewDto, TestSendDto},
     services::template_service::TemplateService,
+    services::preview_service::PreviewService,
     error::ApiError,
     monitoring::Metrics,
 };
@@ -83,4 +85,24 @@ pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
                 }
             }
         }))
+        .route("/{id}/preview", web::post().to({
+            let counter = counter.clone();
+            move |_req: HttpRequest, service: web::Data<PreviewService>, path: web::Path<i32>, dto: web::Json<PreviewDto>| {
+                counter.with_label_values(&["preview_template"]).inc();
+                async move {
+                    let preview = service.preview_template(path.into_inner(), &dto).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(preview))
+                }
+            }
+        }))
+        .route("/{id}/test-send", web::post().to({
+            let counter = counter.clone();
+            move |_req: HttpRequest, service: web::Data<PreviewService>, path: web::Path<i32>, dto: web::Json<TestSendDto>| {
+                counter.with_label_values(&["test_send_template"]).inc();
+                async move {
+                    let response = service.test_send_template(path.into_inner(), &dto).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(response))
+                }
+            }
+        }))
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use crate::{
+    services::subscription_service::SubscriptionService,
+    error::ApiError,
+    monitoring::Metrics,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+
+// Shown when the List-Unsubscribe link is opened in a browser, unsubscribing only takes the POST
+const CONFIRM_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width"><title>Unsubscribe</title></head>
+<body style="font-family:sans-serif;text-align:center;padding:40px">
+<form method="post"><p>Stop receiving these emails?</p><button type="submit">Unsubscribe</button></form>
+</body>
+</html>"#;
+
+//
+//** Public one-click unsubscribe (RFC 8058), authenticated by the signed token only
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_unsubscribe_requests_total", "Total number of requests to unsubscribe endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register unsubscribe counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    web::scope("/unsubscribe")
+        .route("/{token}", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<SubscriptionService>, token: web::Path<String>| {
+                counter.with_label_values(&["unsubscribe"]).inc();
+                async move {
+                    let lists = service.unsubscribe(&token.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(serde_json::json!({
+                        "unsubscribed": true,
+                        "list_ids": lists,
+                    })))
+                }
+            }
+        }))
+        .route("/{token}", web::get().to({
+            let counter = counter_arc.clone();
+            move || {
+                counter.with_label_values(&["unsubscribe_page"]).inc();
+                async move {
+                    Ok::<HttpResponse, ApiError>(
+                        HttpResponse::Ok().content_type("text/html; charset=utf-8").body(CONFIRM_PAGE)
+                    )
+                }
+            }
+        }))
+}
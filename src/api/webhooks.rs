@@ -0,0 +1,120 @@
+use crate::{
+    models::webhook::{CreateWebhookDto, UpdateWebhookDto, WebhookDeliveryFilter},
+    models::delivery::DeliveryPagination,
+    services::webhook_service::WebhookService,
+    error::ApiError,
+    monitoring::Metrics,
+};
+use std::sync::Arc;
+use actix_web::{web, HttpResponse};
+use prometheus::IntCounterVec;
+
+//
+//** Outbound webhooks, endpoints notified of subscriber events, and their delivery log
+//** Registered after the inbound provider webhooks, whose scope would otherwise be shadowed
+//
+pub fn config(metrics: Arc<Metrics>) -> actix_web::Scope {
+    let counter = IntCounterVec::new(
+        prometheus::opts!("api_webhooks_requests_total", "Total number of requests to webhooks endpoints"),
+        &["endpoint"]
+    ).unwrap();
+
+    // Register the counter with the metrics registry, handling potential errors
+    if let Err(e) = metrics.registry.register(Box::new(counter.clone())) {
+        tracing::warn!("Failed to register webhooks counter: {}", e);
+    }
+
+    let counter_arc = Arc::new(counter);
+
+    web::scope("/webhooks")
+        .route("", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>| {
+                counter.with_label_values(&["get_webhooks"]).inc();
+                async move {
+                    let webhooks = service.get_webhooks().await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(webhooks))
+                }
+            }
+        }))
+        .route("", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, dto: web::Json<CreateWebhookDto>| {
+                counter.with_label_values(&["create_webhook"]).inc();
+                async move {
+                    let webhook = service.create_webhook(dto.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Created().json(webhook))
+                }
+            }
+        }))
+        .route("/deliveries", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, filter: web::Query<WebhookDeliveryFilter>, pagination: web::Query<DeliveryPagination>| {
+                counter.with_label_values(&["get_deliveries"]).inc();
+                async move {
+                    let deliveries = service.get_deliveries(filter.into_inner(), pagination.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(deliveries))
+                }
+            }
+        }))
+        .route("/deliveries/{id}", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, id: web::Path<i64>| {
+                counter.with_label_values(&["get_delivery"]).inc();
+                async move {
+                    let delivery = service.get_delivery(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(delivery))
+                }
+            }
+        }))
+        .route("/deliveries/{id}/redeliver", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, id: web::Path<i64>| {
+                counter.with_label_values(&["redeliver"]).inc();
+                async move {
+                    let delivery = service.redeliver(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Accepted().json(delivery))
+                }
+            }
+        }))
+        .route("/{id}", web::get().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, id: web::Path<i32>| {
+                counter.with_label_values(&["get_webhook"]).inc();
+                async move {
+                    let webhook = service.get_webhook(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(webhook))
+                }
+            }
+        }))
+        .route("/{id}", web::put().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, id: web::Path<i32>, dto: web::Json<UpdateWebhookDto>| {
+                counter.with_label_values(&["update_webhook"]).inc();
+                async move {
+                    let webhook = service.update_webhook(id.into_inner(), dto.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(webhook))
+                }
+            }
+        }))
+        .route("/{id}", web::delete().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, id: web::Path<i32>| {
+                counter.with_label_values(&["delete_webhook"]).inc();
+                async move {
+                    let webhook = service.delete_webhook(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Ok().json(webhook))
+                }
+            }
+        }))
+        .route("/{id}/test", web::post().to({
+            let counter = counter_arc.clone();
+            move |service: web::Data<WebhookService>, id: web::Path<i32>| {
+                counter.with_label_values(&["test_webhook"]).inc();
+                async move {
+                    service.test_webhook(id.into_inner()).await?;
+                    Ok::<HttpResponse, ApiError>(HttpResponse::Accepted().json(serde_json::json!({ "queued": true })))
+                }
+            }
+        }))
+}
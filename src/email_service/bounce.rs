@@ -0,0 +1,262 @@
+use mailparse::{MailHeader, MailHeaderMap, ParsedMail};
+use crate::email_service::error::EmailError;
+use crate::models::bounce::BounceType;
+
+//
+//** VERP return paths, <prefix>+<outbox id>@<domain>
+//** The bounce of a message comes back to its own address, so it can be matched
+//** to its delivery even when the DSN does not quote the original Message-ID
+//
+#[derive(Debug, Clone)]
+pub struct Verp {
+    prefix: String,
+    domain: String,
+}
+
+impl Verp {
+    pub fn new(prefix: impl Into<String>, domain: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into().to_lowercase(),
+            domain: domain.into().to_lowercase(),
+        }
+    }
+
+    pub fn address(&self, outbox_id: i64) -> String {
+        format!("{}+{}@{}", self.prefix, outbox_id, self.domain)
+    }
+
+    /// Outbox id encoded in a VERP address, None for any other address
+    pub fn outbox_id(&self, address: &str) -> Option<i64> {
+        let address = address.trim().trim_start_matches('<').trim_end_matches('>').to_lowercase();
+        let (local, domain) = address.rsplit_once('@')?;
+        if domain != self.domain {
+            return None;
+        }
+        local.strip_prefix(&self.prefix)?.strip_prefix('+')?.parse().ok()
+    }
+}
+
+/// One recipient of a delivery status notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsnRecipient {
+    pub email: String,
+    pub bounce_type: BounceType,
+    // RFC 3463 enhanced status code, e.g. 5.1.1
+    pub status: Option<String>,
+    pub diagnostic: Option<String>,
+}
+
+/// Bounced recipients of an RFC 3464 report, with what identifies the original message
+#[derive(Debug, Clone, Default)]
+pub struct DsnReport {
+    pub message_id: Option<String>,
+    pub outbox_id: Option<i64>,
+    pub recipients: Vec<DsnRecipient>,
+}
+
+// Permanent failures that usually clear up on their own
+const SOFT_PERMANENT_STATUSES: &[&str] = &["5.2.2", "5.4.7"];
+
+//
+//** Classify a DSN recipient from its Action and Status fields
+//** Return : None for recipients that were delivered, relayed or expanded, and for delayed ones
+//** (the MTA is still retrying, the message may yet be delivered)
+//
+fn classify(action: &str, status: Option<&str>) -> Option<BounceType> {
+    match action.to_lowercase().as_str() {
+        "failed" => match status {
+            Some(status) if status.starts_with('4') || SOFT_PERMANENT_STATUSES.contains(&status) => Some(BounceType::Soft),
+            _ => Some(BounceType::Hard),
+        },
+        _ => None,
+    }
+}
+
+/// Strip the "rfc822;" type prefix of address and diagnostic fields
+pub(crate) fn typed_value(value: &str) -> String {
+    match value.split_once(';') {
+        Some((_, value)) => value.trim().to_string(),
+        None => value.trim().to_string(),
+    }
+}
+
+/// Field groups of a message/delivery-status body, the per-message one first
+pub(crate) fn field_groups(body: &[u8]) -> Result<Vec<Vec<MailHeader<'_>>>, EmailError> {
+    let mut groups = Vec::new();
+    let mut rest = body;
+
+    loop {
+        while let Some(stripped) = rest.strip_prefix(b"\r\n").or_else(|| rest.strip_prefix(b"\n")) {
+            rest = stripped;
+        }
+        if rest.is_empty() {
+            break;
+        }
+
+        let (headers, consumed) = mailparse::parse_headers(rest)
+            .map_err(|e| EmailError::MessageError(format!("Invalid delivery status: {}", e)))?;
+        if headers.is_empty() || consumed == 0 {
+            break;
+        }
+        groups.push(headers);
+        rest = &rest[consumed.min(rest.len())..];
+    }
+
+    Ok(groups)
+}
+
+pub(crate) fn find_part<'a>(part: &'a ParsedMail<'a>, mimetypes: &[&str]) -> Option<&'a ParsedMail<'a>> {
+    if mimetypes.contains(&part.ctype.mimetype.to_lowercase().as_str()) {
+        return Some(part);
+    }
+    part.subparts.iter().find_map(|sub| find_part(sub, mimetypes))
+}
+
+//
+//** Parse a raw RFC 3464 delivery status notification
+//** Params : raw (full MIME message) , verp (to find the outbox id in the addresses the report was sent to)
+//** Return : DsnReport, or an error when the message is not a delivery status report
+//
+pub fn parse_dsn(raw: &[u8], verp: Option<&Verp>) -> Result<DsnReport, EmailError> {
+    let mail = mailparse::parse_mail(raw)
+        .map_err(|e| EmailError::MessageError(format!("Invalid MIME message: {}", e)))?;
+
+    let status = find_part(&mail, &["message/delivery-status", "message/global-delivery-status"])
+        .ok_or_else(|| EmailError::MessageError("Not a delivery status notification".to_string()))?;
+    let body = status.get_body_raw()
+        .map_err(|e| EmailError::MessageError(format!("Invalid delivery status: {}", e)))?;
+
+    let mut report = DsnReport::default();
+
+    // The first group holds per-message fields, every other one describes a recipient
+    for fields in field_groups(&body)?.iter().skip(1) {
+        let Some(email) = fields.get_first_value("Final-Recipient")
+            .or_else(|| fields.get_first_value("Original-Recipient"))
+            .map(|value| typed_value(&value).to_lowercase())
+        else {
+            continue;
+        };
+
+        let action = fields.get_first_value("Action").unwrap_or_default();
+        let status = fields.get_first_value("Status")
+            .and_then(|value| value.split_whitespace().next().map(str::to_string));
+
+        if let Some(bounce_type) = classify(action.trim(), status.as_deref()) {
+            report.recipients.push(DsnRecipient {
+                email,
+                bounce_type,
+                status,
+                diagnostic: fields.get_first_value("Diagnostic-Code").map(|value| typed_value(&value)),
+            });
+        }
+    }
+
+    let original = OriginalMessage::find(&mail, verp);
+    report.message_id = original.message_id;
+    report.outbox_id = original.outbox_id;
+
+    Ok(report)
+}
+
+/// What identifies the message a report is about
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OriginalMessage {
+    pub message_id: Option<String>,
+    pub outbox_id: Option<i64>,
+    pub to: Option<String>,
+}
+
+impl OriginalMessage {
+    //
+    //** Read the original message quoted in a report (whole or headers only)
+    //** The outbox id comes from its VERP Return-Path, or from the VERP address the report was sent to
+    //
+    pub(crate) fn find(mail: &ParsedMail<'_>, verp: Option<&Verp>) -> Self {
+        let mut original = Self::default();
+
+        let quoted = find_part(mail, &["message/rfc822", "text/rfc822-headers", "message/global"])
+            .and_then(|part| part.get_body_raw().ok());
+        if let Some(Ok((headers, _))) = quoted.as_deref().map(mailparse::parse_headers) {
+            original.message_id = headers.get_first_value("Message-ID")
+                .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+                .filter(|id| !id.is_empty());
+            original.to = headers.get_first_value("To");
+
+            if let Some(verp) = verp {
+                original.outbox_id = headers.get_first_value("Return-Path")
+                    .and_then(|address| verp.outbox_id(&address));
+            }
+        }
+
+        if let (None, Some(verp)) = (original.outbox_id, verp) {
+            original.outbox_id = ["X-Original-To", "Delivered-To", "Envelope-To", "To"].iter()
+                .flat_map(|name| mail.headers.get_all_values(name))
+                .find_map(|address| verp.outbox_id(&address));
+        }
+
+        original
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DSN: &str = "From: MAILER-DAEMON@mx.example.org\r\n\
+To: bounces+42@bounce.example.com\r\n\
+Subject: Undelivered Mail Returned to Sender\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/report; report-type=delivery-status; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Your message could not be delivered.\r\n\
+--b1\r\n\
+Content-Type: message/delivery-status\r\n\
+\r\n\
+Reporting-MTA: dns; mx.example.org\r\n\
+\r\n\
+Final-Recipient: rfc822; gone@example.org\r\n\
+Action: failed\r\n\
+Status: 5.1.1\r\n\
+Diagnostic-Code: smtp; 550 5.1.1 User unknown\r\n\
+\r\n\
+Final-Recipient: rfc822; full@example.org\r\n\
+Action: failed\r\n\
+Status: 5.2.2\r\n\
+\r\n\
+Final-Recipient: rfc822; ok@example.org\r\n\
+Action: delivered\r\n\
+Status: 2.0.0\r\n\
+\r\n\
+Final-Recipient: rfc822; slow@example.org\r\n\
+Action: delayed\r\n\
+Status: 4.4.7\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/rfc822-headers\r\n\
+\r\n\
+Message-ID: <abc@news.example.com>\r\n\
+Subject: Hello\r\n\
+\r\n\
+--b1--\r\n";
+
+    #[test]
+    fn parses_and_classifies_recipients() {
+        let verp = Verp::new("bounces", "Bounce.example.com");
+        let report = parse_dsn(DSN.as_bytes(), Some(&verp)).unwrap();
+
+        assert_eq!(report.message_id.as_deref(), Some("abc@news.example.com"));
+        assert_eq!(report.outbox_id, Some(42));
+        assert_eq!(report.recipients.len(), 2);
+        assert_eq!(report.recipients[0].email, "gone@example.org");
+        assert_eq!(report.recipients[0].bounce_type, BounceType::Hard);
+        assert_eq!(report.recipients[0].diagnostic.as_deref(), Some("550 5.1.1 User unknown"));
+        assert_eq!(report.recipients[1].bounce_type, BounceType::Soft);
+
+        assert!(parse_dsn(b"Subject: hi\r\n\r\nhello", None).is_err());
+        assert_eq!(verp.outbox_id(&verp.address(7)), Some(7));
+        assert_eq!(verp.outbox_id("bounces+7@other.com"), None);
+    }
+}
@@ -0,0 +1,104 @@
+use mailparse::MailHeaderMap;
+use crate::email_service::bounce::{field_groups, find_part, OriginalMessage, Verp};
+use crate::email_service::error::EmailError;
+
+/// A spam complaint from an RFC 5965 feedback report
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackReport {
+    // abuse, fraud, virus, other... only abuse is a spam complaint
+    pub feedback_type: String,
+    pub user_agent: Option<String>,
+    // Complaining recipient, often redacted by the mailbox provider
+    pub email: Option<String>,
+    pub message_id: Option<String>,
+    pub outbox_id: Option<i64>,
+}
+
+fn address(value: &str) -> Option<String> {
+    let value = value.trim();
+    let address = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value,
+    };
+    address.contains('@').then(|| address.trim().to_lowercase())
+}
+
+//
+//** Parse a raw ARF (RFC 5965) feedback loop report
+//** The recipient is taken from Original-Rcpt-To, or from the To header of the quoted message
+//** Params : raw (full MIME message) , verp (to find the outbox id of the original message)
+//** Return : FeedbackReport, or an error when the message is not a feedback report
+//
+pub fn parse_arf(raw: &[u8], verp: Option<&Verp>) -> Result<FeedbackReport, EmailError> {
+    let mail = mailparse::parse_mail(raw)
+        .map_err(|e| EmailError::MessageError(format!("Invalid MIME message: {}", e)))?;
+
+    let part = find_part(&mail, &["message/feedback-report"])
+        .ok_or_else(|| EmailError::MessageError("Not a feedback report".to_string()))?;
+    let body = part.get_body_raw()
+        .map_err(|e| EmailError::MessageError(format!("Invalid feedback report: {}", e)))?;
+    let groups = field_groups(&body)?;
+    let fields = groups.first()
+        .ok_or_else(|| EmailError::MessageError("Empty feedback report".to_string()))?;
+
+    let original = OriginalMessage::find(&mail, verp);
+
+    Ok(FeedbackReport {
+        feedback_type: fields.get_first_value("Feedback-Type")
+            .map(|value| value.trim().to_lowercase())
+            .unwrap_or_else(|| "abuse".to_string()),
+        user_agent: fields.get_first_value("User-Agent"),
+        email: fields.get_first_value("Original-Rcpt-To")
+            .or(original.to)
+            .and_then(|value| address(&value)),
+        message_id: original.message_id,
+        outbox_id: original.outbox_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARF: &str = "From: fbl@isp.example\r\n\
+To: fbl@news.example.com\r\n\
+Subject: FW: Hello\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/report; report-type=feedback-report; boundary=\"b2\"\r\n\
+\r\n\
+--b2\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+This is an email abuse report.\r\n\
+--b2\r\n\
+Content-Type: message/feedback-report\r\n\
+\r\n\
+Feedback-Type: abuse\r\n\
+User-Agent: SomeGenerator/1.0\r\n\
+Version: 1\r\n\
+\r\n\
+--b2\r\n\
+Content-Type: message/rfc822\r\n\
+\r\n\
+Return-Path: <bounces+9@bounce.example.com>\r\n\
+Message-ID: <xyz@news.example.com>\r\n\
+To: Someone <Someone@isp.example>\r\n\
+Subject: Hello\r\n\
+\r\n\
+Hi\r\n\
+--b2--\r\n";
+
+    #[test]
+    fn parses_feedback_reports() {
+        let verp = Verp::new("bounces", "bounce.example.com");
+        let report = parse_arf(ARF.as_bytes(), Some(&verp)).unwrap();
+
+        assert_eq!(report.feedback_type, "abuse");
+        assert_eq!(report.user_agent.as_deref(), Some("SomeGenerator/1.0"));
+        assert_eq!(report.email.as_deref(), Some("someone@isp.example"));
+        assert_eq!(report.message_id.as_deref(), Some("xyz@news.example.com"));
+        assert_eq!(report.outbox_id, Some(9));
+
+        assert!(parse_arf(b"Subject: hi\r\n\r\nhello", None).is_err());
+    }
+}
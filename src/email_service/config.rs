@@ -1,36 +1,810 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::email_service::bounce::Verp;
 #[derive(Debug , Clone , Serialize , Deserialize)]
 #[serde(tag = "type")]
 pub enum EmailProviderConfig {
     Smtp(SmtpConfig),
     AwsSes(AwsSesConfig),
+    Mock(MockConfig),
 }
 
+impl EmailProviderConfig {
+    //
+    //** Read the primary provider configuration from the environment
+    //** EMAIL_PROVIDER selects the provider : smtp (default), ses or mock
+    //
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("EMAIL_PROVIDER").as_deref() {
+            Ok("mock") => Ok(Self::Mock(MockConfig::from_env())),
+            Ok("ses") => AwsSesConfig::from_env()
+                .map(Self::AwsSes)
+                .ok_or_else(|| "AWS_SES_REGION must be set when EMAIL_PROVIDER=ses".to_string()),
+            Ok("smtp") | Err(_) => SmtpConfig::from_env().map(Self::Smtp),
+            Ok(other) => Err(format!("Unknown EMAIL_PROVIDER: {}", other)),
+        }
+    }
+
+    /// Messenger name the provider is registered under
+    pub fn messenger(&self) -> &'static str {
+        match self {
+            Self::Smtp(_) => "smtp",
+            Self::AwsSes(_) => "ses",
+            Self::Mock(_) => "mock",
+        }
+    }
+
+    pub fn sender_email(&self) -> String {
+        match self {
+            Self::Smtp(config) => config.sender_email.clone(),
+            Self::AwsSes(config) => config.sender_email.clone(),
+            Self::Mock(_) => std::env::var("FROM_EMAIL").unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug , Clone , Copy , PartialEq , Serialize , Deserialize , Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTlsMode {
+    // Plaintext connection upgraded with STARTTLS (usually port 587)
+    #[default]
+    StartTls,
+    // Implicit TLS from the first byte (usually port 465)
+    Tls,
+    // No encryption at all, for local catchers such as MailHog on port 1025
+    None,
+}
+
+impl SmtpTlsMode {
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Self::StartTls => 587,
+            Self::Tls => 465,
+            Self::None => 25,
+        }
+    }
+}
+
+#[derive(Debug , Clone , Copy , PartialEq , Serialize , Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpAuthMechanism {
+    Plain,
+    Login,
+    Xoauth2,
+}
 
 #[derive(Debug , Clone , Serialize , Deserialize)]
 pub struct SmtpConfig {
     pub server: String,
-    pub port: i32,
-    pub username: String,
-    pub password: String,
+    pub port: u16,
+    #[serde(default)]
+    pub tls: SmtpTlsMode,
+    // Authentication is skipped when no username is set
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    // Negotiated with the server when unset
+    #[serde(default)]
+    pub auth_mechanism: Option<SmtpAuthMechanism>,
+    // Name sent with EHLO/HELO, the local hostname when unset
+    #[serde(default)]
+    pub helo_name: Option<String>,
+    #[serde(default = "default_smtp_timeout")]
+    pub timeout_secs: u64,
     pub sender_email: String,
+    #[serde(default)]
+    pub pool: SmtpPoolConfig,
+}
+
+fn default_smtp_timeout() -> u64 {
+    30
+}
+
+impl SmtpConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let server = std::env::var("SMTP_HOST").map_err(|_| "SMTP_HOST must be set".to_string())?;
+        let sender_email = std::env::var("FROM_EMAIL").map_err(|_| "FROM_EMAIL must be set".to_string())?;
+
+        let port: Option<u16> = match std::env::var("SMTP_PORT") {
+            Ok(port) => Some(port.parse().map_err(|_| format!("Invalid SMTP_PORT: {}", port))?),
+            Err(_) => None,
+        };
+
+        // Without SMTP_TLS, keep implicit TLS unless the port says otherwise
+        let tls = match std::env::var("SMTP_TLS").as_deref() {
+            Ok("tls") => SmtpTlsMode::Tls,
+            Ok("starttls") => SmtpTlsMode::StartTls,
+            Ok("none") => SmtpTlsMode::None,
+            Ok(other) => return Err(format!("Unknown SMTP_TLS mode: {}", other)),
+            Err(_) => match port {
+                None | Some(465) => SmtpTlsMode::Tls,
+                Some(_) => SmtpTlsMode::StartTls,
+            },
+        };
+
+        let auth_mechanism = match std::env::var("SMTP_AUTH_MECHANISM").as_deref() {
+            Ok("plain") => Some(SmtpAuthMechanism::Plain),
+            Ok("login") => Some(SmtpAuthMechanism::Login),
+            Ok("xoauth2") => Some(SmtpAuthMechanism::Xoauth2),
+            Ok(other) => return Err(format!("Unknown SMTP_AUTH_MECHANISM: {}", other)),
+            Err(_) => None,
+        };
+
+        Ok(Self {
+            server,
+            port: port.unwrap_or_else(|| tls.default_port()),
+            tls,
+            username: std::env::var("SMTP_USERNAME").ok().filter(|u| !u.is_empty()),
+            password: std::env::var("SMTP_PASSWORD").ok(),
+            auth_mechanism,
+            helo_name: std::env::var("SMTP_HELO_NAME").ok(),
+            timeout_secs: env_or("SMTP_TIMEOUT_SECS", default_smtp_timeout()),
+            sender_email,
+            pool: SmtpPoolConfig::from_env(),
+        })
+    }
 }
 
 #[derive(Debug , Clone , Serialize , Deserialize)]
 pub struct AwsSesConfig {
     pub region: String,
     pub sender_email: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    #[serde(default)]
+    pub session_token: Option<String>,
+    // Overrides https://email.{region}.amazonaws.com, e.g. a local SES emulator
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+    #[serde(default)]
+    pub configuration_set: Option<String>,
+}
+
+impl AwsSesConfig {
+    /// Read the SES configuration from the environment, `None` when no region is set
+    pub fn from_env() -> Option<Self> {
+        let region = std::env::var("AWS_SES_REGION").ok()?;
+
+        Some(Self {
+            region,
+            sender_email: std::env::var("FROM_EMAIL").unwrap_or_default(),
+            access_key_id: std::env::var("AWS_ACCESS_KEY_ID").unwrap_or_default(),
+            secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default(),
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            endpoint_url: std::env::var("AWS_SES_ENDPOINT_URL").ok(),
+            configuration_set: std::env::var("AWS_SES_CONFIGURATION_SET").ok(),
+        })
+    }
+
+    pub fn endpoint(&self) -> String {
+        self.endpoint_url
+            .clone()
+            .unwrap_or_else(|| format!("https://email.{}.amazonaws.com", self.region))
+    }
+}
+
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct OutboxConfig {
+    // Number of concurrent workers draining the outbox
+    pub workers: usize,
+    // Messages claimed by a worker at once
+    pub batch_size: i64,
+    // Wait before polling again when the outbox is empty
+    pub poll_interval_ms: u64,
+    // Messages locked longer than this are considered abandoned and claimed again
+    pub lock_timeout_secs: i64,
+    pub retry: RetryConfig,
 }
 
-impl Default for SmtpConfig {
+impl Default for OutboxConfig {
     fn default() -> Self {
         Self {
-            server: "ssl0.ovh.net".to_string(),
-            port: 465,
-            username: "info@wayfe.net".to_string(),
-            password: "JFHSUifhu8fida99".to_string(),
-            sender_email: "info@wayfe.net".to_string(),
+            workers: 10,
+            batch_size: 10,
+            poll_interval_ms: 1000,
+            lock_timeout_secs: 300,
+            retry: RetryConfig::default(),
         }
     }
-} 
+}
+
+impl OutboxConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            workers: env_or("OUTBOX_WORKERS", default.workers).max(1),
+            batch_size: env_or("OUTBOX_BATCH_SIZE", default.batch_size).max(1),
+            poll_interval_ms: env_or("OUTBOX_POLL_INTERVAL_MS", default.poll_interval_ms),
+            lock_timeout_secs: env_or("OUTBOX_LOCK_TIMEOUT_SECS", default.lock_timeout_secs),
+            retry: RetryConfig::from_env(),
+        }
+    }
+}
+
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct RetryConfig {
+    // Attempts before a transient failure is given up, the first send included
+    pub max_attempts: i32,
+    pub base_delay_secs: u64,
+    pub max_delay_secs: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay_secs: 30,
+            max_delay_secs: 3600,
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_attempts: env_or("EMAIL_RETRY_MAX_ATTEMPTS", default.max_attempts).max(1),
+            base_delay_secs: env_or("EMAIL_RETRY_BASE_DELAY_SECS", default.base_delay_secs).max(1),
+            max_delay_secs: env_or("EMAIL_RETRY_MAX_DELAY_SECS", default.max_delay_secs),
+        }
+    }
+
+    //
+    //** Delay before the next attempt, after `attempt` failed ones
+    //** Exponential with equal jitter so the retries of a campaign do not hit the server together
+    //** Return : std::time::Duration
+    //
+    pub fn backoff(&self, attempt: u32) -> std::time::Duration {
+        let exponent = attempt.saturating_sub(1).min(20);
+        let delay = self.base_delay_secs
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_secs)
+            .max(1);
+
+        let half = delay / 2;
+        std::time::Duration::from_secs(half + rand::thread_rng().gen_range(0..=delay - half))
+    }
+}
 
+/// `limit` messages every `period_secs`
+#[derive(Debug , Clone , Copy , PartialEq , Serialize , Deserialize)]
+pub struct Rate {
+    pub limit: u32,
+    pub period_secs: u64,
+}
+
+impl std::str::FromStr for Rate {
+    type Err = String;
+
+    // "10/s", "600/m", "5000/h" or "20000/d"
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (limit, unit) = value.trim()
+            .split_once('/')
+            .ok_or_else(|| format!("Invalid rate '{}', expected <count>/<s|m|h|d>", value))?;
+
+        let limit: u32 = limit.trim().parse()
+            .map_err(|_| format!("Invalid rate count in '{}'", value))?;
+        let period_secs = match unit.trim() {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86400,
+            other => return Err(format!("Invalid rate unit '{}' in '{}'", other, value)),
+        };
+        if limit == 0 {
+            return Err(format!("Rate '{}' would never send anything", value));
+        }
+
+        Ok(Self { limit, period_secs })
+    }
+}
+
+//
+//** Sending rates per messenger and per recipient domain
+//** Several rates can apply to the same key, e.g. gmail.com:5/s,gmail.com:2000/h
+//
+#[derive(Debug , Clone , Default , Serialize , Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default)]
+    pub providers: HashMap<String, Vec<Rate>>,
+    #[serde(default)]
+    pub domains: HashMap<String, Vec<Rate>>,
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            providers: parse_rates(&std::env::var("EMAIL_PROVIDER_RATE_LIMITS").unwrap_or_default())?,
+            domains: parse_rates(&std::env::var("EMAIL_DOMAIN_RATE_LIMITS").unwrap_or_default())?
+                .into_iter()
+                .map(|(domain, rates)| (domain.to_lowercase(), rates))
+                .collect(),
+        })
+    }
+}
+
+/// Parse "key:rate,key:rate"
+fn parse_rates(value: &str) -> Result<HashMap<String, Vec<Rate>>, String> {
+    let mut rates: HashMap<String, Vec<Rate>> = HashMap::new();
+
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (key, rate) = entry
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid rate limit '{}', expected <key>:<count>/<unit>", entry))?;
+        rates.entry(key.trim().to_string()).or_default().push(rate.parse()?);
+    }
+
+    Ok(rates)
+}
+
+#[derive(Debug , Clone , Copy , Default , PartialEq , Serialize , Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingStrategy {
+    // Always try the routes in the configured order
+    #[default]
+    Failover,
+    // Spread sends by weight, the other routes serving as fallbacks
+    Weighted,
+}
+
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct RouteConfig {
+    // Recorded on each delivery sent through this route
+    pub name: String,
+    #[serde(default = "default_route_weight")]
+    pub weight: u32,
+    pub provider: EmailProviderConfig,
+}
+
+fn default_route_weight() -> u32 {
+    1
+}
+
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct CircuitBreakerConfig {
+    // Consecutive transient failures before a route is taken out
+    pub failure_threshold: u32,
+    // Time before a tripped route gets a trial send
+    pub cooldown_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown_secs: 60,
+        }
+    }
+}
+
+//
+//** Several providers behind a single messenger
+//** Read as JSON from EMAIL_ROUTES, e.g.
+//** {"strategy":"failover","routes":[{"name":"primary","provider":{"type":"Smtp",...}},
+//**                                  {"name":"backup","provider":{"type":"AwsSes",...}}]}
+//
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct RoutingConfig {
+    #[serde(default)]
+    pub strategy: RoutingStrategy,
+    pub routes: Vec<RouteConfig>,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+impl RoutingConfig {
+    /// None when EMAIL_ROUTES is unset and a single provider is used
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let value = match std::env::var("EMAIL_ROUTES") {
+            Ok(value) if !value.trim().is_empty() => value,
+            _ => return Ok(None),
+        };
+
+        let config: Self = serde_json::from_str(&value)
+            .map_err(|e| format!("Invalid EMAIL_ROUTES: {}", e))?;
+        if config.routes.is_empty() {
+            return Err("EMAIL_ROUTES must contain at least one route".to_string());
+        }
+
+        Ok(Some(config))
+    }
+}
+
+#[derive(Debug , Clone , Copy , Default , PartialEq , Serialize , Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DkimAlgorithm {
+    #[default]
+    Rsa,
+    Ed25519,
+}
+
+//
+//** DKIM key of a sending domain
+//** The key is a PKCS#1 PEM for RSA, the base64 of the 32 byte secret for Ed25519
+//
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct DkimDomainConfig {
+    pub domain: String,
+    pub selector: String,
+    #[serde(default)]
+    pub algorithm: DkimAlgorithm,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    // Read when private_key is unset
+    #[serde(default)]
+    pub private_key_path: Option<PathBuf>,
+    #[serde(default = "default_dkim_headers")]
+    pub headers: Vec<String>,
+}
+
+pub fn default_dkim_headers() -> Vec<String> {
+    // RFC 8058 requires the List-Unsubscribe headers to be signed
+    ["From", "To", "Subject", "Date", "Message-ID", "MIME-Version", "List-Unsubscribe", "List-Unsubscribe-Post"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect()
+}
+
+impl DkimDomainConfig {
+    /// Key of a single domain from DKIM_DOMAIN, DKIM_SELECTOR and DKIM_PRIVATE_KEY_PATH
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let domain = match std::env::var("DKIM_DOMAIN") {
+            Ok(domain) if !domain.is_empty() => domain,
+            _ => return Ok(None),
+        };
+
+        let algorithm = match std::env::var("DKIM_ALGORITHM").as_deref() {
+            Ok("rsa") | Err(_) => DkimAlgorithm::Rsa,
+            Ok("ed25519") => DkimAlgorithm::Ed25519,
+            Ok(other) => return Err(format!("Unknown DKIM_ALGORITHM: {}", other)),
+        };
+
+        let headers = match std::env::var("DKIM_HEADERS") {
+            Ok(headers) => headers.split(':').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect(),
+            Err(_) => default_dkim_headers(),
+        };
+
+        Ok(Some(Self {
+            domain,
+            selector: std::env::var("DKIM_SELECTOR").map_err(|_| "DKIM_SELECTOR must be set with DKIM_DOMAIN".to_string())?,
+            algorithm,
+            private_key: None,
+            private_key_path: Some(
+                std::env::var("DKIM_PRIVATE_KEY_PATH")
+                    .map_err(|_| "DKIM_PRIVATE_KEY_PATH must be set with DKIM_DOMAIN".to_string())?
+                    .into(),
+            ),
+            headers,
+        }))
+    }
+
+    pub fn load_private_key(&self) -> Result<String, String> {
+        match (&self.private_key, &self.private_key_path) {
+            (Some(key), _) => Ok(key.clone()),
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .map(|key| key.trim().to_string())
+                .map_err(|e| format!("Cannot read DKIM key {}: {}", path.display(), e)),
+            (None, None) => Err(format!("No DKIM private key for {}", self.domain)),
+        }
+    }
+}
+
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct SmtpPoolConfig {
+    // Maximum number of open connections to the relay
+    pub max_size: u32,
+    // Connections kept open while idle
+    pub min_idle: u32,
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for SmtpPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10,
+            min_idle: 0,
+            idle_timeout_secs: 60,
+        }
+    }
+}
+
+impl SmtpPoolConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            max_size: env_or("SMTP_POOL_MAX_SIZE", default.max_size),
+            min_idle: env_or("SMTP_POOL_MIN_IDLE", default.min_idle),
+            idle_timeout_secs: env_or("SMTP_POOL_IDLE_TIMEOUT_SECS", default.idle_timeout_secs),
+        }
+    }
+}
+
+//
+//** Bounce handling
+//** BOUNCE_VERP_DOMAIN enables VERP return paths (<prefix>+<outbox id>@<domain>), off when unset
+//** BOUNCE_MAILDIR is polled for DSN reports every BOUNCE_MAILDIR_POLL_SECS, off when unset
+//
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct BounceConfig {
+    pub verp_domain: Option<String>,
+    pub verp_prefix: String,
+    // Hard bounces after which a subscriber is blocklisted
+    pub hard_bounce_limit: i64,
+    pub maildir: Option<PathBuf>,
+    pub maildir_poll_secs: u64,
+}
+
+impl Default for BounceConfig {
+    fn default() -> Self {
+        Self {
+            verp_domain: None,
+            verp_prefix: "bounces".to_string(),
+            hard_bounce_limit: 2,
+            maildir: None,
+            maildir_poll_secs: 60,
+        }
+    }
+}
+
+impl BounceConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            verp_domain: std::env::var("BOUNCE_VERP_DOMAIN").ok().filter(|d| !d.is_empty()),
+            verp_prefix: env_or("BOUNCE_VERP_PREFIX", default.verp_prefix),
+            hard_bounce_limit: env_or("BOUNCE_HARD_LIMIT", default.hard_bounce_limit).max(1),
+            maildir: std::env::var("BOUNCE_MAILDIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from),
+            maildir_poll_secs: env_or("BOUNCE_MAILDIR_POLL_SECS", default.maildir_poll_secs).max(1),
+        }
+    }
+
+    pub fn verp(&self) -> Option<Verp> {
+        self.verp_domain.as_ref().map(|domain| Verp::new(self.verp_prefix.clone(), domain.clone()))
+    }
+}
+
+/// Feedback loop mailbox, COMPLAINT_MAILDIR is polled every COMPLAINT_MAILDIR_POLL_SECS, off when unset
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct ComplaintConfig {
+    pub maildir: Option<PathBuf>,
+    pub maildir_poll_secs: u64,
+}
+
+impl Default for ComplaintConfig {
+    fn default() -> Self {
+        Self {
+            maildir: None,
+            maildir_poll_secs: 60,
+        }
+    }
+}
+
+impl ComplaintConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            maildir: std::env::var("COMPLAINT_MAILDIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from),
+            maildir_poll_secs: env_or("COMPLAINT_MAILDIR_POLL_SECS", default.maildir_poll_secs).max(1),
+        }
+    }
+}
+
+//
+//** Signature checks of the delivery events posted by providers to /api/webhooks/inbound/{provider}
+//** A provider without its key (SENDGRID_WEBHOOK_PUBLIC_KEY, MAILGUN_WEBHOOK_SIGNING_KEY) is refused,
+//** SNS messages are checked against the AWS certificate they reference, from SNS_TOPIC_ARNS only when set
+//
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct InboundWebhookConfig {
+    // Base64 DER public key of the SendGrid signed event webhook
+    pub sendgrid_public_key: Option<String>,
+    pub mailgun_signing_key: Option<String>,
+    // Comma separated, any topic is accepted when empty
+    pub sns_topic_arns: Vec<String>,
+    // Confirm SNS subscriptions by following their SubscribeURL
+    pub sns_auto_confirm: bool,
+    // Signed timestamps older than this are refused (replays)
+    pub max_age_secs: i64,
+}
+
+impl Default for InboundWebhookConfig {
+    fn default() -> Self {
+        Self {
+            sendgrid_public_key: None,
+            mailgun_signing_key: None,
+            sns_topic_arns: Vec::new(),
+            sns_auto_confirm: true,
+            max_age_secs: 300,
+        }
+    }
+}
+
+impl InboundWebhookConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            sendgrid_public_key: std::env::var("SENDGRID_WEBHOOK_PUBLIC_KEY").ok().filter(|k| !k.is_empty()),
+            mailgun_signing_key: std::env::var("MAILGUN_WEBHOOK_SIGNING_KEY").ok().filter(|k| !k.is_empty()),
+            sns_topic_arns: std::env::var("SNS_TOPIC_ARNS")
+                .map(|arns| arns.split(',').map(str::trim).filter(|arn| !arn.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default(),
+            sns_auto_confirm: env_or("SNS_AUTO_CONFIRM", default.sns_auto_confirm),
+            max_age_secs: env_or("INBOUND_WEBHOOK_MAX_AGE_SECS", default.max_age_secs).max(1),
+        }
+    }
+}
+
+//
+//** Outbound event webhooks, delivered by workers polling webhook_deliveries
+//** Retries use the same backoff as emails with their own WEBHOOK_RETRY_* settings
+//
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct WebhookConfig {
+    pub workers: usize,
+    pub batch_size: i64,
+    pub poll_interval_ms: u64,
+    // Deliveries locked longer than this are considered abandoned and claimed again
+    pub lock_timeout_secs: i64,
+    // Time given to the receiver to answer
+    pub timeout_secs: u64,
+    pub retry: RetryConfig,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            batch_size: 20,
+            poll_interval_ms: 1000,
+            lock_timeout_secs: 300,
+            timeout_secs: 10,
+            retry: RetryConfig {
+                max_attempts: 8,
+                base_delay_secs: 30,
+                max_delay_secs: 6 * 3600,
+            },
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            workers: env_or("WEBHOOK_WORKERS", default.workers).max(1),
+            batch_size: env_or("WEBHOOK_BATCH_SIZE", default.batch_size).max(1),
+            poll_interval_ms: env_or("WEBHOOK_POLL_INTERVAL_MS", default.poll_interval_ms),
+            lock_timeout_secs: env_or("WEBHOOK_LOCK_TIMEOUT_SECS", default.lock_timeout_secs),
+            timeout_secs: env_or("WEBHOOK_TIMEOUT_SECS", default.timeout_secs).max(1),
+            retry: RetryConfig {
+                max_attempts: env_or("WEBHOOK_RETRY_MAX_ATTEMPTS", default.retry.max_attempts).max(1),
+                base_delay_secs: env_or("WEBHOOK_RETRY_BASE_DELAY_SECS", default.retry.base_delay_secs).max(1),
+                max_delay_secs: env_or("WEBHOOK_RETRY_MAX_DELAY_SECS", default.retry.max_delay_secs),
+            },
+        }
+    }
+}
+
+//
+//** Pre-send HTML pipeline: CSS inlining, script and form stripping, relative URLs made absolute
+//** HTML_BASE_URL is where relative links and images point to, BASE_URL when unset
+//
+#[derive(Debug , Clone , Serialize , Deserialize)]
+pub struct HtmlConfig {
+    pub enabled: bool,
+    pub base_url: Option<String>,
+    // Normalized bodies kept in memory, so a sequence email is processed once and not per recipient
+    pub cache_size: usize,
+}
+
+impl Default for HtmlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            base_url: None,
+            cache_size: 256,
+        }
+    }
+}
+
+impl HtmlConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            enabled: env_or("HTML_NORMALIZE", default.enabled),
+            base_url: std::env::var("HTML_BASE_URL")
+                .or_else(|_| std::env::var("BASE_URL"))
+                .ok()
+                .filter(|url| !url.trim().is_empty()),
+            cache_size: env_or("HTML_CACHE_SIZE", default.cache_size),
+        }
+    }
+}
+
+/// Parse an environment variable, falling back to `default` when missing or invalid
+pub fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[derive(Debug , Clone , Copy , PartialEq , Serialize , Deserialize , Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MockOutputFormat {
+    // One `<uuid>.eml` file per message
+    #[default]
+    Eml,
+    // Maildir layout (tmp/new/cur), readable by any mail client
+    Maildir,
+}
+
+#[derive(Debug , Clone , Serialize , Deserialize , Default)]
+pub struct MockConfig {
+    // Directory where rendered messages are written, in memory only when unset
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub output_format: MockOutputFormat,
+    // Recipients for which the provider reports a failure
+    #[serde(default)]
+    pub fail_recipients: Vec<String>,
+    // Report those failures as transient (e.g. a 421) instead of a rejection
+    #[serde(default)]
+    pub fail_transient: bool,
+}
+
+impl MockConfig {
+    pub fn from_env() -> Self {
+        let output_dir = std::env::var("MOCK_OUTPUT_DIR").ok()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+
+        let output_format = match std::env::var("MOCK_OUTPUT_FORMAT").as_deref() {
+            Ok("maildir") => MockOutputFormat::Maildir,
+            _ => MockOutputFormat::Eml,
+        };
+
+        let fail_recipients = std::env::var("MOCK_FAIL_RECIPIENTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect();
+
+        Self {
+            output_dir,
+            output_format,
+            fail_recipients,
+            fail_transient: std::env::var("MOCK_FAIL_TRANSIENT").as_deref() == Ok("true"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_stays_capped() {
+        let retry = RetryConfig { max_attempts: 5, base_delay_secs: 10, max_delay_secs: 60 };
+
+        for _ in 0..20 {
+            let first = retry.backoff(1).as_secs();
+            assert!((5..=10).contains(&first));
+
+            let third = retry.backoff(3).as_secs();
+            assert!((20..=40).contains(&third));
+
+            let capped = retry.backoff(30).as_secs();
+            assert!((30..=60).contains(&capped));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use pulldown_cmark::{html, Options, Parser};
+use crate::models::campaign::ContentType;
+
+// Column the generated text part is wrapped at
+const TEXT_WIDTH: usize = 78;
+
+// Private use characters around the index of a protected merge tag, left alone by every renderer
+pub(crate) const TAG_START: char = '\u{E000}';
+const TAG_END: char = '\u{E001}';
+
+// Attributes kept on rich text elements on top of ammonia's defaults, emails are styled inline
+const RICHTEXT_ATTRIBUTES: &[&str] = &["style", "class", "align", "valign", "width", "height", "bgcolor", "border", "cellpadding", "cellspacing"];
+
+//
+//** Take merge tags ({{ ... }} and {% ... %}) out of a body before it is converted
+//** Markdown and sanitizing would otherwise escape the quotes of their arguments
+//** Return : (body with placeholders, tags in order)
+//
+pub(crate) fn protect_tags(body: &str) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(body.len());
+    let mut tags = Vec::new();
+    let mut rest = body;
+
+    loop {
+        let next = [("{{", "}}"), ("{%", "%}")].iter()
+            .filter_map(|(open, close)| rest.find(open).map(|start| (start, *close)))
+            .min_by_key(|(start, _)| *start);
+
+        let Some((start, close)) = next else { break };
+        let Some(end) = rest[start + 2..].find(close).map(|i| start + 2 + i + close.len()) else { break };
+
+        out.push_str(&rest[..start]);
+        out.push(TAG_START);
+        out.push_str(&tags.len().to_string());
+        out.push(TAG_END);
+        tags.push(rest[start..end].to_string());
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    (out, tags)
+}
+
+pub(crate) fn restore_tags(body: &str, tags: &[String]) -> String {
+    // Markdown percent-encodes placeholders that end up in link targets
+    let body = body.replace("%EE%80%80", "\u{E000}").replace("%EE%80%81", "\u{E001}");
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body.as_str();
+
+    while let Some(start) = rest.find(TAG_START) {
+        let Some(end) = rest[start..].find(TAG_END).map(|i| start + i) else { break };
+        let tag = rest[start + TAG_START.len_utf8()..end].parse::<usize>().ok().and_then(|i| tags.get(i));
+
+        out.push_str(&rest[..start]);
+        match tag {
+            Some(tag) => out.push_str(tag),
+            None => out.push_str(&rest[start..end + TAG_END.len_utf8()]),
+        }
+        rest = &rest[end + TAG_END.len_utf8()..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Plain text as HTML paragraphs, line breaks kept
+fn plain_to_html(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .map(|paragraph| paragraph.trim_matches('\n'))
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .map(|paragraph| format!("<p>{}</p>", escape_html(paragraph).replace('\n', "<br>\n")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn markdown_to_html(text: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_FOOTNOTES;
+    let mut out = String::with_capacity(text.len() * 3 / 2);
+    html::push_html(&mut out, Parser::new_ext(text, options));
+    out
+}
+
+fn sanitize(html: &str) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder.add_generic_attributes(RICHTEXT_ATTRIBUTES.iter().copied());
+    builder.clean(html).to_string()
+}
+
+//
+//** Render an email body to HTML according to its content type
+//** html is sent as written, richtext is sanitized, markdown converted and plain text escaped.
+//** Merge tags are kept as they are, to be rendered per subscriber when the message is sent
+//** Params : content_type , body
+//** Return : String (HTML body)
+//
+pub fn render_body(content_type: ContentType, body: &str) -> String {
+    if content_type == ContentType::Html {
+        return body.to_string();
+    }
+
+    let (body, tags) = protect_tags(body);
+    let html = match content_type {
+        ContentType::Richtext => sanitize(&body),
+        ContentType::Markdown => markdown_to_html(&body),
+        ContentType::Plain => plain_to_html(&body),
+        ContentType::Html => body,
+    };
+    restore_tags(&html, &tags)
+}
+
+/// Plain text alternative of an HTML body, links listed as footnotes
+pub fn html_to_text(html: &str) -> String {
+    html2text::from_read(html.as_bytes(), TEXT_WIDTH).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_each_content_type() {
+        let markdown = "# Hi {{ subscriber.name | default(\"there\") }}\n\nSee *this* [link]({{ unsubscribe_url }}).";
+        assert_eq!(
+            render_body(ContentType::Markdown, markdown),
+            "<h1>Hi {{ subscriber.name | default(\"there\") }}</h1>\n<p>See <em>this</em> <a href=\"{{ unsubscribe_url }}\">link</a>.</p>\n"
+        );
+
+        assert_eq!(
+            render_body(ContentType::Plain, "Hello <b>{{ subscriber.name }}</b>,\nline two\n\nBye & thanks"),
+            "<p>Hello &lt;b&gt;{{ subscriber.name }}&lt;/b&gt;,<br>\nline two</p>\n<p>Bye &amp; thanks</p>"
+        );
+
+        let richtext = render_body(
+            ContentType::Richtext,
+            "<p style=\"color:red\" onclick=\"steal()\">Hi {% if subscriber.name %}{{ subscriber.name }}{% endif %}</p><script>alert(1)</script>",
+        );
+        assert_eq!(richtext, "<p style=\"color:red\">Hi {% if subscriber.name %}{{ subscriber.name }}{% endif %}</p>");
+
+        assert_eq!(render_body(ContentType::Html, "<script>kept</script>"), "<script>kept</script>");
+    }
+
+    #[test]
+    fn generates_text_parts() {
+        let text = html_to_text("<h1>Title</h1><p>Hello <b>you</b>,<br>welcome.</p><p><a href=\"https://example.com\">Read more</a></p>");
+
+        assert!(text.contains("Title"));
+        assert!(text.contains("Hello you,\nwelcome."));
+        assert!(text.contains("https://example.com"));
+        assert!(!text.contains('<'));
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use lettre::message::dkim::{
+    DkimCanonicalization, DkimCanonicalizationType, DkimConfig, DkimSigningAlgorithm, DkimSigningKey,
+};
+use lettre::message::header::HeaderName;
+use lettre::Message;
+use crate::email_service::{
+    config::{DkimAlgorithm, DkimDomainConfig},
+    error::EmailError,
+};
+
+//
+//** DKIM keys of the sending domains
+//** Messages are signed with the key of their From domain, or of its closest parent domain
+//
+pub struct DkimSigner {
+    domains: HashMap<String, DkimConfig>,
+}
+
+impl DkimSigner {
+    //
+    //** Load the signing key of every domain
+    //** Params : configs, a later entry for the same domain replaces an earlier one
+    //** Return : DkimSigner or an error for the first unreadable key or header name
+    //
+    pub fn new(configs: &[DkimDomainConfig]) -> Result<Self, EmailError> {
+        let mut domains = HashMap::new();
+
+        for config in configs {
+            let private_key = config.load_private_key().map_err(EmailError::DkimError)?;
+            let algorithm = match config.algorithm {
+                DkimAlgorithm::Rsa => DkimSigningAlgorithm::Rsa,
+                DkimAlgorithm::Ed25519 => DkimSigningAlgorithm::Ed25519,
+            };
+            let key = DkimSigningKey::new(&private_key, algorithm)
+                .map_err(|e| EmailError::DkimError(format!("Invalid key for {}: {}", config.domain, e)))?;
+
+            let headers = config.headers.iter()
+                .map(|name| {
+                    HeaderName::new_from_ascii(name.clone())
+                        .map_err(|_| EmailError::DkimError(format!("Invalid header name '{}' for {}", name, config.domain)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Relaxed on both sides survives relays that refold headers or trim trailing spaces
+            let canonicalization = DkimCanonicalization {
+                header: DkimCanonicalizationType::Relaxed,
+                body: DkimCanonicalizationType::Relaxed,
+            };
+
+            let domain = config.domain.to_lowercase();
+            domains.insert(
+                domain.clone(),
+                DkimConfig::new(config.selector.clone(), domain, key, headers, canonicalization),
+            );
+        }
+
+        Ok(Self { domains })
+    }
+
+    pub fn domains(&self) -> Vec<String> {
+        self.domains.keys().cloned().collect()
+    }
+
+    /// Key for a sender domain, news.example.com falling back to example.com
+    fn config_for(&self, domain: &str) -> Option<&DkimConfig> {
+        let domain = domain.to_lowercase();
+        let mut candidate = domain.as_str();
+
+        loop {
+            if let Some(config) = self.domains.get(candidate) {
+                return Some(config);
+            }
+            candidate = candidate.split_once('.')?.1;
+        }
+    }
+
+    /// Add a DKIM-Signature header when a key is known for the sender domain
+    pub fn sign(&self, message: &mut Message, sender_domain: &str) {
+        if let Some(config) = self.config_for(sender_domain) {
+            message.sign(config);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use rsa::pkcs1::{EncodeRsaPrivateKey, LineEnding};
+    use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+    use sha2::{Digest, Sha256};
+    use crate::email_service::{message::build_message, models::EmailRequest};
+
+    /// Unfolded headers of a formatted message, in order
+    fn headers(raw: &str) -> Vec<(String, String)> {
+        let head = raw.split("\r\n\r\n").next().unwrap();
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in head.split("\r\n") {
+            if line.starts_with(' ') || line.starts_with('\t') {
+                headers.last_mut().unwrap().1.push_str(line);
+            } else {
+                let (name, value) = line.split_once(':').unwrap();
+                headers.push((name.to_string(), value.to_string()));
+            }
+        }
+        headers
+    }
+
+    /// RFC 6376 relaxed header canonicalization
+    fn relaxed_header(name: &str, value: &str) -> String {
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        format!("{}:{}", name.to_lowercase(), value)
+    }
+
+    /// RFC 6376 relaxed body canonicalization
+    fn relaxed_body(body: &str) -> String {
+        let mut out = String::new();
+        for line in body.split("\r\n") {
+            let mut whitespace = false;
+            for c in line.chars() {
+                if c == ' ' || c == '\t' {
+                    whitespace = true;
+                } else {
+                    if whitespace {
+                        out.push(' ');
+                    }
+                    whitespace = false;
+                    out.push(c);
+                }
+            }
+            out.push_str("\r\n");
+        }
+        while out.ends_with("\r\n\r\n") {
+            out.truncate(out.len() - 2);
+        }
+        out
+    }
+
+    /// Check a DKIM-Signature the way a receiving server would, with the public key in place of DNS
+    fn verify(raw: &str, public_key: &RsaPublicKey) -> bool {
+        let headers = headers(raw);
+        let body = raw.split_once("\r\n\r\n").unwrap().1;
+
+        let (name, signature) = headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("DKIM-Signature")).unwrap();
+        let tags: HashMap<&str, String> = signature.split(';')
+            .filter_map(|tag| tag.split_once('='))
+            .map(|(k, v)| (k.trim(), v.split_whitespace().collect::<String>()))
+            .collect();
+
+        if BASE64.encode(Sha256::digest(relaxed_body(body))) != tags["bh"] {
+            return false;
+        }
+
+        let mut signed = String::new();
+        for header in tags["h"].split(':') {
+            if let Some((name, value)) = headers.iter().find(|(name, _)| name.eq_ignore_ascii_case(header)) {
+                signed.push_str(&relaxed_header(name, value));
+                signed.push_str("\r\n");
+            }
+        }
+        // The signature header itself, with an empty b= tag and no trailing CRLF
+        let b_start = signature.match_indices("b=")
+            .map(|(i, _)| i)
+            .find(|&i| signature[..i].trim_end().ends_with(';'))
+            .unwrap() + 2;
+        signed.push_str(&relaxed_header(name, &signature[..b_start]));
+
+        let signature = BASE64.decode(&tags["b"]).unwrap();
+        public_key
+            .verify(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(signed), &signature)
+            .is_ok()
+    }
+
+    #[test]
+    fn signs_and_verifies_round_trip() {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let signer = DkimSigner::new(&[DkimDomainConfig {
+            domain: "example.com".to_string(),
+            selector: "mail".to_string(),
+            algorithm: DkimAlgorithm::Rsa,
+            private_key: Some(key.to_pkcs1_pem(LineEnding::LF).unwrap().to_string()),
+            private_key_path: None,
+            headers: crate::email_service::config::default_dkim_headers(),
+        }])
+        .unwrap();
+
+        let request = EmailRequest {
+            to: "someone@example.org".to_string(),
+            subject: "Hello   there".to_string(),
+            body: "<p>Hi  \t you</p>\r\n\r\n".to_string(),
+            text_body: Some("Hi you".to_string()),
+            from: Some("News <news@Mail.Example.com>".to_string()),
+            headers: vec![("List-Unsubscribe-Post".to_string(), "List-Unsubscribe=One-Click".to_string())],
+            return_path: None,
+        };
+
+        let raw = String::from_utf8(build_message(&request, Some(&signer)).unwrap().formatted()).unwrap();
+        assert!(raw.contains("d=example.com; s=mail;"));
+        assert!(raw.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        assert!(verify(&raw, &key.to_public_key()));
+
+        // Any change to a signed header breaks the signature
+        let tampered = raw.replacen("Subject: Hello", "Subject: Bye", 1);
+        assert!(!verify(&tampered, &key.to_public_key()));
+
+        // Domains without a key go out unsigned
+        let other = EmailRequest { from: Some("someone@other.com".to_string()), ..request };
+        let raw = String::from_utf8(build_message(&other, Some(&signer)).unwrap().formatted()).unwrap();
+        assert!(!raw.contains("DKIM-Signature"));
+    }
+}
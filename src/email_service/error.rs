@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use lettre::transport::smtp::Error as SmtpError;
 
@@ -9,4 +10,148 @@ pub enum EmailError {
     ExecutionError(String),
     #[error("Message error: {0}")]
     MessageError(String),
-}
\ No newline at end of file
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+    // Provider-side failure worth retrying (throttling, 5xx from an HTTP API, network)
+    #[error("Temporary provider error: {0}")]
+    TemporaryFailure(String),
+    #[error("Unknown email provider: {0}")]
+    UnknownProvider(String),
+    #[error("Invalid send strategy: {0}")]
+    InvalidStrategy(String),
+    #[error("DKIM error: {0}")]
+    DkimError(String),
+    // Every recipient is on the suppression list
+    #[error("Suppressed recipient: {0}")]
+    Suppressed(String),
+    // Provider webhook that does not carry a valid signature
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    // Merge tags that cannot be rendered for a recipient
+    #[error("Template error: {0}")]
+    TemplateError(String),
+}
+
+/// Whether a failed send is worth retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureKind {
+    Transient,
+    Permanent,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Transient => "transient",
+            FailureKind::Permanent => "permanent",
+        }
+    }
+}
+
+impl EmailError {
+    //
+    //** Classify a send failure
+    //** 4xx replies, timeouts and connection problems are transient, 5xx replies are permanent
+    //** Return : FailureKind
+    //
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            EmailError::SmtpError(e) => {
+                if e.is_permanent() {
+                    FailureKind::Permanent
+                } else if e.is_transient() || e.is_timeout() {
+                    FailureKind::Transient
+                } else if e.status().is_none() {
+                    // No reply from the server: connection reset, DNS, TLS handshake...
+                    FailureKind::Transient
+                } else {
+                    FailureKind::Permanent
+                }
+            }
+            EmailError::ExecutionError(_) | EmailError::TemporaryFailure(_) => FailureKind::Transient,
+            EmailError::MessageError(_)
+            | EmailError::InvalidAddress(_)
+            | EmailError::ProviderError(_)
+            | EmailError::UnknownProvider(_)
+            | EmailError::InvalidStrategy(_)
+            | EmailError::DkimError(_)
+            | EmailError::Suppressed(_)
+            | EmailError::InvalidSignature(_)
+            | EmailError::TemplateError(_) => FailureKind::Permanent,
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.failure_kind() == FailureKind::Transient
+    }
+
+    /// SMTP reply code of the failure, when the server answered
+    pub fn smtp_code(&self) -> Option<u16> {
+        match self {
+            EmailError::SmtpError(e) => e.status().map(u16::from),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::time::Duration;
+    use lettre::transport::smtp::client::SmtpConnection;
+    use lettre::transport::smtp::extension::ClientId;
+
+    /// Error lettre returns when the server greets with `reply`
+    fn smtp_reply(reply: &'static str) -> EmailError {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(reply.as_bytes()).unwrap();
+        });
+
+        let error = SmtpConnection::connect(address, Some(Duration::from_secs(5)), &ClientId::default(), None, None)
+            .err()
+            .unwrap();
+        EmailError::SmtpError(error)
+    }
+
+    #[test]
+    fn classifies_smtp_replies() {
+        let busy = smtp_reply("421 4.3.2 Service not available\r\n");
+        assert_eq!(busy.failure_kind(), FailureKind::Transient);
+        assert_eq!(busy.smtp_code(), Some(421));
+
+        let greylisted = smtp_reply("450 4.2.0 Greylisted, try again later\r\n");
+        assert_eq!(greylisted.failure_kind(), FailureKind::Transient);
+        assert_eq!(greylisted.smtp_code(), Some(450));
+
+        let rejected = smtp_reply("550 5.1.1 User unknown\r\n");
+        assert_eq!(rejected.failure_kind(), FailureKind::Permanent);
+        assert_eq!(rejected.smtp_code(), Some(550));
+
+        // Nothing listening, no reply at all
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let refused = SmtpConnection::connect(closed, Some(Duration::from_secs(5)), &ClientId::default(), None, None)
+            .err()
+            .map(EmailError::SmtpError)
+            .unwrap();
+        assert_eq!(refused.failure_kind(), FailureKind::Transient);
+        assert_eq!(refused.smtp_code(), None);
+    }
+
+    #[test]
+    fn classifies_failures() {
+        assert!(EmailError::TemporaryFailure("throttled".to_string()).is_transient());
+        assert!(EmailError::ExecutionError("timeout".to_string()).is_transient());
+        assert!(!EmailError::InvalidAddress("nope".to_string()).is_transient());
+        assert!(!EmailError::ProviderError("rejected".to_string()).is_transient());
+        assert!(!EmailError::Suppressed("gone@example.com".to_string()).is_transient());
+        assert_eq!(EmailError::ProviderError("rejected".to_string()).smtp_code(), None);
+    }
+}
@@ -1,46 +1,444 @@
-use tokio::time::{sleep, interval};
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use sqlx::PgPool;
 use std::sync::Arc;
+use tokio::time::sleep;
 use crate::email_service::{
     models::{EmailRequest, SendStrategy},
-    error::EmailError,
+    message::recipients,
     service::EmailService,
 };
+use crate::error::ApiError;
+use crate::models::email_job::{EmailJob, EmailJobRun, EmailJobStatus};
+use crate::repositories::email_job_repository::EmailJobRepository;
 
-pub struct EmailExecutor {
-    service: Arc<EmailService>,
-}
+// Jobs claimed on each scheduler tick
+const DUE_JOBS_LIMIT: i64 = 50;
+
+// A job still running after this long is taken to be abandoned and claimed again
+const JOB_LOCK_TIMEOUT_SECS: i64 = 3600;
 
+#[derive(Debug, Clone, Serialize)]
 pub struct ExecutorResponse {
-    success: bool,
-    error: Option<EmailError>,
-    message : Option<String>,
+    pub job_id: Option<i32>,
+    pub status: EmailJobStatus,
+    pub success: bool,
+    pub sent: usize,
+    pub failed: usize,
+    pub batches: usize,
+    // Recipients left out because max_batches was reached
+    pub skipped: usize,
+    pub message_ids: Vec<String>,
+    pub failures: Vec<(String, String)>, // (email, error message)
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub message: Option<String>,
+}
+
+impl ExecutorResponse {
+    fn new(job_id: Option<i32>, status: EmailJobStatus) -> Self {
+        Self {
+            job_id,
+            status,
+            success: false,
+            sent: 0,
+            failed: 0,
+            batches: 0,
+            skipped: 0,
+            message_ids: Vec::new(),
+            failures: Vec::new(),
+            next_run_at: None,
+            error: None,
+            message: None,
+        }
+    }
+
+    /// Close a response of sends done in the current call
+    fn finish(mut self, message: &str) -> Self {
+        self.status = if self.sent == 0 && self.failed > 0 {
+            EmailJobStatus::Failed
+        } else {
+            EmailJobStatus::Completed
+        };
+        self.success = self.failed == 0;
+        self.error = self.failures.first().map(|(_, error)| error.clone());
+        self.message = Some(format!("{}: {} sent, {} failed", message, self.sent, self.failed));
+        self
+    }
+}
+
+//
+//** Runs an email request with a send strategy
+//** Immediate and Batch sends run right away, Scheduled and Recurring ones are stored
+//** in email_jobs and picked up by `run_due_jobs`
+//
+pub struct EmailExecutor {
+    service: Arc<EmailService>,
+    jobs: EmailJobRepository,
 }
 
 impl EmailExecutor {
-    pub fn new(service: Arc<EmailService>) -> Self {
-        Self { service}
+    pub fn new(service: Arc<EmailService>, pool: PgPool) -> Self {
+        Self {
+            service,
+            jobs: EmailJobRepository::new(pool),
+        }
     }
 
-    pub async fn execute(&self, request: EmailRequest, strategy: SendStrategy) -> Result<ExecutorResponse, EmailError> {
+    //
+    //** Execute requests with a strategy
+    //** Params : requests , strategy , messenger (None = default provider)
+    //** Return : Result<ExecutorResponse, ApiError>
+    //
+    pub async fn execute(
+        &self,
+        requests: Vec<EmailRequest>,
+        strategy: SendStrategy,
+        messenger: Option<String>,
+    ) -> Result<ExecutorResponse, ApiError> {
+        strategy.validate()?;
+        if requests.is_empty() {
+            return Err(ApiError::BadRequest("No email to send".to_string()));
+        }
+
         match strategy {
             SendStrategy::Immediate => {
-                self.service.send_email(request, None).await?;
-                Ok(ExecutorResponse {
-                    success: true,
-                    error: None,
-                    message: Some("Email sent successfully immediately".to_string()),
-                })
+                let mut response = ExecutorResponse::new(None, EmailJobStatus::Running);
+                self.send_all(requests, messenger.as_deref(), &mut response).await;
+                Ok(response.finish("Emails sent immediately"))
             }
+            SendStrategy::Scheduled { .. } | SendStrategy::Recurring { .. } => {
+                let next_run_at = strategy.first_run_at();
+                let job = self.jobs.create(&strategy, &requests, messenger.as_deref(), next_run_at).await?;
 
+                let mut response = ExecutorResponse::new(Some(job.id), EmailJobStatus::Scheduled);
+                response.success = true;
+                response.next_run_at = next_run_at;
+                response.message = Some(format!("Email job {} scheduled", job.id));
+                Ok(response)
+            }
+            SendStrategy::Batch { batch_size, delay_between_batches, max_batches } => {
+                let response = self
+                    .run_batches(requests, batch_size, delay_between_batches, max_batches, messenger.as_deref())
+                    .await;
+                Ok(response.finish("Batched emails sent"))
+            }
+        }
+    }
+
+    pub async fn job(&self, id: i32) -> Result<EmailJob, ApiError> {
+        self.jobs.find_by_id(id).await?.ok_or(ApiError::NotFound)
+    }
 
-            //TODO: Implémenter les autres stratégies
+    /// Cancel a job that has not run to completion yet
+    pub async fn cancel(&self, id: i32) -> Result<bool, ApiError> {
+        self.jobs.cancel(id).await
+    }
+
+    //
+    //** Run the stored jobs that are due, called by the scheduler
+    //** Return : Result<Vec<ExecutorResponse>, ApiError> (one response per job run)
+    //
+    pub async fn run_due_jobs(&self) -> Result<Vec<ExecutorResponse>, ApiError> {
+        let now = Utc::now();
+        let jobs = self.jobs.claim_due(now, DUE_JOBS_LIMIT, JOB_LOCK_TIMEOUT_SECS).await?;
+        let mut responses = Vec::with_capacity(jobs.len());
+
+        for job in jobs {
+            let job_id = job.id;
+            let retry = matches!(job.strategy.0, SendStrategy::Recurring { .. });
+            match self.run_job(job, now).await {
+                Ok(response) => responses.push(response),
+                Err(e) => {
+                    tracing::error!("Failed to run email job {}: {}", job_id, e);
+                    // Out of 'running', or it would only be picked up again after the lock timeout
+                    if let Err(e) = self.jobs.release(job_id, retry, &e.to_string()).await {
+                        tracing::error!("Failed to release email job {}: {}", job_id, e);
+                    }
+                }
+            }
         }
-        //Default case
-        Ok(ExecutorResponse {
-            success: false,
-            error: Some(EmailError::InvalidStrategy),
-            message: None,
+
+        Ok(responses)
+    }
+
+    async fn run_job(&self, job: EmailJob, now: DateTime<Utc>) -> Result<ExecutorResponse, ApiError> {
+        let mut response = ExecutorResponse::new(Some(job.id), EmailJobStatus::Running);
+        self.send_all(job.requests.0.clone(), job.messenger.as_deref(), &mut response).await;
+
+        // A run fails when nothing went out, partial failures are reported but not retried
+        let NextRun { status, next_run_at, runs_completed, attempts } = next_run(&job, response.sent == 0, now);
+
+        response.status = status;
+        response.next_run_at = next_run_at;
+        response.success = response.failed == 0;
+        response.error = response.failures.first().map(|(_, error)| error.clone());
+        response.message = Some(format!(
+            "Email job {} run {}: {} sent, {} failed",
+            job.id, runs_completed, response.sent, response.failed
+        ));
+
+        self.jobs
+            .record_run(job.id, EmailJobRun {
+                status,
+                next_run_at,
+                runs_completed,
+                attempts,
+                last_error: response.error.clone(),
+                last_result: serde_json::to_value(&response)?,
+            })
+            .await?;
+
+        Ok(response)
+    }
+
+    async fn run_batches(
+        &self,
+        requests: Vec<EmailRequest>,
+        batch_size: usize,
+        delay_between_batches: Duration,
+        max_batches: Option<u32>,
+        messenger: Option<&str>,
+    ) -> ExecutorResponse {
+        let mut response = ExecutorResponse::new(None, EmailJobStatus::Running);
+
+        // Batches are counted in recipients, not in requests
+        let requests: Vec<EmailRequest> = requests.into_iter().flat_map(split_recipients).collect();
+        let delay = delay_between_batches.to_std().unwrap_or_default();
+
+        for (index, batch) in requests.chunks(batch_size).enumerate() {
+            if max_batches.is_some_and(|max| index >= max as usize) {
+                response.skipped = requests.len() - index * batch_size;
+                break;
+            }
+            if index > 0 {
+                sleep(delay).await;
+            }
+
+            self.send_all(batch.to_vec(), messenger, &mut response).await;
+            response.batches += 1;
+        }
+
+        response
+    }
+
+    async fn send_all(&self, requests: Vec<EmailRequest>, messenger: Option<&str>, response: &mut ExecutorResponse) {
+        let results = stream::iter(requests)
+            .map(|request| async move {
+                let to = request.to.clone();
+                (to, self.service.send_via(messenger, request).await)
+            })
+            .buffered(self.service.concurrency())
+            .collect::<Vec<_>>()
+            .await;
+
+        for (to, result) in results {
+            match result {
+                Ok(receipt) => {
+                    response.sent += 1;
+                    response.message_ids.push(receipt.message_id);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to send email to {}: {}", to, e);
+                    response.failed += 1;
+                    response.failures.push((to, e.to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// One request per recipient of `request`
+fn split_recipients(request: EmailRequest) -> Vec<EmailRequest> {
+    recipients(&request.to)
+        .map(|to| EmailRequest {
+            to: to.to_string(),
+            ..request.clone()
         })
+        .collect()
+}
+
+/// Next occurrence after `now`, skipping the ones missed while the service was down
+/// Where a job goes after a run, counting the run and the failed attempts
+#[derive(Debug, PartialEq)]
+struct NextRun {
+    status: EmailJobStatus,
+    next_run_at: Option<DateTime<Utc>>,
+    runs_completed: i32,
+    attempts: i32,
+}
+
+fn next_run(job: &EmailJob, run_failed: bool, now: DateTime<Utc>) -> NextRun {
+    let mut runs_completed = job.runs_completed;
+    let mut attempts = job.attempts;
+
+    let (status, next_run_at) = match job.strategy.0 {
+        SendStrategy::Recurring { interval, repeat_count, max_attempts, .. } => {
+            if run_failed {
+                attempts += 1;
+                if attempts >= max_attempts as i32 {
+                    (EmailJobStatus::Failed, None)
+                } else {
+                    (EmailJobStatus::Scheduled, Some(now + Duration::minutes(1).min(interval)))
+                }
+            } else {
+                runs_completed += 1;
+                attempts = 0;
+                if repeat_count.is_some_and(|count| runs_completed >= count as i32) {
+                    (EmailJobStatus::Completed, None)
+                } else {
+                    let last = job.next_run_at.unwrap_or(now);
+                    (EmailJobStatus::Scheduled, Some(next_occurrence(last, interval, now)))
+                }
+            }
+        }
+        // Scheduled jobs run once
+        _ => {
+            if run_failed {
+                attempts += 1;
+                (EmailJobStatus::Failed, None)
+            } else {
+                runs_completed += 1;
+                (EmailJobStatus::Completed, None)
+            }
+        }
+    };
+
+    NextRun { status, next_run_at, runs_completed, attempts }
+}
+
+fn next_occurrence(last: DateTime<Utc>, interval: Duration, now: DateTime<Utc>) -> DateTime<Utc> {
+    let next = last + interval;
+    if next > now {
+        return next;
     }
+
+    let missed = (now - last).num_milliseconds() / interval.num_milliseconds().max(1);
+    last + interval * (missed as i32 + 1)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use sqlx::types::Json;
+    use crate::email_service::{config::MockConfig, provider::ProviderRegistry, providers::MockProvider};
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, hour, minute, 0).unwrap()
+    }
+
+    fn recurring_job(runs_completed: i32, attempts: i32, repeat_count: Option<u32>) -> EmailJob {
+        EmailJob {
+            id: 1,
+            strategy: Json(SendStrategy::Recurring {
+                start_at: at(9, 0),
+                interval: Duration::hours(1),
+                repeat_count,
+                max_attempts: 3,
+            }),
+            requests: Json(Vec::new()),
+            messenger: None,
+            status: EmailJobStatus::Running,
+            next_run_at: Some(at(10, 0)),
+            runs_completed,
+            attempts,
+            last_error: None,
+            last_result: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn executor(provider: &MockProvider) -> EmailExecutor {
+        let providers = ProviderRegistry::new("mock").with_provider("mock", Box::new(provider.clone()));
+        let service = EmailService::new("sender@example.com".to_string(), providers);
+        // Never connected, batches do not touch the database
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        EmailExecutor::new(Arc::new(service), pool)
+    }
+
+    fn request(to: &str) -> EmailRequest {
+        EmailRequest {
+            to: to.to_string(),
+            subject: "Hello".to_string(),
+            body: "<p>Hi</p>".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn next_occurrence_follows_the_interval() {
+        assert_eq!(next_occurrence(at(10, 0), Duration::hours(1), at(10, 5)), at(11, 0));
+    }
+
+    #[test]
+    fn next_occurrence_catches_up_on_missed_runs() {
+        // Three occurrences were missed while the scheduler was down, they are not replayed
+        assert_eq!(next_occurrence(at(10, 0), Duration::hours(1), at(13, 30)), at(14, 0));
+    }
+
+    #[test]
+    fn next_occurrence_is_after_now_on_an_exact_boundary() {
+        assert_eq!(next_occurrence(at(10, 0), Duration::hours(1), at(11, 0)), at(12, 0));
+        assert_eq!(next_occurrence(at(10, 0), Duration::hours(1), at(13, 0)), at(14, 0));
+    }
+
+    #[test]
+    fn recurring_jobs_stop_after_repeat_count() {
+        let next = next_run(&recurring_job(1, 0, Some(3)), false, at(10, 1));
+        assert_eq!(next, NextRun {
+            status: EmailJobStatus::Scheduled,
+            next_run_at: Some(at(11, 0)),
+            runs_completed: 2,
+            attempts: 0,
+        });
+
+        let next = next_run(&recurring_job(2, 0, Some(3)), false, at(10, 1));
+        assert_eq!(next.status, EmailJobStatus::Completed);
+        assert_eq!(next.next_run_at, None);
+        assert_eq!(next.runs_completed, 3);
+    }
+
+    #[test]
+    fn recurring_jobs_retry_until_max_attempts() {
+        let next = next_run(&recurring_job(0, 1, None), true, at(10, 1));
+        assert_eq!(next.status, EmailJobStatus::Scheduled);
+        assert_eq!(next.next_run_at, Some(at(10, 2)));
+        assert_eq!(next.attempts, 2);
+
+        let next = next_run(&recurring_job(0, 2, None), true, at(10, 1));
+        assert_eq!(next.status, EmailJobStatus::Failed);
+        assert_eq!(next.next_run_at, None);
+        assert_eq!(next.attempts, 3);
+
+        // A successful run resets the attempts
+        assert_eq!(next_run(&recurring_job(0, 2, None), false, at(10, 1)).attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn batches_are_split_per_recipient() {
+        let provider = MockProvider::new(MockConfig::default());
+        let requests = vec![request("a@example.com, b@example.com, c@example.com"), request("d@example.com")];
+
+        let response = executor(&provider).run_batches(requests, 3, Duration::zero(), None, None).await;
+        assert_eq!(response.batches, 2);
+        assert_eq!(response.sent, 4);
+        assert_eq!(response.skipped, 0);
+        assert_eq!(provider.sent().len(), 4);
+        assert_eq!(provider.sent_to("b@example.com").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn batches_stop_at_max_batches() {
+        let provider = MockProvider::new(MockConfig::default());
+        let requests = (0..5).map(|i| request(&format!("user{}@example.com", i))).collect();
+
+        let response = executor(&provider).run_batches(requests, 2, Duration::zero(), Some(2), None).await;
+        assert_eq!(response.batches, 2);
+        assert_eq!(response.sent, 4);
+        assert_eq!(response.skipped, 1);
+        assert!(provider.sent_to("user4@example.com").is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use css_inline::CSSInliner;
+use lol_html::html_content::ContentType;
+use lol_html::{element, rewrite_str, RewriteStrSettings};
+use reqwest::Url;
+use crate::email_service::{config::HtmlConfig, content};
+
+// Removed with their content, mail clients block or mangle them anyway
+const STRIPPED_ELEMENTS: &str = "script, noscript, form, input, button, select, textarea";
+
+// Attributes holding a link or a resource, made absolute against the base URL
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "background"];
+
+//
+//** Pre-send HTML pipeline, run once per body and cached
+//** Inlines <style> rules into style attributes, keeps media queries in the head,
+//** strips scripts, forms and event handlers, and makes relative URLs absolute
+//
+#[derive(Clone)]
+pub struct HtmlPipeline {
+    config: HtmlConfig,
+    base_url: Option<Url>,
+    cache: Arc<Mutex<BodyCache>>,
+}
+
+/// Normalized bodies keyed by the source body, the least recently used one is evicted when full
+struct BodyCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (String, u64)>,
+}
+
+impl BodyCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, html: &str) -> Option<String> {
+        self.tick += 1;
+        let (normalized, last_used) = self.entries.get_mut(html)?;
+        *last_used = self.tick;
+        Some(normalized.clone())
+    }
+
+    fn insert(&mut self, html: &str, normalized: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(html) {
+            let oldest = self.entries.iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(html.to_string(), (normalized, self.tick));
+    }
+}
+
+impl HtmlPipeline {
+    pub fn new(config: HtmlConfig) -> Self {
+        let base_url = config.base_url.as_deref().and_then(|url| {
+            // Relative paths resolve inside the base, not next to it
+            let url = if url.ends_with('/') { url.to_string() } else { format!("{}/", url) };
+            Url::parse(&url)
+                .map_err(|e| tracing::warn!("Ignoring invalid HTML base URL {}: {}", url, e))
+                .ok()
+        });
+
+        Self {
+            cache: Arc::new(Mutex::new(BodyCache::new(config.cache_size))),
+            config,
+            base_url,
+        }
+    }
+
+    //
+    //** Normalize an email body, served from the cache when the same body was seen before
+    //** Merge tags are left untouched for the per recipient rendering
+    //** Params : html
+    //** Return : String (HTML to send)
+    //
+    pub fn normalize(&self, html: &str) -> String {
+        if !self.config.enabled {
+            return html.to_string();
+        }
+
+        if let Some(normalized) = self.cache.lock().unwrap().get(html) {
+            return normalized;
+        }
+
+        let normalized = self.process(html);
+        self.cache.lock().unwrap().insert(html, normalized.clone());
+        normalized
+    }
+
+    fn process(&self, html: &str) -> String {
+        let (html, tags) = content::protect_tags(html);
+
+        // Inlining drops the style blocks, media queries cannot be inlined and are put back afterwards
+        let inliner = CSSInliner::options()
+            .keep_style_tags(false)
+            .load_remote_stylesheets(false)
+            .build();
+        let (inlined, media) = match inliner.inline(&html) {
+            Ok(inlined) => (inlined, media_queries(&html)),
+            Err(e) => {
+                tracing::warn!("Could not inline the CSS of an email: {}", e);
+                (html, Vec::new())
+            }
+        };
+
+        let cleaned = match self.clean(&inlined, &media) {
+            Ok(cleaned) => cleaned,
+            Err(e) => {
+                tracing::warn!("Could not clean the HTML of an email: {}", e);
+                inlined
+            }
+        };
+
+        content::restore_tags(&cleaned, &tags)
+    }
+
+    fn clean(&self, html: &str, media: &[String]) -> Result<String, lol_html::errors::RewritingError> {
+        let head_style = (!media.is_empty())
+            .then(|| format!("<style type=\"text/css\">\n{}\n</style>", media.join("\n")));
+        // Documents without a head get the media queries at the start of their body, or of the document
+        let style_written = Cell::new(false);
+
+        let cleaned = rewrite_str(html, RewriteStrSettings {
+            element_content_handlers: vec![
+                element!(STRIPPED_ELEMENTS, |el| {
+                    el.remove();
+                    Ok(())
+                }),
+                element!("*", |el| {
+                    let names: Vec<String> = el.attributes().iter().map(|attribute| attribute.name()).collect();
+                    for name in names {
+                        let value = el.get_attribute(&name).unwrap_or_default();
+                        if name.starts_with("on") || (URL_ATTRIBUTES.contains(&name.as_str()) && is_script_url(&value)) {
+                            el.remove_attribute(&name);
+                        } else if URL_ATTRIBUTES.contains(&name.as_str()) {
+                            if let Some(url) = self.absolute(&value) {
+                                el.set_attribute(&name, &url)?;
+                            }
+                        }
+                    }
+                    Ok(())
+                }),
+                element!("head", |el| {
+                    if let Some(style) = &head_style {
+                        el.append(style, ContentType::Html);
+                        style_written.set(true);
+                    }
+                    Ok(())
+                }),
+                element!("body", |el| {
+                    if let Some(style) = head_style.as_ref().filter(|_| !style_written.get()) {
+                        el.prepend(style, ContentType::Html);
+                        style_written.set(true);
+                    }
+                    Ok(())
+                }),
+            ],
+            ..RewriteStrSettings::default()
+        })?;
+
+        Ok(match head_style.filter(|_| !style_written.get()) {
+            Some(style) => format!("{}{}", style, cleaned),
+            None => cleaned,
+        })
+    }
+
+    /// Absolute form of a relative URL, None when it is already absolute, an anchor or a merge tag
+    fn absolute(&self, value: &str) -> Option<String> {
+        let base = self.base_url.as_ref()?;
+        let value = value.trim();
+        if value.is_empty() || value.starts_with('#') || value.starts_with(content::TAG_START) || Url::parse(value).is_ok() {
+            return None;
+        }
+
+        base.join(value).ok().map(String::from)
+    }
+}
+
+fn is_script_url(value: &str) -> bool {
+    let value = value.trim_start().to_ascii_lowercase();
+    value.starts_with("javascript:") || value.starts_with("vbscript:")
+}
+
+/// @media blocks of every <style> element of a document
+fn media_queries(html: &str) -> Vec<String> {
+    let mut media = Vec::new();
+    let lower = html.to_ascii_lowercase();
+    let mut offset = 0;
+
+    while let Some(start) = lower[offset..].find("<style").map(|i| offset + i) {
+        let Some(open) = lower[start..].find('>').map(|i| start + i + 1) else { break };
+        let close = lower[open..].find("</style").map(|i| open + i).unwrap_or(html.len());
+        let css = &html[open..close];
+        let css_lower = &lower[open..close];
+
+        let mut position = 0;
+        while let Some(rule) = css_lower[position..].find("@media").map(|i| position + i) {
+            let Some(body) = css[rule..].find('{').map(|i| rule + i) else { break };
+
+            let mut depth = 0;
+            let mut end = None;
+            for (i, c) in css[body..].char_indices() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(body + i + 1);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(end) = end else { break };
+            media.push(css[rule..end].trim().to_string());
+            position = end;
+        }
+
+        offset = close;
+    }
+
+    media
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline() -> HtmlPipeline {
+        HtmlPipeline::new(HtmlConfig {
+            base_url: Some("https://example.com/assets".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn inlines_css_and_cleans_the_document() {
+        let html = r#"<html><head><style>
+            p { color: red }
+            @media (max-width: 600px) { p { font-size: 18px } }
+        </style><script>alert(1)</script></head>
+        <body>
+            <p onclick="steal()">Hi {{ subscriber.name | default("there") }}</p>
+            <img src="logo.png"><a href="/about">About</a><a href="https://other.org/x">Other</a>
+            <a href="{{ unsubscribe_url }}">Unsubscribe</a><a href="javascript:alert(1)">Bad</a>
+            <form action="/subscribe"><input name="email"></form>
+        </body></html>"#;
+
+        let out = pipeline().normalize(html);
+
+        assert!(out.contains("<p style=\"color"));
+        let media = out.find("@media (max-width: 600px) { p { font-size: 18px } }").unwrap();
+        assert!(media < out.find("<body").unwrap());
+        assert!(!out.contains("<script") && !out.contains("<form") && !out.contains("<input"));
+        assert!(!out.contains("onclick") && !out.contains("javascript:"));
+        assert!(out.contains("src=\"https://example.com/assets/logo.png\""));
+        assert!(out.contains("href=\"https://example.com/about\""));
+        assert!(out.contains("href=\"https://other.org/x\""));
+        assert!(out.contains("href=\"{{ unsubscribe_url }}\""));
+        assert!(out.contains("Hi {{ subscriber.name | default(\"there\") }}"));
+    }
+
+    #[test]
+    fn keeps_media_queries_of_fragments() {
+        let fragment = "<style>@media (max-width: 600px) { p { font-size: 18px } }</style><p>Hello</p>";
+        let out = pipeline().normalize(fragment);
+        assert!(out.contains("@media (max-width: 600px) { p { font-size: 18px } }"));
+
+        let body_only = "<html><body><style>@media print { p { color: black } }</style><p>Hello</p></body></html>";
+        let out = pipeline().normalize(body_only);
+        let media = out.find("@media print { p { color: black } }").unwrap();
+        assert!(media < out.find("<p").unwrap());
+    }
+
+    #[test]
+    fn caches_normalized_bodies() {
+        let pipeline = pipeline();
+        let first = pipeline.normalize("<style>b { color: blue }</style><b>Hello</b>");
+        let second = pipeline.clone().normalize("<style>b { color: blue }</style><b>Hello</b>");
+
+        assert_eq!(first, second);
+        assert_eq!(pipeline.cache.lock().unwrap().entries.len(), 1);
+
+        let disabled = HtmlPipeline::new(HtmlConfig { enabled: false, ..Default::default() });
+        assert_eq!(disabled.normalize("<script>x</script>"), "<script>x</script>");
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_body() {
+        let mut cache = BodyCache::new(2);
+        cache.insert("a", "A".to_string());
+        cache.insert("b", "B".to_string());
+        assert_eq!(cache.get("a").as_deref(), Some("A"));
+
+        cache.insert("c", "C".to_string());
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("A"));
+        assert_eq!(cache.get("c").as_deref(), Some("C"));
+    }
+}
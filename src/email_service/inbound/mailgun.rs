@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use crate::email_service::error::EmailError;
+use crate::email_service::inbound::{bare_message_id, check_timestamp, outbox_id, InboundEvent};
+use crate::models::bounce::BounceType;
+use crate::models::provider_event::ProviderEventType;
+
+const PROVIDER: &str = "mailgun";
+
+#[derive(Debug, Deserialize)]
+pub struct MailgunWebhook {
+    signature: MailgunSignature,
+    #[serde(rename = "event-data")]
+    event_data: MailgunEvent,
+}
+
+#[derive(Debug, Deserialize)]
+struct MailgunSignature {
+    timestamp: String,
+    token: String,
+    signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MailgunEvent {
+    id: Option<String>,
+    event: String,
+    // permanent or temporary, for failed events
+    severity: Option<String>,
+    recipient: String,
+    timestamp: Option<f64>,
+    reason: Option<String>,
+    url: Option<String>,
+    ip: Option<String>,
+    message: Option<MailgunMessage>,
+    #[serde(rename = "delivery-status")]
+    delivery_status: Option<MailgunDeliveryStatus>,
+    #[serde(rename = "client-info")]
+    client_info: Option<MailgunClientInfo>,
+    #[serde(rename = "user-variables", default)]
+    user_variables: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MailgunMessage {
+    #[serde(default)]
+    headers: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MailgunDeliveryStatus {
+    #[serde(rename = "enhanced-code")]
+    enhanced_code: Option<String>,
+    message: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MailgunClientInfo {
+    #[serde(rename = "user-agent")]
+    user_agent: Option<String>,
+}
+
+impl MailgunWebhook {
+    pub fn parse(body: &[u8]) -> Result<Self, EmailError> {
+        serde_json::from_slice(body).map_err(|e| EmailError::MessageError(format!("Invalid Mailgun webhook: {}", e)))
+    }
+
+    //
+    //** Check the webhook signature, an HMAC-SHA256 of timestamp and token with the webhook signing key
+    //** Params : signing_key , max_age_secs
+    //
+    pub fn verify(&self, signing_key: &str, max_age_secs: i64) -> Result<(), EmailError> {
+        let invalid = |e: &str| EmailError::InvalidSignature(format!("Mailgun webhook: {}", e));
+        let signature = &self.signature;
+
+        let expected = hex::decode(signature.signature.trim()).map_err(|_| invalid("signature is not hex"))?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(signing_key.as_bytes()).map_err(|_| invalid("invalid signing key"))?;
+        mac.update(signature.timestamp.as_bytes());
+        mac.update(signature.token.as_bytes());
+        mac.verify_slice(&expected).map_err(|_| invalid("signature mismatch"))?;
+
+        let timestamp = signature.timestamp.trim().parse().map_err(|_| invalid("invalid timestamp"))?;
+        check_timestamp(timestamp, max_age_secs)
+    }
+
+    /// Normalized event, None for events that are not tracked (accepted, stored...)
+    pub fn event(self) -> Option<InboundEvent> {
+        let data = self.event_data;
+        let mut inbound = match (data.event.as_str(), data.severity.as_deref()) {
+            ("delivered", _) => InboundEvent::new(PROVIDER, ProviderEventType::Delivered, &data.recipient),
+            ("failed", Some("permanent")) if data.reason.as_deref() == Some("suppress-bounce") => {
+                // Not attempted, the address is on the Mailgun bounce list
+                InboundEvent::new(PROVIDER, ProviderEventType::Dropped, &data.recipient)
+            }
+            ("failed", Some("permanent")) => InboundEvent::bounce(PROVIDER, BounceType::Hard, &data.recipient),
+            ("failed", _) => InboundEvent::new(PROVIDER, ProviderEventType::Deferred, &data.recipient),
+            ("rejected", _) => InboundEvent::new(PROVIDER, ProviderEventType::Dropped, &data.recipient),
+            ("complained", _) => InboundEvent::new(PROVIDER, ProviderEventType::Complaint, &data.recipient),
+            ("opened", _) => InboundEvent::new(PROVIDER, ProviderEventType::Open, &data.recipient),
+            ("clicked", _) => InboundEvent::new(PROVIDER, ProviderEventType::Click, &data.recipient),
+            ("unsubscribed", _) => InboundEvent::new(PROVIDER, ProviderEventType::Unsubscribe, &data.recipient),
+            _ => return None,
+        };
+
+        inbound.message_id = data.message.as_ref()
+            .and_then(|message| message.headers.get("message-id"))
+            .and_then(|id| id.as_str())
+            .and_then(bare_message_id);
+        inbound.event_id = data.id;
+        inbound.outbox_id = data.user_variables.get("outbox_id").and_then(outbox_id);
+        if let Some(status) = data.delivery_status {
+            inbound.status_code = status.enhanced_code;
+            inbound.diagnostic = status.message.filter(|m| !m.is_empty()).or(status.description);
+        }
+        inbound.url = data.url;
+        inbound.user_agent = data.client_info.and_then(|info| info.user_agent);
+        inbound.ip_address = data.ip;
+        inbound.occurred_at = data.timestamp
+            .and_then(|timestamp| DateTime::<Utc>::from_timestamp(timestamp as i64, 0))
+            .unwrap_or_else(Utc::now);
+        Some(inbound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook(key: &str, timestamp: i64) -> String {
+        let token = "a8ce0edb2dd8301dee6c2405235584e45aa91d1e9f979f3de0";
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+        mac.update(format!("{}{}", timestamp, token).as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        format!(r#"{{
+            "signature": {{"timestamp": "{}", "token": "{}", "signature": "{}"}},
+            "event-data": {{
+                "id": "G9Bn5sl1TC6nu79C8C0bwg", "event": "failed", "severity": "permanent", "recipient": "Gone@example.org", "timestamp": 1760788800.5,
+                "message": {{"headers": {{"message-id": "20261018.1@mg.example.com"}}}},
+                "delivery-status": {{"code": 550, "enhanced-code": "5.1.1", "message": "", "description": "No such user"}},
+                "user-variables": {{"outbox_id": 9}}
+            }}
+        }}"#, timestamp, token, signature)
+    }
+
+    #[test]
+    fn verifies_and_parses_webhooks() {
+        let body = webhook("key-secret", Utc::now().timestamp());
+        let hook = MailgunWebhook::parse(body.as_bytes()).unwrap();
+        assert!(hook.verify("key-secret", 300).is_ok());
+        assert!(hook.verify("other-key", 300).is_err());
+
+        let event = hook.event().unwrap();
+        assert_eq!(event.bounce_type, Some(BounceType::Hard));
+        assert_eq!(event.email, "gone@example.org");
+        assert_eq!(event.message_id.as_deref(), Some("20261018.1@mg.example.com"));
+        assert_eq!(event.outbox_id, Some(9));
+        assert_eq!(event.event_id.as_deref(), Some("G9Bn5sl1TC6nu79C8C0bwg"));
+        assert_eq!(event.diagnostic.as_deref(), Some("No such user"));
+
+        let stale = MailgunWebhook::parse(webhook("key-secret", 1000).as_bytes()).unwrap();
+        assert!(stale.verify("key-secret", 300).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use crate::email_service::error::EmailError;
+use crate::models::bounce::BounceType;
+use crate::models::provider_event::ProviderEventType;
+
+pub mod sns;
+pub mod sendgrid;
+pub mod mailgun;
+
+pub use self::sns::parse_ses;
+
+//
+//** Delivery event reported by a provider webhook, normalized across providers
+//** The message is found by outbox id when the provider echoes the `outbox_id` custom argument
+//** (SendGrid unique args, Mailgun user variables, SES message tags), else by Message-ID
+//
+#[derive(Debug, Clone)]
+pub struct InboundEvent {
+    pub provider: &'static str,
+    // Id of the event at the provider, redelivered events are only recorded once
+    pub event_id: Option<String>,
+    pub event_type: ProviderEventType,
+    // Set for bounces only
+    pub bounce_type: Option<BounceType>,
+    pub email: String,
+    pub message_id: Option<String>,
+    pub outbox_id: Option<i64>,
+    pub status_code: Option<String>,
+    pub diagnostic: Option<String>,
+    // Clicked link
+    pub url: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl InboundEvent {
+    pub fn new(provider: &'static str, event_type: ProviderEventType, email: impl Into<String>) -> Self {
+        Self {
+            provider,
+            event_id: None,
+            event_type,
+            bounce_type: None,
+            email: email.into().trim().to_lowercase(),
+            message_id: None,
+            outbox_id: None,
+            status_code: None,
+            diagnostic: None,
+            url: None,
+            user_agent: None,
+            ip_address: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn bounce(provider: &'static str, bounce_type: BounceType, email: impl Into<String>) -> Self {
+        Self {
+            bounce_type: Some(bounce_type),
+            ..Self::new(provider, ProviderEventType::Bounce, email)
+        }
+    }
+}
+
+/// Strip the angle brackets of a Message-ID
+pub(crate) fn bare_message_id(id: &str) -> Option<String> {
+    let id = id.trim().trim_start_matches('<').trim_end_matches('>').trim();
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+/// Outbox id echoed back as a custom argument, providers send it as a string or a number
+pub(crate) fn outbox_id(value: &serde_json::Value) -> Option<i64> {
+    match value {
+        serde_json::Value::Number(number) => number.as_i64(),
+        serde_json::Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Refuse signed timestamps too far from now, replayed requests included
+pub(crate) fn check_timestamp(timestamp: i64, max_age_secs: i64) -> Result<(), EmailError> {
+    if (Utc::now().timestamp() - timestamp).abs() > max_age_secs {
+        return Err(EmailError::InvalidSignature("Signature timestamp is too old".to_string()));
+    }
+    Ok(())
+}
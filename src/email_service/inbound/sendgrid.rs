@@ -0,0 +1,153 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+use serde::Deserialize;
+use crate::email_service::error::EmailError;
+use crate::email_service::inbound::{bare_message_id, check_timestamp, outbox_id, InboundEvent};
+use crate::models::bounce::BounceType;
+use crate::models::provider_event::ProviderEventType;
+
+const PROVIDER: &str = "sendgrid";
+
+pub const SIGNATURE_HEADER: &str = "X-Twilio-Email-Event-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Twilio-Email-Event-Webhook-Timestamp";
+
+//
+//** Check a SendGrid signed event webhook request
+//** The ECDSA P-256 signature covers the timestamp header followed by the raw body
+//** Params : public_key (base64 DER, from the SendGrid settings) , signature , timestamp , body , max_age_secs
+//
+pub fn verify(public_key: &str, signature: &str, timestamp: &str, body: &[u8], max_age_secs: i64) -> Result<(), EmailError> {
+    let invalid = |e: String| EmailError::InvalidSignature(format!("SendGrid event webhook: {}", e));
+
+    let key = BASE64.decode(public_key.trim()).map_err(|e| invalid(e.to_string()))?;
+    let key = VerifyingKey::from_public_key_der(&key).map_err(|e| invalid(e.to_string()))?;
+    let signature = BASE64.decode(signature.trim()).map_err(|e| invalid(e.to_string()))?;
+    let signature = Signature::from_der(&signature).map_err(|e| invalid(e.to_string()))?;
+
+    let mut signed = timestamp.as_bytes().to_vec();
+    signed.extend_from_slice(body);
+    key.verify(&signed, &signature).map_err(|e| invalid(e.to_string()))?;
+
+    let timestamp = timestamp.trim().parse().map_err(|_| invalid("invalid timestamp".to_string()))?;
+    check_timestamp(timestamp, max_age_secs)
+}
+
+#[derive(Debug, Deserialize)]
+struct SendGridEvent {
+    email: String,
+    event: String,
+    timestamp: Option<i64>,
+    sg_event_id: Option<String>,
+    sg_message_id: Option<String>,
+    #[serde(rename = "smtp-id")]
+    smtp_id: Option<String>,
+    // bounce or blocked, for bounce events
+    #[serde(rename = "type")]
+    bounce_type: Option<String>,
+    status: Option<String>,
+    reason: Option<String>,
+    response: Option<String>,
+    url: Option<String>,
+    useragent: Option<String>,
+    ip: Option<String>,
+    // Custom argument set when sending
+    outbox_id: Option<serde_json::Value>,
+}
+
+//
+//** Parse a batch of SendGrid events
+//** Return : the tracked events, processed and group resubscribe events are left out
+//
+pub fn parse(body: &[u8]) -> Result<Vec<InboundEvent>, EmailError> {
+    let events: Vec<SendGridEvent> = serde_json::from_slice(body)
+        .map_err(|e| EmailError::MessageError(format!("Invalid SendGrid events: {}", e)))?;
+
+    Ok(events.into_iter().filter_map(|event| {
+        let mut inbound = match event.event.as_str() {
+            "delivered" => InboundEvent::new(PROVIDER, ProviderEventType::Delivered, &event.email),
+            "deferred" => InboundEvent::new(PROVIDER, ProviderEventType::Deferred, &event.email),
+            "bounce" => {
+                // Blocked messages are refused for now (reputation, content), not for the address
+                let bounce_type = match event.bounce_type.as_deref() {
+                    Some("blocked") => BounceType::Soft,
+                    _ => BounceType::Hard,
+                };
+                InboundEvent::bounce(PROVIDER, bounce_type, &event.email)
+            }
+            "dropped" => InboundEvent::new(PROVIDER, ProviderEventType::Dropped, &event.email),
+            "spamreport" => InboundEvent::new(PROVIDER, ProviderEventType::Complaint, &event.email),
+            "open" => InboundEvent::new(PROVIDER, ProviderEventType::Open, &event.email),
+            "click" => InboundEvent::new(PROVIDER, ProviderEventType::Click, &event.email),
+            "unsubscribe" | "group_unsubscribe" => InboundEvent::new(PROVIDER, ProviderEventType::Unsubscribe, &event.email),
+            _ => return None,
+        };
+
+        // smtp-id is the Message-ID header, sg_message_id the X-Message-Id of the API send plus a filter suffix
+        inbound.message_id = event.smtp_id.as_deref().and_then(bare_message_id).or_else(|| {
+            event.sg_message_id.as_deref()
+                .and_then(|id| id.split(".filter").next())
+                .and_then(bare_message_id)
+        });
+        inbound.event_id = event.sg_event_id;
+        inbound.outbox_id = event.outbox_id.as_ref().and_then(outbox_id);
+        inbound.status_code = event.status;
+        inbound.diagnostic = event.reason.or(event.response);
+        inbound.url = event.url;
+        inbound.user_agent = event.useragent;
+        inbound.ip_address = event.ip;
+        inbound.occurred_at = event.timestamp
+            .and_then(|timestamp| DateTime::<Utc>::from_timestamp(timestamp, 0))
+            .unwrap_or_else(Utc::now);
+        Some(inbound)
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+    use p256::pkcs8::EncodePublicKey;
+
+    const EVENTS: &str = r#"[
+        {"email": "one@example.org", "timestamp": 1760788800, "event": "processed", "sg_message_id": "abc.filter001"},
+        {"email": "one@example.org", "timestamp": 1760788801, "event": "bounce", "type": "bounce", "status": "5.1.1", "sg_event_id": "ev1",
+         "reason": "550 unknown user", "smtp-id": "<xyz@news.example.com>", "outbox_id": "17"},
+        {"email": "two@example.org", "timestamp": 1760788802, "event": "click", "url": "https://example.com/",
+         "sg_message_id": "def.filter002.123"}
+    ]"#;
+
+    #[test]
+    fn verifies_signed_events() {
+        let signing_key = SigningKey::random(&mut rand::rngs::OsRng);
+        let public_key = signing_key.verifying_key().to_public_key_der().unwrap();
+        let public_key = BASE64.encode(public_key.as_bytes());
+
+        let timestamp = Utc::now().timestamp().to_string();
+        let mut signed = timestamp.as_bytes().to_vec();
+        signed.extend_from_slice(EVENTS.as_bytes());
+        let signature: Signature = signing_key.sign(&signed);
+        let signature = BASE64.encode(signature.to_der().as_bytes());
+
+        assert!(verify(&public_key, &signature, &timestamp, EVENTS.as_bytes(), 300).is_ok());
+        assert!(verify(&public_key, &signature, &timestamp, b"[]", 300).is_err());
+        assert!(verify(&public_key, &signature, "1000", EVENTS.as_bytes(), 300).is_err());
+    }
+
+    #[test]
+    fn parses_events() {
+        let events = parse(EVENTS.as_bytes()).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].bounce_type, Some(BounceType::Hard));
+        assert_eq!(events[0].message_id.as_deref(), Some("xyz@news.example.com"));
+        assert_eq!(events[0].outbox_id, Some(17));
+        assert_eq!(events[0].event_id.as_deref(), Some("ev1"));
+        assert_eq!(events[1].event_type, ProviderEventType::Click);
+        assert_eq!(events[1].message_id.as_deref(), Some("def"));
+        assert_eq!(events[1].url.as_deref(), Some("https://example.com/"));
+    }
+}
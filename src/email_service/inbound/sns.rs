@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use serde::Deserialize;
+use sha2::Sha256;
+use x509_cert::der::{DecodePem, Encode};
+use x509_cert::Certificate;
+use crate::email_service::error::EmailError;
+use crate::email_service::inbound::{bare_message_id, check_timestamp, outbox_id, InboundEvent};
+use crate::models::bounce::BounceType;
+use crate::models::provider_event::ProviderEventType;
+
+const PROVIDER: &str = "ses";
+
+/// SNS HTTP(S) message envelope
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SnsMessage {
+    #[serde(rename = "Type")]
+    pub kind: String,
+    pub message_id: String,
+    pub topic_arn: String,
+    pub subject: Option<String>,
+    pub message: String,
+    pub timestamp: String,
+    pub signature_version: String,
+    pub signature: String,
+    #[serde(rename = "SigningCertURL")]
+    pub signing_cert_url: String,
+    #[serde(rename = "SubscribeURL")]
+    pub subscribe_url: Option<String>,
+    pub token: Option<String>,
+}
+
+impl SnsMessage {
+    pub fn parse(raw: &[u8]) -> Result<Self, EmailError> {
+        serde_json::from_slice(raw).map_err(|e| EmailError::MessageError(format!("Invalid SNS message: {}", e)))
+    }
+
+    pub fn is_subscription_confirmation(&self) -> bool {
+        self.kind == "SubscriptionConfirmation"
+    }
+
+    //
+    //** String signed by SNS, "Name\nvalue\n" for each signed field in this order
+    //** Notifications sign Subject when present, (un)subscription confirmations sign SubscribeURL and Token
+    //
+    fn string_to_sign(&self) -> String {
+        let mut fields: Vec<(&str, &str)> = vec![("Message", self.message.as_str()), ("MessageId", self.message_id.as_str())];
+
+        if self.kind == "Notification" {
+            if let Some(subject) = &self.subject {
+                fields.push(("Subject", subject.as_str()));
+            }
+            fields.push(("Timestamp", self.timestamp.as_str()));
+        } else {
+            fields.push(("SubscribeURL", self.subscribe_url.as_deref().unwrap_or_default()));
+            fields.push(("Timestamp", self.timestamp.as_str()));
+            fields.push(("Token", self.token.as_deref().unwrap_or_default()));
+        }
+        fields.push(("TopicArn", self.topic_arn.as_str()));
+        fields.push(("Type", self.kind.as_str()));
+
+        fields.iter().map(|(name, value)| format!("{}\n{}\n", name, value)).collect()
+    }
+
+    //
+    //** SES events carried by a notification
+    //** One message may hold several recipients, their event ids are the SNS MessageId and the address
+    //
+    pub fn events(&self) -> Result<Vec<InboundEvent>, EmailError> {
+        let mut events = parse_ses(&self.message)?;
+        for event in &mut events {
+            event.event_id = Some(format!("{}:{}", self.message_id, event.email));
+        }
+        Ok(events)
+    }
+
+    /// Refuse messages signed more than max_age_secs ago, SNS signs its Timestamp
+    pub fn check_freshness(&self, max_age_secs: i64) -> Result<(), EmailError> {
+        let timestamp = DateTime::parse_from_rfc3339(&self.timestamp)
+            .map_err(|e| EmailError::InvalidSignature(format!("SNS message {}: invalid timestamp: {}", self.message_id, e)))?;
+        check_timestamp(timestamp.timestamp(), max_age_secs)
+    }
+
+    /// Check the signature with the public key of the signing certificate
+    pub fn verify_with(&self, key: &RsaPublicKey) -> Result<(), EmailError> {
+        let invalid = |e: String| EmailError::InvalidSignature(format!("SNS message {}: {}", self.message_id, e));
+
+        let signature = BASE64.decode(self.signature.trim()).map_err(|e| invalid(e.to_string()))?;
+        let signature = Signature::try_from(signature.as_slice()).map_err(|e| invalid(e.to_string()))?;
+        let signed = self.string_to_sign();
+
+        match self.signature_version.as_str() {
+            "1" => VerifyingKey::<sha1::Sha1>::new(key.clone()).verify(signed.as_bytes(), &signature),
+            "2" => VerifyingKey::<Sha256>::new(key.clone()).verify(signed.as_bytes(), &signature),
+            version => return Err(invalid(format!("unsupported signature version {}", version))),
+        }
+        .map_err(|e| invalid(e.to_string()))
+    }
+}
+
+/// SNS certificates and subscription URLs are only ever served by sns.<region>.amazonaws.com
+pub fn is_sns_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or_default();
+    let region = host
+        .strip_prefix("sns.")
+        .and_then(|rest| rest.strip_suffix(".amazonaws.com").or_else(|| rest.strip_suffix(".amazonaws.com.cn")));
+
+    url.scheme() == "https"
+        && region.is_some_and(|region| !region.is_empty() && region.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+//
+//** Checks SNS signatures, the signing certificates are downloaded once and kept in memory
+//
+pub struct SnsVerifier {
+    client: reqwest::Client,
+    keys: RwLock<HashMap<String, RsaPublicKey>>,
+}
+
+impl Default for SnsVerifier {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl SnsVerifier {
+    /// Check the signature and that the message is not older than max_age_secs
+    pub async fn verify(&self, message: &SnsMessage, max_age_secs: i64) -> Result<(), EmailError> {
+        let key = self.signing_key(&message.signing_cert_url).await?;
+        message.verify_with(&key)?;
+        message.check_freshness(max_age_secs)
+    }
+
+    async fn signing_key(&self, url: &str) -> Result<RsaPublicKey, EmailError> {
+        if !is_sns_url(url) {
+            return Err(EmailError::InvalidSignature(format!("Signing certificate not served by SNS: {}", url)));
+        }
+        if let Some(key) = self.keys.read().unwrap().get(url) {
+            return Ok(key.clone());
+        }
+
+        let pem = self.client.get(url).send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| EmailError::TemporaryFailure(format!("Failed to download {}: {}", url, e)))?
+            .text().await
+            .map_err(|e| EmailError::TemporaryFailure(format!("Failed to download {}: {}", url, e)))?;
+
+        let invalid = |e: String| EmailError::InvalidSignature(format!("Invalid signing certificate {}: {}", url, e));
+        let certificate = Certificate::from_pem(pem.as_bytes()).map_err(|e| invalid(e.to_string()))?;
+        let spki = certificate.tbs_certificate.subject_public_key_info.to_der().map_err(|e| invalid(e.to_string()))?;
+        let key = RsaPublicKey::from_public_key_der(&spki).map_err(|e| invalid(e.to_string()))?;
+
+        self.keys.write().unwrap().insert(url.to_string(), key.clone());
+        Ok(key)
+    }
+
+    /// Confirm a subscription by following its SubscribeURL
+    pub async fn confirm_subscription(&self, message: &SnsMessage) -> Result<(), EmailError> {
+        let url = message.subscribe_url.as_deref()
+            .filter(|url| is_sns_url(url))
+            .ok_or_else(|| EmailError::MessageError("Missing or foreign SubscribeURL".to_string()))?;
+
+        self.client.get(url).send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| EmailError::TemporaryFailure(format!("Failed to confirm SNS subscription: {}", e)))?;
+
+        tracing::info!("Confirmed SNS subscription to {}", message.topic_arn);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesNotification {
+    // Event publishing (configuration sets)
+    event_type: Option<String>,
+    // Identity notifications
+    notification_type: Option<String>,
+    mail: SesMail,
+    bounce: Option<SesBounce>,
+    complaint: Option<SesComplaint>,
+    delivery: Option<SesDelivery>,
+    open: Option<SesEngagement>,
+    click: Option<SesEngagement>,
+    delivery_delay: Option<SesDeliveryDelay>,
+    reject: Option<SesReject>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesMail {
+    message_id: String,
+    timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    destination: Vec<String>,
+    #[serde(default)]
+    tags: HashMap<String, Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesRecipient {
+    email_address: String,
+    status: Option<String>,
+    diagnostic_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesBounce {
+    // Permanent, Transient or Undetermined
+    bounce_type: String,
+    bounced_recipients: Vec<SesRecipient>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesComplaint {
+    complained_recipients: Vec<SesRecipient>,
+    complaint_feedback_type: Option<String>,
+    user_agent: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesDelivery {
+    recipients: Vec<String>,
+    smtp_response: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesEngagement {
+    link: Option<String>,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesDeliveryDelay {
+    delayed_recipients: Vec<SesRecipient>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SesReject {
+    reason: Option<String>,
+}
+
+//
+//** Parse the SES notification carried by an SNS message
+//** Return : one event per recipient, none for event types that are not tracked (Send, Rendering Failure...)
+//
+pub fn parse_ses(message: &str) -> Result<Vec<InboundEvent>, EmailError> {
+    let notification: SesNotification = serde_json::from_str(message)
+        .map_err(|e| EmailError::MessageError(format!("Invalid SES notification: {}", e)))?;
+
+    let mail = &notification.mail;
+    let message_id = bare_message_id(&mail.message_id);
+    let outbox_id = mail.tags.get("outbox_id").and_then(|values| values.first()).and_then(outbox_id);
+    let event = |mut event: InboundEvent, timestamp: Option<DateTime<Utc>>| {
+        event.message_id = message_id.clone();
+        event.outbox_id = outbox_id;
+        event.occurred_at = timestamp.or(mail.timestamp).unwrap_or_else(Utc::now);
+        event
+    };
+
+    let kind = notification.event_type.or(notification.notification_type).unwrap_or_default();
+    let events = match kind.as_str() {
+        "Bounce" => {
+            let Some(bounce) = notification.bounce else { return Ok(Vec::new()) };
+            let bounce_type = if bounce.bounce_type == "Permanent" { BounceType::Hard } else { BounceType::Soft };
+            bounce.bounced_recipients.into_iter()
+                .map(|recipient| {
+                    let mut bounced = InboundEvent::bounce(PROVIDER, bounce_type, recipient.email_address);
+                    bounced.status_code = recipient.status;
+                    bounced.diagnostic = recipient.diagnostic_code;
+                    event(bounced, bounce.timestamp)
+                })
+                .collect()
+        }
+        "Complaint" => {
+            let Some(complaint) = notification.complaint else { return Ok(Vec::new()) };
+            if complaint.complaint_feedback_type.as_deref() == Some("not-spam") {
+                return Ok(Vec::new());
+            }
+            complaint.complained_recipients.into_iter()
+                .map(|recipient| {
+                    let mut complained = InboundEvent::new(PROVIDER, ProviderEventType::Complaint, recipient.email_address);
+                    complained.user_agent = complaint.user_agent.clone();
+                    event(complained, complaint.timestamp)
+                })
+                .collect()
+        }
+        "Delivery" => {
+            let Some(delivery) = notification.delivery else { return Ok(Vec::new()) };
+            delivery.recipients.into_iter()
+                .map(|recipient| {
+                    let mut delivered = InboundEvent::new(PROVIDER, ProviderEventType::Delivered, recipient);
+                    delivered.diagnostic = delivery.smtp_response.clone();
+                    event(delivered, delivery.timestamp)
+                })
+                .collect()
+        }
+        "DeliveryDelay" => {
+            let Some(delay) = notification.delivery_delay else { return Ok(Vec::new()) };
+            delay.delayed_recipients.into_iter()
+                .map(|recipient| {
+                    let mut deferred = InboundEvent::new(PROVIDER, ProviderEventType::Deferred, recipient.email_address);
+                    deferred.status_code = recipient.status;
+                    deferred.diagnostic = recipient.diagnostic_code;
+                    event(deferred, delay.timestamp)
+                })
+                .collect()
+        }
+        "Reject" => mail.destination.iter()
+            .map(|recipient| {
+                let mut dropped = InboundEvent::new(PROVIDER, ProviderEventType::Dropped, recipient.clone());
+                dropped.diagnostic = notification.reject.as_ref().and_then(|reject| reject.reason.clone());
+                event(dropped, None)
+            })
+            .collect(),
+        "Open" | "Click" => {
+            let (event_type, engagement) = match kind.as_str() {
+                "Open" => (ProviderEventType::Open, notification.open),
+                _ => (ProviderEventType::Click, notification.click),
+            };
+            let Some(engagement) = engagement else { return Ok(Vec::new()) };
+            mail.destination.iter()
+                .map(|recipient| {
+                    let mut engaged = InboundEvent::new(PROVIDER, event_type, recipient.clone());
+                    engaged.url = engagement.link.clone();
+                    engaged.user_agent = engagement.user_agent.clone();
+                    engaged.ip_address = engagement.ip_address.clone();
+                    event(engaged, engagement.timestamp)
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::RsaPrivateKey;
+
+    const BOUNCE: &str = r#"{
+        "notificationType": "Bounce",
+        "bounce": {
+            "bounceType": "Permanent",
+            "bouncedRecipients": [{"emailAddress": "Gone@example.org", "status": "5.1.1", "diagnosticCode": "smtp; 550 5.1.1 unknown"}],
+            "timestamp": "2026-10-18T12:00:00.000Z"
+        },
+        "mail": {"messageId": "0100018abc", "timestamp": "2026-10-18T11:59:00.000Z", "destination": ["Gone@example.org"], "tags": {"outbox_id": ["42"]}}
+    }"#;
+
+    fn message(kind: &str, message: &str) -> SnsMessage {
+        SnsMessage {
+            kind: kind.to_string(),
+            message_id: "a1b2".to_string(),
+            topic_arn: "arn:aws:sns:eu-west-1:123456789012:ses-events".to_string(),
+            subject: None,
+            message: message.to_string(),
+            timestamp: "2026-10-18T12:00:01.000Z".to_string(),
+            signature_version: "2".to_string(),
+            signature: String::new(),
+            signing_cert_url: "https://sns.eu-west-1.amazonaws.com/SimpleNotificationService-abc.pem".to_string(),
+            subscribe_url: None,
+            token: None,
+        }
+    }
+
+    #[test]
+    fn verifies_signatures() {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let public_key = RsaPublicKey::from(&private_key);
+
+        let mut sns = message("Notification", BOUNCE);
+        let signature = SigningKey::<Sha256>::new(private_key).sign(sns.string_to_sign().as_bytes());
+        sns.signature = BASE64.encode(signature.to_bytes());
+        assert!(sns.verify_with(&public_key).is_ok());
+
+        sns.message.push(' ');
+        assert!(sns.verify_with(&public_key).is_err());
+
+        // Signed long ago, a replay
+        sns.timestamp = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        assert!(matches!(sns.check_freshness(300), Err(EmailError::InvalidSignature(_))));
+        sns.timestamp = Utc::now().to_rfc3339();
+        assert!(sns.check_freshness(300).is_ok());
+
+        assert!(is_sns_url(&sns.signing_cert_url));
+        assert!(!is_sns_url("https://sns.eu-west-1.amazonaws.com.evil.example/cert.pem"));
+        assert!(!is_sns_url("http://sns.eu-west-1.amazonaws.com/cert.pem"));
+    }
+
+    #[test]
+    fn parses_ses_notifications() {
+        let events = message("Notification", BOUNCE).events().unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, ProviderEventType::Bounce);
+        assert_eq!(events[0].bounce_type, Some(BounceType::Hard));
+        assert_eq!(events[0].email, "gone@example.org");
+        assert_eq!(events[0].message_id.as_deref(), Some("0100018abc"));
+        assert_eq!(events[0].outbox_id, Some(42));
+        assert_eq!(events[0].status_code.as_deref(), Some("5.1.1"));
+        assert_eq!(events[0].event_id.as_deref(), Some("a1b2:gone@example.org"));
+
+        let send = r#"{"eventType": "Send", "mail": {"messageId": "x"}, "send": {}}"#;
+        assert!(parse_ses(send).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::models::template::Template;
+
+/// Where the email content goes in a layout template
+pub const CONTENT_PLACEHOLDER: &str = "{{ content }}";
+
+/// Position and length of the first {{ content }} placeholder, whatever the spacing inside the braces
+fn find_placeholder(layout: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+
+    while let Some(start) = layout[offset..].find("{{").map(|i| offset + i) {
+        let end = layout[start..].find("}}").map(|i| start + i + 2)?;
+        if layout[start + 2..end - 2].trim() == "content" {
+            return Some((start, end - start));
+        }
+        offset = start + 2;
+    }
+
+    None
+}
+
+pub fn has_placeholder(layout: &str) -> bool {
+    find_placeholder(layout).is_some()
+}
+
+/// Put `content` in place of the placeholder, None when the layout has none
+pub fn wrap(layout: &str, content: &str) -> Option<String> {
+    let (start, len) = find_placeholder(layout)?;
+    Some(format!("{}{}{}", &layout[..start], content, &layout[start + len..]))
+}
+
+//
+//** Wrap an email body in a layout template
+//** A template without placeholder is not a layout, the body is then sent as it is
+//** Params : layout , content
+//** Return : String (body to send)
+//
+pub fn apply(layout: &Template, content: &str) -> String {
+    match wrap(&layout.body, content) {
+        Some(body) => body,
+        None => {
+            tracing::warn!("Template {} has no {} placeholder, not used as a layout", layout.id, CONTENT_PLACEHOLDER);
+            content.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_content_at_the_placeholder() {
+        let layout = "<html><body>{{ subscriber.name }}<header/>{{content}}<footer>{{ unsubscribe_url }}</footer></body></html>";
+
+        assert_eq!(
+            wrap(layout, "<p>Hi</p>").as_deref(),
+            Some("<html><body>{{ subscriber.name }}<header/><p>Hi</p><footer>{{ unsubscribe_url }}</footer></body></html>")
+        );
+        assert_eq!(wrap("<div>{{  content }}</div>", "x").as_deref(), Some("<div>x</div>"));
+        // Only the first placeholder is replaced
+        assert_eq!(wrap("{{ content }}|{{ content }}", "x").as_deref(), Some("x|{{ content }}"));
+
+        assert!(!has_placeholder("<p>{{ contents }} {{ subscriber.content }}</p>"));
+        assert_eq!(wrap("<p>no layout</p>", "x"), None);
+    }
+}
@@ -0,0 +1,35 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Reader of a Maildir fed by the MTA (bounce or feedback loop mailbox)
+#[derive(Debug, Clone)]
+pub struct MaildirReader {
+    dir: PathBuf,
+}
+
+impl MaildirReader {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Messages waiting in new/
+    pub async fn unread(&self) -> io::Result<Vec<PathBuf>> {
+        let mut entries = tokio::fs::read_dir(self.dir.join("new")).await?;
+        let mut paths = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            paths.push(entry.path());
+        }
+        Ok(paths)
+    }
+
+    /// Move a message to cur/ with the Seen flag so it is not read again
+    pub async fn mark_read(&self, path: &Path) -> io::Result<()> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let unique = name.split(':').next().unwrap_or(&name);
+        tokio::fs::rename(path, self.dir.join("cur").join(format!("{}:2,S", unique))).await
+    }
+}
@@ -0,0 +1,139 @@
+use minijinja::{escape_formatter, AutoEscape, Environment, UndefinedBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use crate::email_service::{content::escape_html, error::EmailError, models::EmailRequest};
+
+//
+//** What merge tags can use in a campaign message, per recipient
+//** {{ subscriber.name }} , {{ subscriber.email }} , {{ subscriber.attribs.<key> }} ,
+//** {{ campaign.name }} , {{ list.name }} (first list of the campaign the subscriber is on) ,
+//** {{ unsubscribe_url }} and {{ preferences_url }}
+//
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MergeContext {
+    pub subscriber: JsonValue,
+    pub campaign: JsonValue,
+    // Left out when unset, undefined values chain without errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<JsonValue>,
+    pub lists: Vec<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsubscribe_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferences_url: Option<String>,
+}
+
+/// Whether a text holds merge tags or template statements at all
+pub fn has_tags(text: &str) -> bool {
+    text.contains("{{") || text.contains("{%")
+}
+
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    // Missing attributes render empty, even nested ones, so `default` can fill them in
+    env.set_undefined_behavior(UndefinedBehavior::Chainable);
+    // Null attributes and unset URLs render empty rather than "none".
+    // Only & < > " ' are escaped, minijinja would also turn `/` into &#x2f; and mangle URLs
+    env.set_formatter(|out, state, value| {
+        if value.is_none() {
+            Ok(())
+        } else if matches!(state.auto_escape(), AutoEscape::Html) && !value.is_safe() {
+            out.write_str(&escape_html(&value.to_string()))?;
+            Ok(())
+        } else {
+            escape_formatter(out, state, value)
+        }
+    });
+    env
+}
+
+//
+//** Render the merge tags of a message for one recipient
+//** The context is usually a MergeContext, system emails pass their own values.
+//** Values are HTML escaped in the body, not in the subject and text part.
+//** Every builtin filter is available, e.g. {{ subscriber.name | default("there") | title }}
+//** Params : request , context
+//** Return : Err(EmailError::TemplateError) naming the part that could not be rendered
+//
+pub fn render<C: Serialize>(request: &mut EmailRequest, context: &C) -> Result<(), EmailError> {
+    let env = environment();
+    let render = |name: &str, source: &str| {
+        env.render_named_str(name, source, context)
+            .map_err(|e| EmailError::TemplateError(format!("{}: {}", name, e)))
+    };
+
+    if has_tags(&request.subject) {
+        request.subject = render("subject", &request.subject)?;
+    }
+    // The .html name turns auto-escaping on
+    if has_tags(&request.body) {
+        request.body = render("body.html", &request.body)?;
+    }
+    if let Some(text) = request.text_body.as_deref().filter(|text| has_tags(text)) {
+        request.text_body = Some(render("body.txt", text)?);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> MergeContext {
+        let list = serde_json::json!({ "id": 3, "name": "Weekly" });
+        MergeContext {
+            subscriber: serde_json::json!({
+                "id": 7,
+                "email": "ada@example.com",
+                "name": "ada lovelace",
+                "attribs": { "company": "Babbage & <Co>", "plan": null },
+            }),
+            campaign: serde_json::json!({ "id": 1, "name": "Launch" }),
+            list: Some(list.clone()),
+            lists: vec![list],
+            unsubscribe_url: Some("https://example.com/u?t=a&b".to_string()),
+            preferences_url: None,
+        }
+    }
+
+    #[test]
+    fn renders_subscriber_fields_per_recipient() {
+        let mut request = EmailRequest {
+            subject: "{{ subscriber.name | title }}, news from {{ list.name }}".to_string(),
+            body: "<p>{{ subscriber.name }} at {{ subscriber.attribs.company }} ({{ subscriber.attribs.city | default(\"somewhere\") }})</p>\
+                   <a href=\"{{ unsubscribe_url }}\">Unsubscribe</a>{{ preferences_url }}{{ subscriber.attribs.plan }}".to_string(),
+            text_body: Some("{{ campaign.name | upper }} {{ subscriber.attribs.missing.deeper }}".to_string()),
+            ..Default::default()
+        };
+
+        render(&mut request, &context()).unwrap();
+
+        assert_eq!(request.subject, "Ada Lovelace, news from Weekly");
+        assert_eq!(
+            request.body,
+            "<p>ada lovelace at Babbage &amp; &lt;Co&gt; (somewhere)</p><a href=\"https://example.com/u?t=a&amp;b\">Unsubscribe</a>"
+        );
+        assert_eq!(request.text_body.as_deref(), Some("LAUNCH "));
+    }
+
+    #[test]
+    fn reports_template_errors() {
+        let mut request = EmailRequest {
+            subject: "Hello".to_string(),
+            body: "{{ subscriber.name | no_such_filter }}".to_string(),
+            ..Default::default()
+        };
+        let error = render(&mut request, &context()).unwrap_err();
+        assert!(matches!(error, EmailError::TemplateError(ref message) if message.starts_with("body.html")));
+
+        let mut request = EmailRequest { subject: "{% if %}".to_string(), ..Default::default() };
+        assert!(render(&mut request, &context()).is_err());
+
+        // No tags, nothing parsed
+        let mut request = EmailRequest { body: "50% off {not a tag}".to_string(), ..Default::default() };
+        render(&mut request, &context()).unwrap();
+        assert_eq!(request.body, "50% off {not a tag}");
+    }
+}
@@ -0,0 +1,69 @@
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::address::{Address, Envelope};
+use lettre::Message;
+use crate::email_service::{models::EmailRequest, error::EmailError, dkim::DkimSigner, content};
+
+//
+//** Build the RFC 5322 message for a request
+//** `to` may hold several comma separated recipients
+//** Params : request , dkim (signs the message when the sender domain has a key)
+//** Return : Result<Message, EmailError>
+//
+pub fn build_message(request: &EmailRequest, dkim: Option<&DkimSigner>) -> Result<Message, EmailError> {
+    let from = request.from.as_deref()
+        .ok_or_else(|| EmailError::MessageError("Missing sender address".to_string()))?;
+    let from: Mailbox = from.parse().map_err(|_| EmailError::InvalidAddress(from.to_string()))?;
+    let sender_domain = from.email.domain().to_string();
+
+    let mut builder = Message::builder()
+        .from(from)
+        .subject(request.subject.as_str())
+        .message_id(None);
+
+    let mut to = Vec::new();
+    for recipient in recipients(&request.to) {
+        let mailbox: Mailbox = recipient.parse().map_err(|_| EmailError::InvalidAddress(recipient.to_string()))?;
+        to.push(mailbox.email.clone());
+        builder = builder.to(mailbox);
+    }
+
+    // Bounces go to the return path instead of the From address
+    if let Some(return_path) = &request.return_path {
+        let sender: Address = return_path.parse().map_err(|_| EmailError::InvalidAddress(return_path.clone()))?;
+        let envelope = Envelope::new(Some(sender), to).map_err(|e| EmailError::MessageError(e.to_string()))?;
+        builder = builder.envelope(envelope);
+    }
+
+    // Always multipart/alternative, the text part is generated from the HTML when not given
+    let text = match &request.text_body {
+        Some(text) => text.clone(),
+        None => content::html_to_text(&request.body),
+    };
+    let mut message = builder
+        .multipart(MultiPart::alternative_plain_html(text, request.body.clone()))
+        .map_err(|e| EmailError::MessageError(e.to_string()))?;
+
+    for (name, value) in &request.headers {
+        let name = HeaderName::new_from_ascii(name.clone())
+            .map_err(|_| EmailError::MessageError(format!("Invalid header name: {}", name)))?;
+        message.headers_mut().insert_raw(HeaderValue::new(name, value.clone()));
+    }
+
+    // Signed last, nothing may touch the headers after this
+    if let Some(dkim) = dkim {
+        dkim.sign(&mut message, &sender_domain);
+    }
+
+    Ok(message)
+}
+
+/// Split the `to` field of a request into its recipients
+pub fn recipients(to: &str) -> impl Iterator<Item = &str> {
+    to.split(',').map(str::trim).filter(|r| !r.is_empty())
+}
+
+/// Message-ID header of a built message
+pub fn message_id(message: &Message) -> String {
+    message.headers().get_raw("Message-ID").unwrap_or_default().to_string()
+}
@@ -2,7 +2,30 @@ pub mod error;
 pub mod service;
 pub mod models;
 pub mod config;
+pub mod message;
+pub mod provider;
+pub mod providers;
+pub mod executor;
+pub mod outbox;
+pub mod rate_limit;
+pub mod dkim;
+pub mod unsubscribe;
+pub mod bounce;
+pub mod maildir;
+pub mod complaint;
+pub mod inbound;
+pub mod merge;
+pub mod layout;
+pub mod content;
+pub mod html;
 
 pub use self::service::EmailService;
 pub use self::models::EmailRequest;
-pub use self::error::EmailError;
\ No newline at end of file
+pub use self::error::EmailError;
+pub use self::provider::{EmailProvider, ProviderRegistry, SendReceipt};
+pub use self::executor::{EmailExecutor, ExecutorResponse};
+pub use self::outbox::OutboxWorker;
+pub use self::rate_limit::RateLimiter;
+pub use self::dkim::DkimSigner;
+pub use self::unsubscribe::UnsubscribeLinks;
+pub use self::html::HtmlPipeline;
@@ -1,11 +1,29 @@
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 
-#[derive(Debug, Serialize, Deserialize)]
+pub mod send_strategy;
+pub mod builder;
+
+pub use self::send_strategy::SendStrategy;
+pub use self::builder::EmailBuilder;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EmailRequest {
     pub to: String,
     pub subject: String,
     pub body: String,
+    // Sent as the text/plain alternative of the HTML body, generated from the HTML when unset
+    #[serde(default)]
+    pub text_body: Option<String>,
+    // Filled with the service sender address when missing
+    #[serde(default)]
+    pub from: Option<String>,
+    // Extra headers, e.g. List-Unsubscribe
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    // Envelope sender receiving the bounces (VERP address), the From address when unset
+    #[serde(default)]
+    pub return_path: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -44,6 +62,13 @@ pub struct CampaignEmailRequest {
     pub schedule_at: Option<DateTime<Utc>>, // Optional for immediate sending
 }
 
+/// A send handed over to the outbox workers
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueuedEmailJob {
+    pub job_id: i32,
+    pub queued: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CampaignEmailStats {
     pub campaign_id: i32,
@@ -53,4 +78,4 @@ pub struct CampaignEmailStats {
     pub failures: Vec<(String, String)>,
     pub started_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
-}
\ No newline at end of file
+}
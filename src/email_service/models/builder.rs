@@ -1,13 +1,13 @@
 use crate::email_service::{
+    executor::{EmailExecutor, ExecutorResponse},
     models::{EmailRequest, SendStrategy},
-    error::EmailError,
-    provider::EmailProvider,
 };
+use crate::error::ApiError;
 
 use chrono::{DateTime, Duration , Utc};
 
 pub struct EmailBuilder {
-    request: EmailRequest,
+    requests: Vec<EmailRequest>,
     strategy: SendStrategy,
     provider_name: Option<String>,
 }
@@ -15,12 +15,18 @@ pub struct EmailBuilder {
 impl EmailBuilder {
     pub fn new(request: EmailRequest) -> Self {
         Self {
-            request,
+            requests: vec![request],
             strategy: SendStrategy::Immediate,
             provider_name: None,
         }
     }
 
+    // Ajoute un email au même envoi
+    pub fn add_request(mut self, request: EmailRequest) -> Self {
+        self.requests.push(request);
+        self
+    }
+
     // Configuration du provider
     pub fn with_provider(mut self, provider_name: String) -> Self {
         self.provider_name = Some(provider_name);
@@ -30,6 +36,7 @@ impl EmailBuilder {
     // Envoi programmé
     pub fn schedule_at(mut self, send_at: DateTime<Utc>) -> Self {
         self.strategy = SendStrategy::Scheduled { send_at };
+        self
     }
 
     // Envoi récurrent
@@ -64,4 +71,8 @@ impl EmailBuilder {
         self
     }
 
-}
\ No newline at end of file
+    // Exécute l'envoi avec la stratégie choisie
+    pub async fn send(self, executor: &EmailExecutor) -> Result<ExecutorResponse, ApiError> {
+        executor.execute(self.requests, self.strategy, self.provider_name).await
+    }
+}
@@ -1,7 +1,9 @@
 use chrono::{DateTime, Duration , Utc};
 use serde::{Deserialize, Serialize};
+use crate::email_service::error::EmailError;
 
 #[derive(Debug , Clone , Serialize , Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum SendStrategy {
     // Envoi immédiat une seule fois
     Immediate,
@@ -12,6 +14,7 @@ pub enum SendStrategy {
     // Envoi répété avec intervalle
     Recurring {
         start_at: DateTime<Utc>,
+        #[serde(with = "duration_secs")]
         interval: Duration,
         repeat_count: Option<u32>, // None = infini
         max_attempts: u32,
@@ -19,7 +22,113 @@ pub enum SendStrategy {
     // Envoi un lot d'emails avec délai entre chaque lot
     Batch {
         batch_size: usize,
+        #[serde(with = "duration_secs")]
         delay_between_batches: Duration,
         max_batches: Option<u32>,
     }
-}
\ No newline at end of file
+}
+
+impl SendStrategy {
+    /// Reject strategies that could never run
+    pub fn validate(&self) -> Result<(), EmailError> {
+        match self {
+            Self::Recurring { interval, max_attempts, repeat_count, .. } => {
+                // Intervals are stored in seconds
+                if *interval < Duration::seconds(1) {
+                    return Err(EmailError::InvalidStrategy("interval must be at least one second".to_string()));
+                }
+                if *max_attempts == 0 {
+                    return Err(EmailError::InvalidStrategy("max_attempts must be at least 1".to_string()));
+                }
+                if *repeat_count == Some(0) {
+                    return Err(EmailError::InvalidStrategy("repeat_count must be at least 1".to_string()));
+                }
+            }
+            Self::Batch { batch_size, delay_between_batches, max_batches } => {
+                if *batch_size == 0 {
+                    return Err(EmailError::InvalidStrategy("batch_size must be at least 1".to_string()));
+                }
+                if *delay_between_batches < Duration::zero() {
+                    return Err(EmailError::InvalidStrategy("delay_between_batches cannot be negative".to_string()));
+                }
+                if *max_batches == Some(0) {
+                    return Err(EmailError::InvalidStrategy("max_batches must be at least 1".to_string()));
+                }
+            }
+            Self::Immediate | Self::Scheduled { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// Strategies that are stored and run later by the scheduler
+    pub fn is_deferred(&self) -> bool {
+        matches!(self, Self::Scheduled { .. } | Self::Recurring { .. })
+    }
+
+    /// First time a deferred strategy is due
+    pub fn first_run_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Scheduled { send_at } => Some(*send_at),
+            Self::Recurring { start_at, .. } => Some(*start_at),
+            Self::Immediate | Self::Batch { .. } => None,
+        }
+    }
+}
+
+// chrono::Duration has no serde support, store it as a number of seconds
+mod duration_secs {
+    use chrono::Duration;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = i64::deserialize(deserializer)?;
+        Duration::try_seconds(secs).ok_or_else(|| D::Error::custom(format!("duration of {} seconds is out of range", secs)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_durations_as_seconds() {
+        let strategy = SendStrategy::Batch {
+            batch_size: 100,
+            delay_between_batches: Duration::minutes(2),
+            max_batches: None,
+        };
+
+        let json = serde_json::to_value(&strategy).unwrap();
+        assert_eq!(json["type"], "batch");
+        assert_eq!(json["delay_between_batches"], 120);
+
+        match serde_json::from_value(json).unwrap() {
+            SendStrategy::Batch { delay_between_batches, .. } => assert_eq!(delay_between_batches, Duration::minutes(2)),
+            other => panic!("unexpected strategy {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_durations() {
+        let json = serde_json::json!({ "type": "batch", "batch_size": 10, "delay_between_batches": i64::MAX });
+        assert!(serde_json::from_value::<SendStrategy>(json).is_err());
+    }
+
+    #[test]
+    fn rejects_strategies_that_cannot_run() {
+        let recurring = SendStrategy::Recurring {
+            start_at: Utc::now(),
+            interval: Duration::hours(1),
+            repeat_count: None,
+            max_attempts: 0,
+        };
+        assert!(recurring.validate().is_err());
+        assert!(SendStrategy::Batch { batch_size: 0, delay_between_batches: Duration::zero(), max_batches: None }.validate().is_err());
+        assert!(SendStrategy::Immediate.validate().is_ok());
+    }
+}
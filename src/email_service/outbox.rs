@@ -0,0 +1,190 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use crate::email_service::{config::OutboxConfig, error::FailureKind, merge, models::EmailRequest, service::EmailService};
+use crate::error::ApiError;
+use crate::models::email_outbox::OutboxMessage;
+use crate::repositories::email_outbox_repository::EmailOutboxRepository;
+
+//
+//** Worker draining the email_outbox table
+//** Rows are claimed with FOR UPDATE SKIP LOCKED so any number of workers,
+//** in this process or another one, can run side by side
+//
+#[derive(Clone)]
+pub struct OutboxWorker {
+    service: Arc<EmailService>,
+    outbox: EmailOutboxRepository,
+    config: OutboxConfig,
+}
+
+impl OutboxWorker {
+    pub fn new(service: Arc<EmailService>, pool: PgPool, config: OutboxConfig) -> Self {
+        Self {
+            service,
+            outbox: EmailOutboxRepository::new(pool),
+            config,
+        }
+    }
+
+    /// Start `config.workers` workers on the tokio runtime
+    pub fn spawn(self) -> Vec<JoinHandle<()>> {
+        tracing::info!("Starting {} outbox workers", self.config.workers);
+
+        (0..self.config.workers)
+            .map(|worker_id| {
+                let worker = self.clone();
+                tokio::spawn(async move { worker.run(worker_id).await })
+            })
+            .collect()
+    }
+
+    async fn run(self, worker_id: usize) {
+        let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
+
+        loop {
+            match self.process_batch().await {
+                Ok(0) => tokio::time::sleep(poll_interval).await,
+                Ok(count) => tracing::debug!("Outbox worker {} processed {} messages", worker_id, count),
+                Err(e) => {
+                    tracing::error!("Outbox worker {} failed: {}", worker_id, e);
+                    tokio::time::sleep(poll_interval).await;
+                }
+            }
+        }
+    }
+
+    //
+    //** Claim and send one batch of messages
+    //** Return : Result<usize, ApiError> (number of messages claimed)
+    //
+    pub async fn process_batch(&self) -> Result<usize, ApiError> {
+        let messages = self.outbox.claim(self.config.batch_size, self.config.lock_timeout_secs).await?;
+        let count = messages.len();
+
+        // One failing message must not leave the rest of the batch locked
+        for message in messages {
+            let id = message.id;
+            if let Err(e) = self.deliver(message).await {
+                tracing::error!("Failed to deliver outbox message {}: {}", id, e);
+            }
+        }
+
+        Ok(count)
+    }
+
+    //
+    //** Render the merge tags of a campaign message for its subscriber
+    //** Return : Err(ApiError::EmailError(TemplateError)) when the template fails for this subscriber
+    //
+    async fn personalize(&self, request: &mut EmailRequest, campaign_id: i32, subscriber_id: i32) -> Result<(), ApiError> {
+        if !(merge::has_tags(&request.subject)
+            || merge::has_tags(&request.body)
+            || request.text_body.as_deref().is_some_and(merge::has_tags))
+        {
+            return Ok(());
+        }
+
+        let mut context = self.outbox
+            .merge_context(subscriber_id, campaign_id)
+            .await?
+            .unwrap_or_default();
+        context.unsubscribe_url = self.service.unsubscribe_links().map(|links| links.url(subscriber_id, campaign_id));
+        context.preferences_url = self.service.preferences_url(subscriber_id);
+
+        merge::render(request, &context)?;
+        Ok(())
+    }
+
+    async fn deliver(&self, message: OutboxMessage) -> Result<(), ApiError> {
+        let mut request = message.request.0;
+
+        // Rendered per subscriber, a template error only fails this message
+        if let (Some(campaign_id), Some(subscriber_id)) = (message.campaign_id, message.subscriber_id) {
+            match self.personalize(&mut request, campaign_id, subscriber_id).await {
+                Ok(()) => {}
+                Err(ApiError::EmailError(e)) => {
+                    tracing::error!("Failed to render message {} for subscriber {}: {}", message.id, subscriber_id, e);
+                    self.outbox.mark_failed(message.id, &e.to_string(), None, e.failure_kind()).await?;
+                    if let Some(job_id) = message.job_id {
+                        self.outbox.complete_job_if_drained(job_id).await?;
+                    }
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // The tracking pixel and unsubscribe link are specific to each subscriber, add them at send time
+        if let (Some(campaign_id), Some(subscriber_id)) = (message.campaign_id, message.subscriber_id) {
+            request.body = self.service.add_tracking_to_email(
+                &request.body,
+                campaign_id,
+                message.sequence_email_id.unwrap_or(0),
+                subscriber_id,
+            );
+            request.headers.extend(self.service.unsubscribe_headers(campaign_id, subscriber_id));
+        }
+
+        if request.return_path.is_none() {
+            request.return_path = self.service.return_path(message.id);
+        }
+
+        let to = request.to.clone();
+
+        // Campaign at its concurrency limit: hand the row back, other campaigns keep the worker busy
+        let _campaign_slot = match message.campaign_id {
+            Some(campaign_id) => match self.service.try_campaign_slot(campaign_id) {
+                Some(slot) => Some(slot),
+                None => {
+                    let available_at = Utc::now() + chrono::Duration::milliseconds(self.config.poll_interval_ms as i64);
+                    return self.outbox.defer(message.id, available_at).await;
+                }
+            },
+            None => None,
+        };
+
+        // Over a rate limit: hand the row back instead of holding the worker
+        if let Err(wait) = self.service.try_acquire(message.messenger.as_deref(), &to) {
+            tracing::debug!("Sending to {} throttled for {}ms", to, wait.as_millis());
+            let available_at = Utc::now() + chrono::Duration::milliseconds(wait.as_millis().max(1) as i64);
+            return self.outbox.defer(message.id, available_at).await;
+        }
+
+        match self.service.dispatch(message.messenger.as_deref(), request).await {
+            Ok(receipt) => {
+                tracing::info!("Successfully sent email to {} ({})", to, receipt.message_id);
+                self.outbox.mark_sent(message.id, &receipt).await?;
+            }
+            Err(e) => {
+                let kind = e.failure_kind();
+                let retry = &self.config.retry;
+
+                if kind == FailureKind::Transient && message.attempts < retry.max_attempts {
+                    let delay = retry.backoff(message.attempts as u32);
+                    tracing::warn!(
+                        "Transient failure sending to {} (attempt {}/{}), retrying in {}s: {}",
+                        to, message.attempts, retry.max_attempts, delay.as_secs(), e
+                    );
+
+                    let available_at = Utc::now() + chrono::Duration::seconds(delay.as_secs() as i64);
+                    self.outbox.mark_retry(message.id, &e.to_string(), e.smtp_code(), available_at).await?;
+
+                    // Still pending, the job is not drained
+                    return Ok(());
+                }
+
+                tracing::error!("Failed to send email to {} after {} attempts ({}): {}", to, message.attempts, kind.as_str(), e);
+                self.outbox.mark_failed(message.id, &e.to_string(), e.smtp_code(), kind).await?;
+            }
+        }
+
+        if let Some(job_id) = message.job_id {
+            self.outbox.complete_job_if_drained(job_id).await?;
+        }
+
+        Ok(())
+    }
+}
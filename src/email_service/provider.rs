@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use crate::email_service::{models::EmailRequest, error::EmailError};
+use crate::email_service::{config::EmailProviderConfig, dkim::DkimSigner, providers::build_provider};
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ProviderFeature {
+    Html,
+    Attachments,
+    Templates,
+    Tracking,
+    Scheduling,
+    BulkSend,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ProviderType {
+    Smtp,
+    AwsSes,
+    Mailgun,
+    SendGrid,
+    Mock,
+    // Several providers behind one messenger
+    Router,
+}
+
+/// What a provider reports back for an accepted message
+#[derive(Debug, Clone, Serialize)]
+pub struct SendReceipt {
+    pub message_id: String,
+    // Final server reply, e.g. "250 2.0.0 Ok: queued" for SMTP
+    pub response: Option<String>,
+    // Route or messenger that accepted the message, filled by the service when unset
+    pub provider: Option<String>,
+}
+
+#[async_trait]
+pub trait EmailProvider: Send + Sync {
+    async fn send(&self, email: EmailRequest) -> Result<SendReceipt, EmailError>;
+    fn provider_name(&self) -> &'static str;
+    fn provider_type(&self) -> ProviderType;
+    fn supported_features(&self) -> HashSet<ProviderFeature>;
+
+    fn supports_feature(&self, feature: ProviderFeature) -> bool {
+        self.supported_features().contains(&feature)
+    }
+}
+
+// Lets a provider be shared, e.g. by a router and its own messenger
+#[async_trait]
+impl<P: EmailProvider + ?Sized> EmailProvider for Arc<P> {
+    async fn send(&self, email: EmailRequest) -> Result<SendReceipt, EmailError> {
+        (**self).send(email).await
+    }
+
+    fn provider_name(&self) -> &'static str {
+        (**self).provider_name()
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        (**self).provider_type()
+    }
+
+    fn supported_features(&self) -> HashSet<ProviderFeature> {
+        (**self).supported_features()
+    }
+}
+
+//
+//** Registry of the email providers known to the service
+//** Providers are keyed by messenger name (the `campaigns.messenger` column)
+//
+pub struct ProviderRegistry {
+    providers: HashMap<String, Box<dyn EmailProvider>>,
+    default_provider: String,
+}
+
+impl ProviderRegistry {
+    pub fn new(default_provider: impl Into<String>) -> Self {
+        Self {
+            providers: HashMap::new(),
+            default_provider: default_provider.into(),
+        }
+    }
+
+    /// Registry with the configured provider as default, under its messenger name
+    pub fn from_config(config: &EmailProviderConfig, dkim: Option<Arc<DkimSigner>>) -> Result<Self, EmailError> {
+        let messenger = config.messenger();
+        let mut registry = Self::new(messenger);
+        registry.register(messenger, build_provider(config, dkim)?);
+        Ok(registry)
+    }
+
+    /// Register a provider under a messenger name, replacing any previous one
+    pub fn register(&mut self, messenger: impl Into<String>, provider: Box<dyn EmailProvider>) {
+        let messenger = messenger.into();
+        tracing::info!("Registering email provider '{}' as messenger '{}'", provider.provider_name(), messenger);
+        self.providers.insert(messenger, provider);
+    }
+
+    /// Builder-style variant of `register`
+    pub fn with_provider(mut self, messenger: impl Into<String>, provider: Box<dyn EmailProvider>) -> Self {
+        self.register(messenger, provider);
+        self
+    }
+
+    pub fn default_provider(&self) -> &str {
+        &self.default_provider
+    }
+
+    pub fn messengers(&self) -> Vec<String> {
+        self.providers.keys().cloned().collect()
+    }
+
+    /// Resolve a messenger name to its provider, `None` meaning the default provider
+    pub fn get(&self, messenger: Option<&str>) -> Result<&dyn EmailProvider, EmailError> {
+        let name = messenger.unwrap_or(&self.default_provider);
+        self.providers
+            .get(name)
+            .map(|provider| provider.as_ref())
+            .ok_or_else(|| EmailError::UnknownProvider(name.to_string()))
+    }
+}
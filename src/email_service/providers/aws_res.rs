@@ -1 +0,0 @@
-// TODO: Implement AWS SES provider
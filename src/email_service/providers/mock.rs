@@ -1,24 +1,288 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
 use crate::email_service::{
-    provider::EmailProvider,
+    provider::{EmailProvider, ProviderFeature, ProviderType, SendReceipt},
+    message::{build_message, message_id, recipients},
+    dkim::DkimSigner,
+    config::{MockConfig, MockOutputFormat},
     models::EmailRequest,
     error::EmailError,
-    config::AwsSesConfig
 };
 
+/// A message captured by the mock provider
+#[derive(Debug, Clone, Serialize)]
+pub struct SentMessage {
+    pub message_id: String,
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub headers: Vec<(String, String)>,
+    pub html: String,
+    pub text: Option<String>,
+    // Links and tracking pixel URLs found in the HTML body
+    pub urls: Vec<String>,
+    // Full RFC 5322 rendering of the message
+    pub raw: String,
+    pub path: Option<PathBuf>,
+    pub sent_at: DateTime<Utc>,
+}
+
+//
+//** Provider that never talks to a server
+//** Messages are kept in memory and optionally written to disk
+//** Clones share the same store, keep one to assert on what went out
+//
+#[derive(Clone)]
+pub struct MockProvider {
+    config: MockConfig,
+    fail_recipients: HashSet<String>,
+    sent: Arc<Mutex<Vec<SentMessage>>>,
+    dkim: Option<Arc<DkimSigner>>,
+}
+
+impl MockProvider {
+    pub fn new(config: MockConfig) -> Self {
+        let fail_recipients = config.fail_recipients.iter()
+            .map(|r| r.to_lowercase())
+            .collect();
+
+        Self {
+            config,
+            fail_recipients,
+            sent: Arc::new(Mutex::new(Vec::new())),
+            dkim: None,
+        }
+    }
+
+    /// Sign captured messages like the real providers would
+    pub fn with_dkim(mut self, dkim: Option<Arc<DkimSigner>>) -> Self {
+        self.dkim = dkim;
+        self
+    }
+
+    /// Make sends to this recipient fail
+    pub fn fail_for(&mut self, recipient: &str) {
+        self.fail_recipients.insert(recipient.to_lowercase());
+    }
+
+    /// All messages sent so far
+    pub fn sent(&self) -> Vec<SentMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Messages sent to a given recipient
+    pub fn sent_to(&self, recipient: &str) -> Vec<SentMessage> {
+        self.sent.lock().unwrap()
+            .iter()
+            .filter(|m| m.to.iter().any(|to| to.eq_ignore_ascii_case(recipient)))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.sent.lock().unwrap().clear();
+    }
+
+    async fn write_message(&self, dir: &Path, raw: &[u8]) -> Result<PathBuf, EmailError> {
+        let id = Uuid::new_v4();
+
+        let path = match self.config.output_format {
+            MockOutputFormat::Eml => {
+                tokio::fs::create_dir_all(dir).await.map_err(io_error)?;
+                let path = dir.join(format!("{}.eml", id));
+                tokio::fs::write(&path, raw).await.map_err(io_error)?;
+                path
+            }
+            MockOutputFormat::Maildir => {
+                for sub in ["tmp", "new", "cur"] {
+                    tokio::fs::create_dir_all(dir.join(sub)).await.map_err(io_error)?;
+                }
+
+                // Deliver through tmp/ so readers never see a partial file
+                let name = format!("{}.{}.mock", Utc::now().timestamp(), id);
+                let tmp = dir.join("tmp").join(&name);
+                let path = dir.join("new").join(&name);
+                tokio::fs::write(&tmp, raw).await.map_err(io_error)?;
+                tokio::fs::rename(&tmp, &path).await.map_err(io_error)?;
+                path
+            }
+        };
+
+        Ok(path)
+    }
+}
+
+fn io_error(e: std::io::Error) -> EmailError {
+    EmailError::TemporaryFailure(format!("Mock output error: {}", e))
+}
+
+/// Header block of a formatted message, with folded lines joined
+fn parse_headers(raw: &str) -> Vec<(String, String)> {
+    let head = raw.split("\r\n\r\n").next().unwrap_or_default();
+    let mut headers: Vec<(String, String)> = Vec::new();
+
+    for line in head.split("\r\n") {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    headers
+}
+
+/// `src` and `href` attribute values of an HTML document
+fn extract_urls(html: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+
+    for attr in ["src=", "href="] {
+        let mut rest = html;
+        while let Some(pos) = rest.find(attr) {
+            rest = &rest[pos + attr.len()..];
+            let quote = match rest.chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => continue,
+            };
+            if let Some(end) = rest[1..].find(quote) {
+                urls.push(rest[1..end + 1].to_string());
+            }
+        }
+    }
+
+    urls
+}
+
 #[async_trait]
 impl EmailProvider for MockProvider {
-    async fn send(&self , request: EmailRequest) -> Result<String , EmailError> { 
-        if self.should_fail {
-            Err(EmailError::ProviderError("Mock failure".to_string()))
-        } else {
-            Ok(format!("mock_message_id_{}", Uuid::new_v4()))
+    async fn send(&self, request: EmailRequest) -> Result<SendReceipt, EmailError> {
+        let to: Vec<String> = recipients(&request.to).map(str::to_string).collect();
+
+        if let Some(recipient) = to.iter().find(|r| self.fail_recipients.contains(&r.to_lowercase())) {
+            let message = format!("Mock failure for {}", recipient);
+            return Err(if self.config.fail_transient {
+                EmailError::TemporaryFailure(message)
+            } else {
+                EmailError::ProviderError(message)
+            });
         }
+
+        let message = build_message(&request, self.dkim.as_deref())?;
+        let message_id = message_id(&message);
+        let raw = message.formatted();
+
+        let path = match &self.config.output_dir {
+            Some(dir) => Some(self.write_message(dir, &raw).await?),
+            None => None,
+        };
+
+        let raw = String::from_utf8_lossy(&raw).to_string();
+
+        self.sent.lock().unwrap().push(SentMessage {
+            message_id: message_id.clone(),
+            from: request.from.clone().unwrap_or_default(),
+            to,
+            subject: request.subject.clone(),
+            headers: parse_headers(&raw),
+            urls: extract_urls(&request.body),
+            html: request.body,
+            text: request.text_body,
+            raw,
+            path,
+            sent_at: Utc::now(),
+        });
+
+        Ok(SendReceipt {
+            message_id,
+            response: Some("250 Captured by mock provider".to_string()),
+            provider: None,
+        })
     }
-    
 
     fn provider_name(&self) -> &'static str {
         "mock"
     }
 
-}
\ No newline at end of file
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Mock
+    }
+
+    fn supported_features(&self) -> HashSet<ProviderFeature> {
+        [
+            ProviderFeature::Html,
+            ProviderFeature::Tracking,
+            ProviderFeature::BulkSend,
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(to: &str) -> EmailRequest {
+        EmailRequest {
+            to: to.to_string(),
+            subject: "Hello".to_string(),
+            body: r#"<p>Hi</p><img src="https://example.com/1/2/3" width="1" height="1" />"#.to_string(),
+            text_body: Some("Hi".to_string()),
+            from: Some("sender@example.com".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn captures_sent_messages() {
+        let provider = MockProvider::new(MockConfig::default());
+
+        let message_id = provider.send(request("one@example.com")).await.unwrap().message_id;
+
+        let sent = provider.sent_to("one@example.com");
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].message_id, message_id);
+        assert_eq!(sent[0].subject, "Hello");
+        assert_eq!(sent[0].text.as_deref(), Some("Hi"));
+        assert_eq!(sent[0].urls, vec!["https://example.com/1/2/3".to_string()]);
+        assert!(sent[0].headers.iter().any(|(name, value)| name == "Subject" && value == "Hello"));
+    }
+
+    #[tokio::test]
+    async fn fails_for_configured_recipients() {
+        let provider = MockProvider::new(MockConfig {
+            fail_recipients: vec!["Bad@Example.com".to_string()],
+            ..Default::default()
+        });
+
+        assert!(provider.send(request("bad@example.com")).await.is_err());
+        assert!(provider.send(request("good@example.com")).await.is_ok());
+        assert_eq!(provider.sent().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn writes_maildir_messages() {
+        let dir = std::env::temp_dir().join(format!("mock-maildir-{}", Uuid::new_v4()));
+        let provider = MockProvider::new(MockConfig {
+            output_dir: Some(dir.clone()),
+            output_format: MockOutputFormat::Maildir,
+            ..Default::default()
+        });
+
+        provider.send(request("one@example.com")).await.unwrap();
+
+        let path = provider.sent()[0].path.clone().unwrap();
+        assert!(path.starts_with(dir.join("new")));
+        assert!(std::fs::read_to_string(&path).unwrap().contains("Subject: Hello"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
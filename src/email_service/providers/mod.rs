@@ -0,0 +1,26 @@
+pub mod smtp;
+pub mod mock;
+pub mod aws_ses;
+pub mod router;
+
+pub use self::smtp::SmtpProvider;
+pub use self::mock::{MockProvider, SentMessage};
+pub use self::aws_ses::AwsSesProvider;
+pub use self::router::RoutedProvider;
+
+use std::sync::Arc;
+use crate::email_service::{config::EmailProviderConfig, dkim::DkimSigner, error::EmailError, provider::EmailProvider};
+
+/// Instantiate the provider described by a configuration, signing with `dkim` when set
+pub fn build_provider(
+    config: &EmailProviderConfig,
+    dkim: Option<Arc<DkimSigner>>,
+) -> Result<Box<dyn EmailProvider>, EmailError> {
+    let provider: Box<dyn EmailProvider> = match config {
+        EmailProviderConfig::Smtp(config) => Box::new(SmtpProvider::new(config)?.with_dkim(dkim)),
+        EmailProviderConfig::AwsSes(config) => Box::new(AwsSesProvider::new(config.clone())?.with_dkim(dkim)),
+        EmailProviderConfig::Mock(config) => Box::new(MockProvider::new(config.clone()).with_dkim(dkim)),
+    };
+
+    Ok(provider)
+}
@@ -0,0 +1,271 @@
+use async_trait::async_trait;
+use rand::Rng;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::email_service::{
+    dkim::DkimSigner,
+    provider::{EmailProvider, ProviderFeature, ProviderType, SendReceipt},
+    config::{CircuitBreakerConfig, RoutingConfig, RoutingStrategy},
+    models::EmailRequest,
+    error::EmailError,
+};
+use super::build_provider;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BreakerState {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    // Cooldown is over, a single trial send decides whether the route comes back
+    HalfOpen,
+}
+
+/// Takes a route out after repeated transient failures
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Whether a send may go through this route now
+    fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            BreakerState::Closed { .. } => true,
+            BreakerState::Open { until } if Instant::now() >= until => {
+                *state = BreakerState::HalfOpen;
+                true
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen => false,
+        }
+    }
+
+    fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::Closed { failures: 0 };
+    }
+
+    /// Return : true when this failure trips the breaker
+    fn record_failure(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let failures = match *state {
+            BreakerState::Closed { failures } => failures + 1,
+            _ => self.config.failure_threshold,
+        };
+
+        if failures >= self.config.failure_threshold.max(1) {
+            *state = BreakerState::Open {
+                until: Instant::now() + Duration::from_secs(self.config.cooldown_secs),
+            };
+            true
+        } else {
+            *state = BreakerState::Closed { failures };
+            false
+        }
+    }
+}
+
+struct Route {
+    name: String,
+    weight: u32,
+    provider: Arc<dyn EmailProvider>,
+    breaker: CircuitBreaker,
+}
+
+//
+//** Provider spreading sends over several backends
+//** A transient failure moves the message to the next route, permanent ones are returned as is
+//** The route that accepted the message is reported in the receipt
+//
+pub struct RoutedProvider {
+    strategy: RoutingStrategy,
+    breaker: CircuitBreakerConfig,
+    routes: Vec<Route>,
+}
+
+impl RoutedProvider {
+    pub fn new(strategy: RoutingStrategy, breaker: CircuitBreakerConfig) -> Self {
+        Self {
+            strategy,
+            breaker,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Build every route of a routing configuration
+    pub fn from_config(config: &RoutingConfig, dkim: Option<Arc<DkimSigner>>) -> Result<Self, EmailError> {
+        config.routes.iter().try_fold(
+            Self::new(config.strategy, config.circuit_breaker.clone()),
+            |router, route| {
+                let provider: Arc<dyn EmailProvider> = Arc::from(build_provider(&route.provider, dkim.clone())?);
+                Ok(router.with_route(route.name.clone(), route.weight, provider))
+            },
+        )
+    }
+
+    /// Add a route, tried after the ones already added in failover mode
+    pub fn with_route(mut self, name: impl Into<String>, weight: u32, provider: Arc<dyn EmailProvider>) -> Self {
+        self.routes.push(Route {
+            name: name.into(),
+            weight,
+            provider,
+            breaker: CircuitBreaker::new(self.breaker.clone()),
+        });
+        self
+    }
+
+    /// Routes by name, to register them as messengers of their own
+    pub fn routes(&self) -> Vec<(String, Arc<dyn EmailProvider>)> {
+        self.routes.iter()
+            .map(|route| (route.name.clone(), route.provider.clone()))
+            .collect()
+    }
+
+    /// Order in which the routes are tried for one send
+    fn order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.routes.len()).collect();
+
+        if self.strategy == RoutingStrategy::Weighted {
+            let total: u32 = self.routes.iter().map(|route| route.weight).sum();
+            if total > 0 {
+                let mut pick = rand::thread_rng().gen_range(0..total);
+                let first = self.routes.iter()
+                    .position(|route| {
+                        if pick < route.weight {
+                            true
+                        } else {
+                            pick -= route.weight;
+                            false
+                        }
+                    })
+                    .unwrap_or(0);
+
+                order.remove(first);
+                order.insert(0, first);
+            }
+        }
+
+        order
+    }
+}
+
+#[async_trait]
+impl EmailProvider for RoutedProvider {
+    async fn send(&self, request: EmailRequest) -> Result<SendReceipt, EmailError> {
+        let mut last_error = None;
+
+        for index in self.order() {
+            let route = &self.routes[index];
+            if !route.breaker.allow() {
+                continue;
+            }
+
+            match route.provider.send(request.clone()).await {
+                Ok(mut receipt) => {
+                    route.breaker.record_success();
+                    receipt.provider = Some(route.name.clone());
+                    return Ok(receipt);
+                }
+                Err(e) if e.is_transient() => {
+                    if route.breaker.record_failure() {
+                        tracing::warn!("Email route '{}' tripped its circuit breaker: {}", route.name, e);
+                    } else {
+                        tracing::warn!("Email route '{}' failed, trying the next one: {}", route.name, e);
+                    }
+                    last_error = Some(e);
+                }
+                Err(e) => {
+                    // The server answered, the route itself is healthy
+                    route.breaker.record_success();
+                    return Err(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| EmailError::TemporaryFailure("Every email route is unavailable".to_string())))
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "router"
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Router
+    }
+
+    fn supported_features(&self) -> HashSet<ProviderFeature> {
+        // Only what every route can do
+        let mut routes = self.routes.iter().map(|route| route.provider.supported_features());
+        let first = routes.next().unwrap_or_default();
+        routes.fold(first, |features, route| features.intersection(&route).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email_service::{config::MockConfig, providers::MockProvider};
+
+    fn request(to: &str) -> EmailRequest {
+        EmailRequest {
+            to: to.to_string(),
+            subject: "Hello".to_string(),
+            body: "<p>Hi</p>".to_string(),
+            from: Some("sender@example.com".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_and_trips_the_breaker() {
+        let primary = MockProvider::new(MockConfig {
+            fail_recipients: vec!["down@example.com".to_string()],
+            fail_transient: true,
+            ..Default::default()
+        });
+        let secondary = MockProvider::new(MockConfig::default());
+
+        let router = RoutedProvider::new(RoutingStrategy::Failover, CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown_secs: 3600,
+        })
+        .with_route("primary", 1, Arc::new(primary.clone()))
+        .with_route("secondary", 1, Arc::new(secondary.clone()));
+
+        let receipt = router.send(request("up@example.com")).await.unwrap();
+        assert_eq!(receipt.provider.as_deref(), Some("primary"));
+
+        for _ in 0..2 {
+            let receipt = router.send(request("down@example.com")).await.unwrap();
+            assert_eq!(receipt.provider.as_deref(), Some("secondary"));
+        }
+
+        // The breaker is open, the primary is no longer tried
+        let receipt = router.send(request("up@example.com")).await.unwrap();
+        assert_eq!(receipt.provider.as_deref(), Some("secondary"));
+        assert_eq!(primary.sent().len(), 1);
+        assert_eq!(secondary.sent().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_fail_over_permanent_errors() {
+        let primary = MockProvider::new(MockConfig {
+            fail_recipients: vec!["rejected@example.com".to_string()],
+            ..Default::default()
+        });
+        let secondary = MockProvider::new(MockConfig::default());
+
+        let router = RoutedProvider::new(RoutingStrategy::Weighted, CircuitBreakerConfig::default())
+            .with_route("primary", 1, Arc::new(primary))
+            .with_route("secondary", 0, Arc::new(secondary.clone()));
+
+        assert!(router.send(request("rejected@example.com")).await.is_err());
+        assert!(secondary.sent().is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use lettre::transport::smtp::authentication::{Credentials, Mechanism};
+use lettre::transport::smtp::extension::ClientId;
+use lettre::transport::smtp::PoolConfig;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use crate::email_service::{
+    provider::{EmailProvider, ProviderFeature, ProviderType, SendReceipt},
+    message::{build_message, message_id},
+    dkim::DkimSigner,
+    config::{SmtpAuthMechanism, SmtpConfig, SmtpTlsMode},
+    models::EmailRequest,
+    error::EmailError,
+};
+
+//
+//** SMTP provider over a pool of async connections
+//** Concurrent sends reuse open connections instead of reconnecting per message
+//** lettre does not use SMTP PIPELINING, throughput comes from the pool size instead
+//
+#[derive(Clone)]
+pub struct SmtpProvider {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    dkim: Option<Arc<DkimSigner>>,
+}
+
+impl SmtpProvider {
+    //
+    //** Build the pooled transport from the SMTP configuration
+    //** Params : config, TLS mode picks relay (implicit TLS), STARTTLS or plaintext
+    //** Return : SmtpProvider or an error when the server name is invalid
+    //
+    pub fn new(config: &SmtpConfig) -> Result<Self, EmailError> {
+        let builder = match config.tls {
+            SmtpTlsMode::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.server)
+                .map_err(EmailError::SmtpError)?,
+            SmtpTlsMode::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.server)
+                .map_err(EmailError::SmtpError)?,
+            SmtpTlsMode::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.server),
+        };
+
+        let pool_config = PoolConfig::new()
+            .max_size(config.pool.max_size)
+            .min_idle(config.pool.min_idle)
+            .idle_timeout(Duration::from_secs(config.pool.idle_timeout_secs));
+
+        let mut builder = builder
+            .port(config.port)
+            .timeout(Some(Duration::from_secs(config.timeout_secs)))
+            .pool_config(pool_config);
+
+        if let Some(username) = &config.username {
+            let password = config.password.clone().unwrap_or_default();
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+
+        if let Some(mechanism) = config.auth_mechanism {
+            let mechanism = match mechanism {
+                SmtpAuthMechanism::Plain => Mechanism::Plain,
+                SmtpAuthMechanism::Login => Mechanism::Login,
+                SmtpAuthMechanism::Xoauth2 => Mechanism::Xoauth2,
+            };
+            builder = builder.authentication(vec![mechanism]);
+        }
+
+        if let Some(helo_name) = &config.helo_name {
+            builder = builder.hello_name(ClientId::Domain(helo_name.clone()));
+        }
+
+        Ok(Self { transport: builder.build(), dkim: None })
+    }
+
+    /// Sign outgoing messages with the key of their sender domain
+    pub fn with_dkim(mut self, dkim: Option<Arc<DkimSigner>>) -> Self {
+        self.dkim = dkim;
+        self
+    }
+}
+
+#[async_trait]
+impl EmailProvider for SmtpProvider {
+    async fn send(&self, request: EmailRequest) -> Result<SendReceipt, EmailError> {
+        let email = build_message(&request, self.dkim.as_deref())?;
+        let message_id = message_id(&email);
+
+        let response = self.transport
+            .send(email)
+            .await
+            .map_err(EmailError::SmtpError)?;
+
+        Ok(SendReceipt {
+            message_id,
+            response: Some(format!("{} {}", response.code(), response.message().collect::<Vec<_>>().join(" "))),
+            provider: None,
+        })
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Smtp
+    }
+
+    fn supported_features(&self) -> HashSet<ProviderFeature> {
+        [ProviderFeature::Html, ProviderFeature::Tracking, ProviderFeature::BulkSend]
+            .into_iter()
+            .collect()
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::email_service::{config::{Rate, RateLimitConfig}, message::recipients};
+use crate::monitoring::EmailMetrics;
+
+const PROVIDER_SCOPE: &str = "provider";
+const DOMAIN_SCOPE: &str = "domain";
+
+/// Token bucket refilled continuously at `limit / period`
+#[derive(Debug, Clone)]
+struct Bucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: &Rate, now: Instant) -> Self {
+        let capacity = rate.limit as f64;
+        Self {
+            capacity,
+            refill_per_sec: capacity / rate.period_secs as f64,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Time until one token is available
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec)
+        }
+    }
+}
+
+//
+//** Token buckets per messenger and per recipient domain
+//** A send takes a token from every bucket that applies to it, or from none
+//** of them, so a send held back by one domain does not eat the provider quota
+//
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(&'static str, String), Vec<Bucket>>>,
+    metrics: Option<EmailMetrics>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            metrics: None,
+        }
+    }
+
+    /// Report remaining tokens and throttled sends to Prometheus
+    pub fn with_metrics(mut self, metrics: EmailMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Buckets that apply to a send, keyed by (scope, key)
+    fn keys(&self, messenger: &str, to: &str) -> Vec<(&'static str, String)> {
+        let mut keys = Vec::new();
+
+        if self.config.providers.contains_key(messenger) {
+            keys.push((PROVIDER_SCOPE, messenger.to_string()));
+        }
+
+        for recipient in recipients(to) {
+            let domain = match recipient.rsplit_once('@') {
+                Some((_, domain)) => domain.trim_end_matches('>').trim().to_lowercase(),
+                None => continue,
+            };
+            if self.config.domains.contains_key(&domain) && !keys.iter().any(|(_, key)| *key == domain) {
+                keys.push((DOMAIN_SCOPE, domain));
+            }
+        }
+
+        keys
+    }
+
+    fn rates(&self, scope: &str, key: &str) -> &[Rate] {
+        let rates = if scope == PROVIDER_SCOPE { &self.config.providers } else { &self.config.domains };
+        rates.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    //
+    //** Take a token for a send if every limit allows it
+    //** Params : messenger (provider name), to (comma separated recipients)
+    //** Return : Ok(()) or Err(time to wait before trying again)
+    //
+    pub fn try_acquire(&self, messenger: &str, to: &str) -> Result<(), Duration> {
+        let keys = self.keys(messenger, to);
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let mut wait = Duration::ZERO;
+        for key in &keys {
+            let rates = self.rates(key.0, &key.1);
+            let entry = buckets.entry(key.clone())
+                .or_insert_with(|| rates.iter().map(|rate| Bucket::new(rate, now)).collect());
+
+            let key_wait = entry.iter_mut()
+                .map(|bucket| {
+                    bucket.refill(now);
+                    bucket.wait()
+                })
+                .max()
+                .unwrap_or_default();
+
+            if key_wait > Duration::ZERO {
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_throttled(key.0, &key.1);
+                }
+                wait = wait.max(key_wait);
+            }
+        }
+
+        if wait > Duration::ZERO {
+            return Err(wait);
+        }
+
+        for key in &keys {
+            let entry = buckets.get_mut(key).expect("bucket created above");
+            for bucket in entry.iter_mut() {
+                bucket.tokens -= 1.0;
+            }
+
+            if let Some(metrics) = &self.metrics {
+                let tokens = entry.iter().map(|b| b.tokens).fold(f64::INFINITY, f64::min);
+                metrics.record_throttle_tokens(key.0, &key.1, tokens.floor());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Wait until every limit allows the send, then take its tokens
+    pub async fn acquire(&self, messenger: &str, to: &str) {
+        while let Err(wait) = self.try_acquire(messenger, to) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+//
+//** Caps the sends of a campaign in flight at once, across every outbox worker
+//** A semaphore is created on a campaign's first send and dropped once none of its sends holds a permit
+//
+pub struct CampaignLimiter {
+    limit: usize,
+    campaigns: Mutex<HashMap<i32, Arc<Semaphore>>>,
+}
+
+impl CampaignLimiter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            campaigns: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a send slot of a campaign, `None` while all of them are in use
+    pub fn try_acquire(&self, campaign_id: i32) -> Option<OwnedSemaphorePermit> {
+        let mut campaigns = self.campaigns.lock().unwrap();
+        // Permits hold a reference to their semaphore, idle campaigns are only referenced by the map
+        campaigns.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+
+        campaigns
+            .entry(campaign_id)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.limit)))
+            .clone()
+            .try_acquire_owned()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_back_sends_over_the_limit() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            providers: HashMap::from([("smtp".to_string(), vec!["2/s".parse().unwrap()])]),
+            domains: HashMap::from([("gmail.com".to_string(), vec!["1/m".parse().unwrap()])]),
+        });
+
+        assert!(limiter.try_acquire("smtp", "a@gmail.com").is_ok());
+
+        // gmail.com is exhausted, the provider token is left untouched
+        let wait = limiter.try_acquire("smtp", "b@Gmail.com").unwrap_err();
+        assert!(wait > Duration::from_secs(50));
+        assert!(limiter.try_acquire("smtp", "c@example.com").is_ok());
+
+        assert!(limiter.try_acquire("smtp", "d@example.com").is_err());
+        assert!(limiter.try_acquire("ses", "d@example.com").is_ok());
+    }
+
+    #[test]
+    fn limits_sends_in_flight_per_campaign() {
+        let limiter = CampaignLimiter::new(2);
+
+        let first = limiter.try_acquire(1).unwrap();
+        let _second = limiter.try_acquire(1).unwrap();
+        assert!(limiter.try_acquire(1).is_none());
+        assert!(limiter.try_acquire(2).is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire(1).is_some());
+    }
+}
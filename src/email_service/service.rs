@@ -1,126 +1,263 @@
-use async_trait::async_trait;
 use crate::email_service::{models::EmailRequest, error::EmailError};
-use crate::email_service::models::{BulkEmailStats, CampaignEmailStats};
-use std::collections::HashSet;
+use crate::email_service::models::QueuedEmailJob;
+use crate::email_service::provider::{EmailProvider, ProviderRegistry, SendReceipt};
+use crate::email_service::config::EmailProviderConfig;
+use crate::email_service::rate_limit::{CampaignLimiter, RateLimiter};
+use crate::email_service::dkim::DkimSigner;
+use crate::email_service::unsubscribe::UnsubscribeLinks;
+use crate::email_service::bounce::Verp;
+use crate::email_service::message::recipients;
+use crate::email_service::{content, layout};
+use crate::email_service::html::HtmlPipeline;
 use crate::error::ApiError;
-use lettre::message::header::ContentType;
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::transport::smtp::SmtpTransport;
-use lettre::{Message, Transport};
-use tokio::task;
 use sqlx::PgPool;
-use crate::models::subscriber::Subscriber;
-use crate::models::subscriber::SubscriberStatus;
+use crate::models::email_outbox::NewOutboxMessage;
+use crate::repositories::email_outbox_repository::EmailOutboxRepository;
+use crate::repositories::suppression_repository::SuppressionRepository;
+use crate::repositories::template_repository::TemplateRepository;
+use crate::models::sequence_email::SequenceEmail;
+use crate::models::template::Template;
+use lettre::message::Mailbox;
 use serde_json;
 use tracing;
 use chrono::{DateTime, Utc};
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OwnedSemaphorePermit;
+use futures::stream::{self, StreamExt};
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub enum ProviderFeature {
-    Html,
-    Attachments,
-    Templates,
-    Tracking,
-    Scheduling,
-    BulkSend,
-}
-
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum ProviderType {
-    Smtp,
-    AwsSes,
-    Mailgun,
-    SendGrid,
-    Mock,
-}
-
-#[async_trait]
-pub trait EmailProvider: Send + Sync {
-    async fn send(&self, email: EmailRequest) -> Result<String, EmailError>;
-    fn provider_name(&self) -> &'static str;
-    fn provider_type(&self) -> ProviderType;
-    fn supported_features(&self) -> HashSet<ProviderFeature>;
-    
-    fn supports_feature(&self, feature: ProviderFeature) -> bool {
-        self.supported_features().contains(&feature)
-    }
-}
+// Default number of messages of a campaign in flight at the same time
+const DEFAULT_CONCURRENCY: usize = 10;
 
 #[derive(Clone)]
 pub struct EmailService {
-    smtp_client: SmtpTransport,
+    providers: Arc<ProviderRegistry>,
     from_email: String,
+    concurrency: usize,
+    rate_limiter: Arc<RateLimiter>,
+    campaign_limiter: Arc<CampaignLimiter>,
+    unsubscribe: Option<UnsubscribeLinks>,
+    verp: Option<Verp>,
+    suppressions: Option<SuppressionRepository>,
+    html: Option<HtmlPipeline>,
 }
 
 //
 //** Create a new email service
-//** Params : host , username , password , from_email
+//** Params : from_email , providers
 //** Return : EmailService
 //
 impl EmailService {
-    pub fn new(host: String, username: String, password: String, from_email: String) -> Self {
-        let creds = Credentials::new(username, password);
-        
-        let smtp_client = SmtpTransport::relay(&host)
-            .unwrap()
-            .credentials(creds)
-            .build();
-
+    pub fn new(from_email: String, providers: ProviderRegistry) -> Self {
         Self {
-            smtp_client,
+            providers: Arc::new(providers),
             from_email,
+            concurrency: DEFAULT_CONCURRENCY,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            campaign_limiter: Arc::new(CampaignLimiter::new(DEFAULT_CONCURRENCY)),
+            unsubscribe: None,
+            verp: None,
+            suppressions: None,
+            html: None,
+        }
+    }
+
+    /// Build the service and its default provider from a provider configuration
+    pub fn from_config(config: &EmailProviderConfig, dkim: Option<Arc<DkimSigner>>) -> Result<Self, EmailError> {
+        Ok(Self::new(config.sender_email(), ProviderRegistry::from_config(config, dkim)?))
+    }
+
+    /// Limit the number of concurrent sends of a single campaign, and of a single call
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self.campaign_limiter = Arc::new(CampaignLimiter::new(self.concurrency));
+        self
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Throttle sends per messenger and per recipient domain
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Arc::new(rate_limiter);
+        self
+    }
+
+    /// Add one-click unsubscribe headers to campaign messages
+    pub fn with_unsubscribe_links(mut self, links: UnsubscribeLinks) -> Self {
+        self.unsubscribe = Some(links);
+        self
+    }
+
+    pub fn unsubscribe_links(&self) -> Option<&UnsubscribeLinks> {
+        self.unsubscribe.as_ref()
+    }
+
+    /// Send outbox messages with a VERP return path so their bounces can be matched
+    pub fn with_verp(mut self, verp: Option<Verp>) -> Self {
+        self.verp = verp;
+        self
+    }
+
+    /// Check every message against the suppression list before it reaches a provider
+    pub fn with_suppressions(mut self, suppressions: SuppressionRepository) -> Self {
+        self.suppressions = Some(suppressions);
+        self
+    }
+
+    /// Normalize campaign HTML (CSS inlining, stripping, absolute URLs) before it is queued
+    pub fn with_html_pipeline(mut self, html: HtmlPipeline) -> Self {
+        self.html = Some(html);
+        self
+    }
+
+    /// Body as it should be queued, unchanged when no HTML pipeline is configured
+    pub fn normalize_html(&self, body: &str) -> String {
+        match &self.html {
+            Some(html) => html.normalize(body),
+            None => body.to_string(),
         }
     }
 
+    /// VERP return path of an outbox message, None when VERP is not configured
+    pub fn return_path(&self, outbox_id: i64) -> Option<String> {
+        self.verp.as_ref().map(|verp| verp.address(outbox_id))
+    }
+
+    /// Preference center URL of a subscriber, None when links are not configured
+    pub fn preferences_url(&self, subscriber_id: i32) -> Option<String> {
+        self.unsubscribe.as_ref().map(|links| links.preferences_url(subscriber_id))
+    }
+
+    /// List-Unsubscribe headers for a subscriber of a campaign, none when links are not configured
+    pub fn unsubscribe_headers(&self, campaign_id: i32, subscriber_id: i32) -> Vec<(String, String)> {
+        self.unsubscribe
+            .as_ref()
+            .map(|links| links.headers(subscriber_id, campaign_id))
+            .unwrap_or_default()
+    }
+
+    /// Take a send token without waiting, Err holds the time until one is available
+    pub fn try_acquire(&self, messenger: Option<&str>, to: &str) -> Result<(), Duration> {
+        self.rate_limiter.try_acquire(messenger.unwrap_or(self.providers.default_provider()), to)
+    }
+
+    /// Take one of the send slots of a campaign, `None` while the campaign is at its concurrency limit
+    pub fn try_campaign_slot(&self, campaign_id: i32) -> Option<OwnedSemaphorePermit> {
+        self.campaign_limiter.try_acquire(campaign_id)
+    }
+
+    /// Resolve the provider for a messenger name, `None` meaning the default one
+    pub fn provider(&self, messenger: Option<&str>) -> Result<&dyn EmailProvider, EmailError> {
+        self.providers.get(messenger)
+    }
+
+    /// Get the messenger configured on a campaign
+    pub async fn campaign_messenger(&self, pool: &PgPool, campaign_id: i32) -> Result<Option<String>, ApiError> {
+        let messenger = sqlx::query_scalar::<_, String>("SELECT messenger FROM campaigns WHERE id = $1")
+            .bind(campaign_id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(messenger)
+    }
 
     //
-    //** Send an email
-    //** Params : to , subject , body
-    //** Return : Result<(), ApiError>
+    //** Send a request through a provider, waiting for the rate limits to allow it
+    //** Params : messenger , request
+    //** Return : Result<SendReceipt, ApiError> (provider message id and reply)
     //
-    pub async fn send_email(&self, to: &str, subject: &str, body: &str) -> Result<(), ApiError> {
-        let email = Message::builder()
-            .from(self.from_email.parse().unwrap())
-            .to(to.parse().unwrap())
-            .subject(subject)
-            .header(ContentType::TEXT_HTML)
-            .body(String::from(body))
-            .unwrap();
-
-        // Clone the necessary data for the spawned task
-        let smtp_client = self.smtp_client.clone();
-        
-        // Spawn a blocking task for SMTP operations
-        task::spawn_blocking(move || {
-            smtp_client
-                .send(&email)
-                .map_err(|e| EmailError::SmtpError(e))
-        })
-        .await
-        .map_err(|e| EmailError::ExecutionError(e.to_string()))?
-        .map_err(ApiError::EmailError)?;
+    pub async fn send_via(&self, messenger: Option<&str>, request: EmailRequest) -> Result<SendReceipt, ApiError> {
+        self.rate_limiter
+            .acquire(messenger.unwrap_or(self.providers.default_provider()), &request.to)
+            .await;
+
+        self.dispatch(messenger, request).await
+    }
 
+    /// Send a request through a provider once its rate limit token is taken
+    pub(crate) async fn dispatch(&self, messenger: Option<&str>, mut request: EmailRequest) -> Result<SendReceipt, ApiError> {
+        if request.from.is_none() {
+            request.from = Some(self.from_email.clone());
+        }
+
+        self.drop_suppressed(&mut request).await?;
+
+        let provider = self.provider(messenger)?;
+        let mut receipt = provider.send(request).await?;
+        if receipt.provider.is_none() {
+            receipt.provider = Some(messenger.unwrap_or(self.providers.default_provider()).to_string());
+        }
+
+        tracing::debug!("Email sent through '{}' with message id {}", provider.provider_name(), receipt.message_id);
+        Ok(receipt)
+    }
+
+    //
+    //** Remove suppressed recipients from a request
+    //** Return : Err(EmailError::Suppressed) when none is left
+    //
+    async fn drop_suppressed(&self, request: &mut EmailRequest) -> Result<(), ApiError> {
+        let Some(suppressions) = &self.suppressions else {
+            return Ok(());
+        };
+
+        // Unparsable recipients are left for the provider to reject
+        let addresses: Vec<(&str, Option<String>)> = recipients(&request.to)
+            .map(|recipient| {
+                let address = recipient.parse::<Mailbox>().ok().map(|mailbox| mailbox.email.to_string().to_lowercase());
+                (recipient, address)
+            })
+            .collect();
+        let lookup: Vec<String> = addresses.iter().filter_map(|(_, address)| address.clone()).collect();
+        let suppressed = suppressions.suppressed(&lookup).await?;
+        if suppressed.is_empty() {
+            return Ok(());
+        }
+
+        let remaining: Vec<&str> = addresses.iter()
+            .filter(|(_, address)| !address.as_ref().is_some_and(|address| suppressed.contains(address)))
+            .map(|(recipient, _)| *recipient)
+            .collect();
+
+        tracing::info!("Not sending to suppressed recipients {:?}", suppressed);
+        if remaining.is_empty() {
+            return Err(EmailError::Suppressed(suppressed.join(", ")).into());
+        }
+
+        request.to = remaining.join(",");
         Ok(())
     }
 
+    //
+    //** Send an email
+    //** Params : to , subject , body
+    //** Return : Result<String, ApiError>
+    //
+    pub async fn send_email(&self, to: &str, subject: &str, body: &str) -> Result<String, ApiError> {
+        self.send_via(None, EmailRequest {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        }).await.map(|receipt| receipt.message_id)
+    }
+
     // Optional: Method for sending multiple emails concurrently
     pub async fn send_bulk_emails(
         &self,
         emails: Vec<(String, String, String)>, // (to, subject, body)
-    ) -> Vec<Result<(), ApiError>> {
-        let futures: Vec<_> = emails
-            .into_iter()
-            .map(|(to, subject, body)| {
-                // Clone self for the async closure
-                let email_service = self.clone();
-                async move {
-                    email_service.send_email(&to, &subject, &body).await
-                }
+    ) -> Vec<Result<String, ApiError>> {
+        // Results keep the order of `emails`
+        stream::iter(emails)
+            .map(|(to, subject, body)| async move {
+                self.send_email(&to, &subject, &body).await
             })
-            .collect();
-
-        futures::future::join_all(futures).await
+            .buffered(self.concurrency)
+            .collect()
+            .await
     }
 
     /// Send emails to multiple recipients in chunks of 10
@@ -134,36 +271,13 @@ impl EmailService {
 
         // Split recipients into chunks of 10
         for chunk in recipients.chunks(CHUNK_SIZE) {
-            // Create a single email message with multiple recipients
-            let mut email = Message::builder()
-                .from(self.from_email.parse().unwrap())
-                .subject(subject)
-                .header(ContentType::TEXT_HTML);
-
-            // Add all recipients in the current chunk
-            for recipient in chunk {
-                email = email.to(recipient.parse().unwrap());
-            }
-
-            // Add the body after adding all recipients
-            let email = email.body(String::from(body))
-                .map_err(|e| EmailError::MessageError(e.to_string()))?;
-
-            // Clone the SMTP client for the spawned task
-            let smtp_client = self.smtp_client.clone();
-
-            // Spawn a blocking task for SMTP operations
-            task::spawn_blocking(move || {
-                smtp_client
-                    .send(&email)
-                    .map_err(EmailError::SmtpError)
-            })
-            .await
-            .map_err(|e| EmailError::ExecutionError(e.to_string()))?
-            .map_err(ApiError::EmailError)?;
-
-            // Small delay between chunks to avoid overwhelming the SMTP server
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            // A single email message with all recipients of the chunk
+            self.send_via(None, EmailRequest {
+                to: chunk.join(","),
+                subject: subject.to_string(),
+                body: body.to_string(),
+                ..Default::default()
+            }).await?;
         }
 
         Ok(())
@@ -228,7 +342,7 @@ impl EmailService {
         campaign_id: i32,
         sequence_email_id: i32,
         subscriber_id: i32,
-    ) -> Result<(), ApiError> {
+    ) -> Result<String, ApiError> {
         let tracked_body = self.add_tracking_to_email(
             body,
             campaign_id,
@@ -239,8 +353,13 @@ impl EmailService {
         self.send_email(to, subject, &tracked_body).await
     }
 
-    // Update send_emails_to_lists to include tracking
-    pub async fn send_emails_to_lists(
+    //
+    //** Queue an email for every confirmed subscriber of the lists
+    //** Messages are sent by the outbox workers, merge tags and tracking are rendered per subscriber
+    //** Params : pool , list_ids , subject , body , campaign_id , sequence_email_id
+    //** Return : Result<QueuedEmailJob, ApiError> (job id to follow the progress)
+    //
+    pub async fn queue_emails_to_lists(
         &self,
         pool: &PgPool,
         list_ids: &[i32],
@@ -248,164 +367,101 @@ impl EmailService {
         body: &str,
         campaign_id: i32,
         sequence_email_id: i32,
-    ) -> Result<BulkEmailStats, ApiError> {
-        const FETCH_SIZE: i64 = 50;
-        
-        tracing::info!("Starting to send emails to lists: {:?}", list_ids);
-        
-        let mut stats = BulkEmailStats {
-            total_subscribers: 0,
-            successful_sends: 0,
-            failed_sends: 0,
-            failures: Vec::new(),
-        };
+    ) -> Result<QueuedEmailJob, ApiError> {
+        let messenger = self.campaign_messenger(pool, campaign_id).await?;
 
-        // Get total count first
-        let total_count: i64 = sqlx::query_scalar!(
-            r#"
-            SELECT COUNT(DISTINCT s.id)::bigint
-            FROM subscribers s
-            JOIN subscriber_lists sl ON s.id = sl.subscriber_id
-            WHERE sl.list_id = ANY($1)
-            AND sl.status = 'confirmed'
-            AND s.status = 'enabled'
-            "#,
-            list_ids
-        )
-        .fetch_one(pool)
-        .await?
-        .unwrap_or(0);
-
-        tracing::info!("Found {} total subscribers to send to", total_count);
-        stats.total_subscribers = total_count as i32;
-        let mut offset: i64 = 0;
-
-        loop {
-            // Fetch next batch of subscribers
-            let subscribers = sqlx::query_as!(
-                Subscriber,
-                r#"
-                SELECT 
-                    s.id, 
-                    s.uuid, 
-                    s.email, 
-                    s.name, 
-                    s.attribs,
-                    s.status as "status: SubscriberStatus",
-                    s.created_at,
-                    s.updated_at
-                FROM subscribers s
-                JOIN subscriber_lists sl ON s.id = sl.subscriber_id
-                WHERE sl.list_id = ANY($1)
-                AND sl.status = 'confirmed'
-                AND s.status = 'enabled'
-                ORDER BY s.id
-                LIMIT $2 OFFSET $3
-                "#,
-                list_ids,
-                FETCH_SIZE,
-                offset
-            )
-            .fetch_all(pool)
+        let job = EmailOutboxRepository::new(pool.clone())
+            .enqueue_lists(list_ids, subject, body, campaign_id, sequence_email_id, messenger.as_deref())
             .await?;
 
-            if subscribers.is_empty() {
-                break;
-            }
-
-            tracing::info!("Processing batch of {} subscribers", subscribers.len());
-
-            // Send emails individually to each subscriber
-            for subscriber in &subscribers {
-                // Add tracking pixel to email body
-                let tracked_body = self.add_tracking_to_email(
-                    body,
-                    campaign_id,
-                    sequence_email_id,
-                    subscriber.id,
-                );
-
-                // Send individual email
-                match self.send_email(&subscriber.email, subject, &tracked_body).await {
-                    Ok(_) => {
-                        stats.successful_sends += 1;
-                        tracing::info!("Successfully sent email to {}", subscriber.email);
-                    }
-                    Err(e) => {
-                        stats.failed_sends += 1;
-                        let error_msg = format!("Failed to send email: {}", e);
-                        tracing::error!("{} for recipient {}", error_msg, subscriber.email);
-                        stats.failures.push((subscriber.email.clone(), error_msg));
-                    }
-                }
-
-                // Add a small delay between sends to avoid overwhelming the SMTP server
-                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-            }
-
-            offset += FETCH_SIZE;
-        }
-
-        tracing::info!(
-            "Completed sending emails. Success: {}, Failed: {}", 
-            stats.successful_sends, 
-            stats.failed_sends
-        );
+        tracing::info!("Queued {} emails to lists {:?} as job {}", job.queued, list_ids, job.job_id);
+        Ok(job)
+    }
 
-        Ok(stats)
+    /// Queue a single message, sent by the outbox workers
+    pub async fn queue_email(&self, pool: &PgPool, message: NewOutboxMessage) -> Result<i64, ApiError> {
+        EmailOutboxRepository::new(pool.clone()).enqueue(message).await
     }
 
-    pub async fn send_campaign_emails(
+    //
+    //** Queue the first active sequence email of a campaign for the lists
+    //** The email body is rendered from its content type, then wrapped in a layout: template_id when given, else its own, its campaign's or the default one.
+    //** A template without {{ content }} placeholder is sent instead of the sequence email, as before layouts
+    //** Params : pool , campaign_id , list_ids , template_id
+    //** Return : Result<QueuedEmailJob, ApiError>
+    //
+    pub async fn queue_campaign_emails(
         &self,
         pool: &PgPool,
         campaign_id: i32,
         list_ids: &[i32],
         template_id: Option<i32>,
-    ) -> Result<CampaignEmailStats, ApiError> {
-        // Get the email content (either from template or sequence email)
-        let (subject, body) = if let Some(template_id) = template_id {
-            let template = sqlx::query!(
-                r#"SELECT subject, body FROM templates WHERE id = $1"#,
-                template_id
-            )
-            .fetch_one(pool)
-            .await?;
-            (template.subject, template.body)
-        } else {
-            let sequence_email = sqlx::query!(
-                r#"
-                SELECT subject, body 
-                FROM sequence_emails 
-                WHERE campaign_id = $1 
-                AND is_active = true
-                AND (send_at IS NULL OR send_at <= NOW())
-                ORDER BY position ASC
-                LIMIT 1
-                "#,
-                campaign_id
-            )
-            .fetch_optional(pool)
-            .await?
-            .ok_or_else(|| ApiError::BadRequest("No active sequence email found for campaign".to_string()))?;
-            
-            (sequence_email.subject, sequence_email.body)
-        };
+    ) -> Result<QueuedEmailJob, ApiError> {
+        let sequence_email = sqlx::query_as::<_, SequenceEmail>(
+            r#"
+            SELECT id, campaign_id, position, subject, body, template_id, content_type, metadata, is_active,
+                   send_at, status, created_at, updated_at, delay_type, delay_value, delay_unit
+            FROM sequence_emails
+            WHERE campaign_id = $1
+            AND is_active = true
+            AND (send_at IS NULL OR send_at <= NOW())
+            ORDER BY position ASC
+            LIMIT 1
+            "#,
+        )
+        .bind(campaign_id)
+        .fetch_optional(pool)
+        .await?;
 
-        // Use send_emails_to_lists for the actual sending
-        let bulk_stats = self.send_emails_to_lists(pool, list_ids, &subject, &body, campaign_id, 0).await?;
+        let template = match template_id {
+            Some(id) => Some(TemplateRepository::new(pool.clone()).find_by_id(id).await?),
+            None => None,
+        };
 
-        // Convert BulkEmailStats to CampaignEmailStats
-        let campaign_stats = CampaignEmailStats {
-            campaign_id,
-            total_subscribers: bulk_stats.total_subscribers,
-            successful_sends: bulk_stats.successful_sends,
-            failed_sends: bulk_stats.failed_sends,
-            failures: bulk_stats.failures,
-            started_at: Utc::now(),
-            completed_at: Some(Utc::now()),
+        let (subject, body) = match (template, sequence_email) {
+            // The template is the whole email
+            (Some(template), sequence_email) if sequence_email.is_none() || !layout::has_placeholder(&template.body) => {
+                (template.subject, self.normalize_html(&template.body))
+            }
+            (Some(template), Some(email)) => {
+                let body = self.render_sequence_body(&email, Some(&template));
+                (email.subject, body)
+            }
+            (None, Some(email)) => {
+                let body = self.prepare_sequence_body(pool, &email).await?;
+                (email.subject, body)
+            }
+            (_, None) => {
+                return Err(ApiError::BadRequest("No active sequence email found for campaign".to_string()));
+            }
         };
 
-        Ok(campaign_stats)
+        // Hand the messages over to the outbox workers
+        self.queue_emails_to_lists(pool, list_ids, &subject, &body, campaign_id, 0).await
+    }
+
+    //
+    //** Body of a sequence email as it is queued
+    //** Rendered from its content type, wrapped in the layout of the email, of its campaign or the default one, then normalized
+    //** Params : pool , email
+    //** Return : Result<String, ApiError>
+    //
+    pub async fn prepare_sequence_body(&self, pool: &PgPool, email: &SequenceEmail) -> Result<String, ApiError> {
+        let layout = TemplateRepository::new(pool.clone())
+            .find_layout(email.template_id, email.campaign_id)
+            .await?;
+
+        Ok(self.render_sequence_body(email, layout.as_ref()))
+    }
+
+    /// Render a sequence email in a given layout, normalized once here rather than per recipient
+    fn render_sequence_body(&self, email: &SequenceEmail, layout: Option<&Template>) -> String {
+        let body = content::render_body(email.content_type, &email.body);
+        let body = match layout {
+            Some(template) => layout::apply(template, &body),
+            None => body,
+        };
+        self.normalize_html(&body)
     }
 
     pub async fn schedule_campaign_emails(
@@ -468,10 +524,25 @@ impl EmailService {
                 let list_ids: Vec<i32> = serde_json::from_value(schedule["list_ids"].clone())?;
                 let template_id: Option<i32> = serde_json::from_value(schedule["template_id"].clone())?;
 
-                // Send the campaign
-                match self.send_campaign_emails(pool, campaign.id, &list_ids, template_id).await {
-                    Ok(_) => {
-                        tracing::info!("Successfully processed scheduled campaign {}", campaign.id);
+                // Queue the campaign
+                match self.queue_campaign_emails(pool, campaign.id, &list_ids, template_id).await {
+                    Ok(job) => {
+                        tracing::info!("Queued scheduled campaign {} as job {}", campaign.id, job.job_id);
+
+                        // Leave the 'scheduled' state so the next tick does not queue it again
+                        sqlx::query(
+                            r#"
+                            UPDATE campaigns
+                            SET status = 'running',
+                                started_at = NOW(),
+                                archive_meta = jsonb_set(COALESCE(archive_meta, '{}'::jsonb), '{job}', $1::jsonb)
+                            WHERE id = $2
+                            "#,
+                        )
+                        .bind(serde_json::to_value(&job)?)
+                        .bind(campaign.id)
+                        .execute(pool)
+                        .await?;
                     }
                     Err(e) => {
                         tracing::error!("Failed to process scheduled campaign {}: {}", campaign.id, e);
@@ -0,0 +1,250 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use chrono::{Duration, Utc};
+
+/// Who unsubscribes, and from which campaign's lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsubscribeToken {
+    pub subscriber_id: i32,
+    pub campaign_id: i32,
+}
+
+fn mac(secret: &[u8], payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+impl UnsubscribeToken {
+    //
+    //** URL-safe token "<payload>.<signature>"
+    //** The payload is not secret, the HMAC only prevents unsubscribing someone else
+    //
+    pub fn sign(&self, secret: &[u8]) -> String {
+        let payload = BASE64URL.encode(format!("{}.{}", self.subscriber_id, self.campaign_id));
+        let signature = BASE64URL.encode(mac(secret, &payload).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    /// None when the token is malformed or was not signed with `secret`
+    pub fn verify(token: &str, secret: &[u8]) -> Option<Self> {
+        let (payload, signature) = token.split_once('.')?;
+        let signature = BASE64URL.decode(signature).ok()?;
+        mac(secret, payload).verify_slice(&signature).ok()?;
+
+        let payload = String::from_utf8(BASE64URL.decode(payload).ok()?).ok()?;
+        let (subscriber_id, campaign_id) = payload.split_once('.')?;
+
+        Some(Self {
+            subscriber_id: subscriber_id.parse().ok()?,
+            campaign_id: campaign_id.parse().ok()?,
+        })
+    }
+}
+
+/// Access to a subscriber's preference center
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreferenceToken {
+    pub subscriber_id: i32,
+}
+
+impl PreferenceToken {
+    // Keeps preference and unsubscribe tokens from being swapped
+    const PREFIX: &'static str = "p.";
+
+    pub fn sign(&self, secret: &[u8]) -> String {
+        let payload = BASE64URL.encode(format!("{}{}", Self::PREFIX, self.subscriber_id));
+        let signature = BASE64URL.encode(mac(secret, &payload).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    pub fn verify(token: &str, secret: &[u8]) -> Option<Self> {
+        let (payload, signature) = token.split_once('.')?;
+        let signature = BASE64URL.decode(signature).ok()?;
+        mac(secret, payload).verify_slice(&signature).ok()?;
+
+        let payload = String::from_utf8(BASE64URL.decode(payload).ok()?).ok()?;
+        Some(Self {
+            subscriber_id: payload.strip_prefix(Self::PREFIX)?.parse().ok()?,
+        })
+    }
+}
+
+/// Confirmation of a double opt-in subscription, valid until `expires_at` (unix time)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptinToken {
+    pub subscriber_id: i32,
+    pub list_id: i32,
+    pub expires_at: i64,
+}
+
+impl OptinToken {
+    const PREFIX: &'static str = "o.";
+
+    pub fn sign(&self, secret: &[u8]) -> String {
+        let payload = BASE64URL.encode(format!(
+            "{}{}.{}.{}",
+            Self::PREFIX, self.subscriber_id, self.list_id, self.expires_at
+        ));
+        let signature = BASE64URL.encode(mac(secret, &payload).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    /// None when the token is malformed, was not signed with `secret` or has expired
+    pub fn verify(token: &str, secret: &[u8]) -> Option<Self> {
+        let (payload, signature) = token.split_once('.')?;
+        let signature = BASE64URL.decode(signature).ok()?;
+        mac(secret, payload).verify_slice(&signature).ok()?;
+
+        let payload = String::from_utf8(BASE64URL.decode(payload).ok()?).ok()?;
+        let mut parts = payload.strip_prefix(Self::PREFIX)?.splitn(3, '.');
+        let token = Self {
+            subscriber_id: parts.next()?.parse().ok()?,
+            list_id: parts.next()?.parse().ok()?,
+            expires_at: parts.next()?.parse().ok()?,
+        };
+
+        (token.expires_at > Utc::now().timestamp()).then_some(token)
+    }
+}
+
+//
+//** RFC 8058 one-click unsubscribe links
+//** UNSUBSCRIBE_URL is the public URL of POST /api/unsubscribe, BASE_URL/api/unsubscribe when unset
+//** PREFERENCES_URL is the public URL of the preference center, BASE_URL/api/preferences when unset
+//** OPTIN_URL is the public URL of the double opt-in confirmation, BASE_URL/api/optin when unset
+//** OPTIN_LINK_TTL_HOURS is how long confirmation links stay valid, 72 when unset
+//** UNSUBSCRIBE_SECRET signs the tokens, API_KEY is used when unset
+//
+#[derive(Clone)]
+pub struct UnsubscribeLinks {
+    base_url: String,
+    preferences_url: String,
+    optin_url: String,
+    optin_ttl: Duration,
+    secret: Vec<u8>,
+}
+
+impl UnsubscribeLinks {
+    pub fn new(base_url: impl Into<String>, preferences_url: impl Into<String>, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            preferences_url: preferences_url.into().trim_end_matches('/').to_string(),
+            optin_url: String::new(),
+            optin_ttl: Duration::hours(72),
+            secret: secret.into(),
+        }
+    }
+
+    /// Where double opt-in confirmation links point to, and for how long they are valid
+    pub fn with_optin(mut self, optin_url: impl Into<String>, ttl: Duration) -> Self {
+        self.optin_url = optin_url.into().trim_end_matches('/').to_string();
+        self.optin_ttl = ttl;
+        self
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let public_url = |path: &str| {
+            format!("{}/api/{}", std::env::var("BASE_URL").unwrap_or_default().trim_end_matches('/'), path)
+        };
+        let base_url = std::env::var("UNSUBSCRIBE_URL").unwrap_or_else(|_| public_url("unsubscribe"));
+        let preferences_url = std::env::var("PREFERENCES_URL").unwrap_or_else(|_| public_url("preferences"));
+        let optin_url = std::env::var("OPTIN_URL").unwrap_or_else(|_| public_url("optin"));
+        let optin_ttl = match std::env::var("OPTIN_LINK_TTL_HOURS") {
+            Ok(hours) => Duration::hours(hours.parse().map_err(|_| format!("Invalid OPTIN_LINK_TTL_HOURS '{}'", hours))?),
+            Err(_) => Duration::hours(72),
+        };
+
+        let secret = std::env::var("UNSUBSCRIBE_SECRET")
+            .or_else(|_| std::env::var("API_KEY"))
+            .map_err(|_| "UNSUBSCRIBE_SECRET or API_KEY must be set".to_string())?;
+
+        Ok(Self::new(base_url, preferences_url, secret).with_optin(optin_url, optin_ttl))
+    }
+
+    /// Double opt-in confirmation link, expiring after the configured TTL
+    pub fn optin_url(&self, subscriber_id: i32, list_id: i32) -> String {
+        let expires_at = (Utc::now() + self.optin_ttl).timestamp();
+        let token = OptinToken { subscriber_id, list_id, expires_at }.sign(&self.secret);
+        format!("{}/{}", self.optin_url, token)
+    }
+
+    pub fn verify_optin(&self, token: &str) -> Option<OptinToken> {
+        OptinToken::verify(token, &self.secret)
+    }
+
+    /// HTML preference center of a subscriber
+    pub fn preferences_url(&self, subscriber_id: i32) -> String {
+        let token = PreferenceToken { subscriber_id }.sign(&self.secret);
+        format!("{}/{}/page", self.preferences_url, token)
+    }
+
+    pub fn verify_preferences(&self, token: &str) -> Option<PreferenceToken> {
+        PreferenceToken::verify(token, &self.secret)
+    }
+
+    pub fn url(&self, subscriber_id: i32, campaign_id: i32) -> String {
+        let token = UnsubscribeToken { subscriber_id, campaign_id }.sign(&self.secret);
+        format!("{}/{}", self.base_url, token)
+    }
+
+    pub fn verify(&self, token: &str) -> Option<UnsubscribeToken> {
+        UnsubscribeToken::verify(token, &self.secret)
+    }
+
+    /// List-Unsubscribe headers of a campaign message
+    pub fn headers(&self, subscriber_id: i32, campaign_id: i32) -> Vec<(String, String)> {
+        vec![
+            ("List-Unsubscribe".to_string(), format!("<{}>", self.url(subscriber_id, campaign_id))),
+            ("List-Unsubscribe-Post".to_string(), "List-Unsubscribe=One-Click".to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_only_its_own_tokens() {
+        let links = UnsubscribeLinks::new(
+            "https://example.com/api/unsubscribe/",
+            "https://example.com/api/preferences",
+            "secret",
+        );
+        let url = links.url(42, 7);
+        let token = url.strip_prefix("https://example.com/api/unsubscribe/").unwrap();
+
+        assert_eq!(links.verify(token), Some(UnsubscribeToken { subscriber_id: 42, campaign_id: 7 }));
+        assert_eq!(UnsubscribeToken::verify(token, b"other secret"), None);
+
+        // Pointing the signature at another subscriber
+        let forged = format!("{}.{}", BASE64URL.encode("43.7"), token.split_once('.').unwrap().1);
+        assert_eq!(links.verify(&forged), None);
+
+        // Unsubscribe and preference tokens are not interchangeable
+        assert_eq!(links.verify_preferences(token), None);
+        let preferences = links.preferences_url(42);
+        let token = preferences
+            .strip_prefix("https://example.com/api/preferences/").unwrap()
+            .strip_suffix("/page").unwrap();
+        assert_eq!(links.verify_preferences(token), Some(PreferenceToken { subscriber_id: 42 }));
+        assert_eq!(links.verify(token), None);
+    }
+
+    #[test]
+    fn optin_links_expire() {
+        let links = UnsubscribeLinks::new("https://example.com/u", "https://example.com/p", "secret")
+            .with_optin("https://example.com/api/optin/", Duration::hours(1));
+
+        let url = links.optin_url(42, 3);
+        let token = url.strip_prefix("https://example.com/api/optin/").unwrap();
+        let verified = links.verify_optin(token).unwrap();
+        assert_eq!((verified.subscriber_id, verified.list_id), (42, 3));
+        assert_eq!(links.verify_preferences(token), None);
+
+        let expired = OptinToken { subscriber_id: 42, list_id: 3, expires_at: Utc::now().timestamp() - 1 };
+        assert_eq!(links.verify_optin(&expired.sign(b"secret")), None);
+    }
+}
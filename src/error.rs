@@ -1,6 +1,6 @@
 use actix_web::{HttpResponse, ResponseError};
 use thiserror::Error;
-use crate::email_service::error::EmailError;
+use crate::email_service::error::{EmailError, FailureKind};
 use serde_json;
 
 #[derive(Debug, Error)]
@@ -17,6 +17,24 @@ pub enum ApiError {
     BadRequest(String),
 }
 
+impl ApiError {
+    /// Failure kind of a send, database errors are retried
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            ApiError::EmailError(e) => e.failure_kind(),
+            ApiError::DatabaseError(_) => FailureKind::Transient,
+            _ => FailureKind::Permanent,
+        }
+    }
+
+    pub fn smtp_code(&self) -> Option<u16> {
+        match self {
+            ApiError::EmailError(e) => e.smtp_code(),
+            _ => None,
+        }
+    }
+}
+
 impl ResponseError for ApiError {
     fn error_response(&self) -> HttpResponse {
         match self {
@@ -29,6 +47,12 @@ impl ResponseError for ApiError {
             ApiError::BadRequest(msg) => {
                 HttpResponse::BadRequest().json(msg)
             }
+            ApiError::EmailError(e @ (EmailError::Suppressed(_) | EmailError::TemplateError(_))) => {
+                HttpResponse::UnprocessableEntity().json(e.to_string())
+            }
+            ApiError::EmailError(e @ EmailError::InvalidSignature(_)) => {
+                HttpResponse::Unauthorized().json(e.to_string())
+            }
             ApiError::EmailError(e) => {
                 HttpResponse::InternalServerError().json(e.to_string())
             }
@@ -11,7 +11,9 @@ use maxminddb::Reader;
 use std::sync::Arc;
 use crate::middleware::auth::AuthMiddleware;     
 use tokio_cron_scheduler::{Job, JobScheduler};
-use api_boilerplate::email_service::EmailService;
+use api_boilerplate::email_service::{EmailService, EmailExecutor, OutboxWorker, ProviderRegistry, RateLimiter, DkimSigner, UnsubscribeLinks, HtmlPipeline};
+use api_boilerplate::email_service::providers::{MockProvider, AwsSesProvider, RoutedProvider};
+use api_boilerplate::email_service::config::{EmailProviderConfig, AwsSesConfig, OutboxConfig, RateLimitConfig, RoutingConfig, DkimDomainConfig, BounceConfig, ComplaintConfig, InboundWebhookConfig, WebhookConfig, HtmlConfig, env_or};
 use api_boilerplate::models::sequence_email::SequenceEmailStatus;
 use actix_cors::Cors;
 use actix_web_prom::PrometheusMetricsBuilder;
@@ -33,6 +35,13 @@ use api_boilerplate::{
         global_stats_repository::GlobalStatsRepository,
         sequence_email_repository::SequenceEmailRepository,
         subscriber_sequence_progress_repository::SubscriberSequenceProgressRepository,
+        delivery_repository::DeliveryRepository,
+        bounce_repository::BounceRepository,
+        complaint_repository::ComplaintRepository,
+        suppression_repository::SuppressionRepository,
+        provider_event_repository::ProviderEventRepository,
+        webhook_repository::WebhookRepository,
+        dkim_key_repository::DkimKeyRepository,
     },
     services::{
         subscriber_service::SubscriberService,
@@ -46,23 +55,111 @@ use api_boilerplate::{
         campaign_stats_service::CampaignStatsService,
         global_stats_service::GlobalStatsService,
         sequence_optin_service::SequenceOptinService,
+        delivery_service::DeliveryService,
+        subscription_service::SubscriptionService,
+        preference_service::PreferenceService,
+        double_optin_service::DoubleOptinService,
+        bounce_service::BounceService,
+        complaint_service::ComplaintService,
+        suppression_service::SuppressionService,
+        inbound_webhook_service::InboundWebhookService,
+        webhook_service::WebhookService,
+        preview_service::PreviewService,
+        webhook_worker::WebhookWorker,
     },
 };
 
 // Import the api module from the crate
 use api_boilerplate::api;
 
-async fn setup_email_service() -> EmailService {
-    let host = std::env::var("SMTP_HOST").expect("SMTP_HOST must be set");
-    let username = std::env::var("SMTP_USERNAME").expect("SMTP_USERNAME must be set");
-    let password = std::env::var("SMTP_PASSWORD").expect("SMTP_PASSWORD must be set");
+//
+//** DKIM keys from the dkim_keys table, and from DKIM_DOMAIN / DKIM_SELECTOR / DKIM_PRIVATE_KEY_PATH
+//** The environment key wins over a database key of the same domain
+//
+async fn setup_dkim(pool: &PgPool) -> Option<Arc<DkimSigner>> {
+    let mut domains: Vec<DkimDomainConfig> = DkimKeyRepository::new(pool.clone())
+        .find_enabled()
+        .await
+        .expect("Failed to load DKIM keys")
+        .into_iter()
+        .map(DkimDomainConfig::from)
+        .collect();
+
+    if let Some(config) = DkimDomainConfig::from_env().expect("Invalid DKIM configuration") {
+        domains.push(config);
+    }
+
+    if domains.is_empty() {
+        info!("No DKIM key configured, messages go out unsigned");
+        return None;
+    }
+
+    let signer = DkimSigner::new(&domains).expect("Failed to load DKIM keys");
+    info!("DKIM signing enabled for {}", signer.domains().join(", "));
+    Some(Arc::new(signer))
+}
+
+async fn setup_email_service(email_metrics: EmailMetrics, dkim: Option<Arc<DkimSigner>>) -> EmailService {
+    // EMAIL_PROVIDER selects smtp (default), ses or mock
+    let config = EmailProviderConfig::from_env().expect("Invalid email provider configuration");
+
+    let mut providers = ProviderRegistry::from_config(&config, dkim.clone())
+        .expect("Failed to create email provider");
+
+    match &config {
+        EmailProviderConfig::Smtp(smtp) => {
+            info!(
+                "SMTP Configuration: host={}, port={}, tls={:?}, auth={}, from={}",
+                smtp.server, smtp.port, smtp.tls, smtp.username.is_some(), smtp.sender_email
+            );
+
+            // Campaigns with messenger "ses" go through Amazon SES when it is configured
+            if let Some(ses_config) = AwsSesConfig::from_env() {
+                let ses_provider = AwsSesProvider::new(ses_config)
+                    .expect("Failed to create SES provider")
+                    .with_dkim(dkim.clone());
+                providers.register("ses", Box::new(ses_provider));
+            }
+        }
+        EmailProviderConfig::Mock(mock) => {
+            info!("Using mock email provider, no email will leave this process");
+
+            // Also serve the "smtp" messenger so existing campaigns go through the mock
+            providers.register("smtp", Box::new(MockProvider::new(mock.clone()).with_dkim(dkim.clone())));
+        }
+        EmailProviderConfig::AwsSes(ses) => {
+            info!("Using Amazon SES in {}, from={}", ses.region, ses.sender_email);
+        }
+    }
+
+    // EMAIL_ROUTES puts several providers behind the default messenger, each route stays usable by name
+    if let Some(routing) = RoutingConfig::from_env().expect("Invalid email routing configuration") {
+        let router = RoutedProvider::from_config(&routing, dkim).expect("Failed to create email routes");
+        for (name, provider) in router.routes() {
+            providers.register(name, Box::new(provider));
+        }
+
+        info!("Routing messenger '{}' over {} providers ({:?})", config.messenger(), routing.routes.len(), routing.strategy);
+        providers.register(config.messenger(), Box::new(router));
+    }
+
     let from_email = std::env::var("FROM_EMAIL").expect("FROM_EMAIL must be set");
 
-    EmailService::new(host, username, password, from_email)
+    // EMAIL_PROVIDER_RATE_LIMITS="smtp:10/s" , EMAIL_DOMAIN_RATE_LIMITS="gmail.com:5/s,gmail.com:2000/h"
+    let rate_limits = RateLimitConfig::from_env().expect("Invalid email rate limit configuration");
+    if !rate_limits.providers.is_empty() || !rate_limits.domains.is_empty() {
+        info!("Email rate limits: providers={:?}, domains={:?}", rate_limits.providers, rate_limits.domains);
+    }
+
+    EmailService::new(from_email, providers)
+        .with_concurrency(env_or("EMAIL_CAMPAIGN_CONCURRENCY", 10))
+        .with_rate_limiter(RateLimiter::new(rate_limits).with_metrics(email_metrics))
+        .with_html_pipeline(HtmlPipeline::new(HtmlConfig::from_env()))
 }
 
 async fn setup_campaign_scheduler(
     email_service: web::Data<EmailService>,
+    email_executor: web::Data<EmailExecutor>,
     pool: web::Data<PgPool>,
     sequence_email_repository: web::Data<SequenceEmailRepository>,
 ) -> Result<JobScheduler, Box<dyn std::error::Error>> {
@@ -71,10 +168,18 @@ async fn setup_campaign_scheduler(
     // Check for scheduled campaigns and sequence emails every minute
     scheduler.add(Job::new_async("0 * * * * *", move |_, _| {
         let email_service = email_service.clone();
+        let email_executor = email_executor.clone();
         let pool = pool.clone();
         let sequence_repo = sequence_email_repository.clone();
         
         Box::pin(async move {
+            // Run scheduled and recurring email jobs that are due
+            match email_executor.run_due_jobs().await {
+                Ok(runs) if !runs.is_empty() => tracing::info!("Ran {} email jobs", runs.len()),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Failed to run email jobs: {}", e),
+            }
+
             // Process scheduled campaigns
             if let Err(e) = email_service.process_scheduled_campaigns(&pool).await {
                 tracing::error!("Failed to process scheduled campaigns: {}", e);
@@ -112,21 +217,30 @@ async fn setup_campaign_scheduler(
                             continue;
                         }
 
+                        // Rendu, mis en page et normalisé comme les autres envois de séquence
+                        let body = match email_service.prepare_sequence_body(&pool, &email).await {
+                            Ok(body) => body,
+                            Err(e) => {
+                                tracing::error!("Failed to prepare sequence email {}: {}", email.id, e);
+                                continue;
+                            }
+                        };
+
                         // Mettre à jour le statut en 'sending'
                         if let Err(e) = sequence_repo.update_status(email.id, SequenceEmailStatus::Sending).await {
                             tracing::error!("Failed to update email status to sending: {}", e);
                             continue;
                         }
 
-                        match email_service.send_emails_to_lists(
+                        match email_service.queue_emails_to_lists(
                             &pool,
                             &list_ids,
                             &email.subject,
-                            &email.body,
+                            &body,
                             email.campaign_id,
                             sequence_id
                         ).await {
-                            Ok(stats) => {
+                            Ok(job) => {
                                 // Mettre à jour le statut en 'sent' et désactiver
                                 if let Err(e) = sequence_repo.update_status(email.id, SequenceEmailStatus::Sent).await {
                                     tracing::error!("Failed to update email status to sent: {}", e);
@@ -144,41 +258,39 @@ async fn setup_campaign_scheduler(
                                 .execute(&**pool)
                                 .await {
                                     Ok(_) => {
-                                        // Mettre à jour les statistiques de la campagne
-                                        let stats_json = match serde_json::to_value(&stats) {
+                                        // Garder le job de la campagne, les workers comptent les envois
+                                        let job_json = match serde_json::to_value(&job) {
                                             Ok(json) => json,
                                             Err(e) => {
-                                                tracing::error!("Failed to serialize stats: {}", e);
+                                                tracing::error!("Failed to serialize job: {}", e);
                                                 continue;
                                             }
                                         };
 
-                                        match sqlx::query!(
+                                        match sqlx::query(
                                             r#"
                                             UPDATE campaigns
-                                            SET sent = sent + $1,
-                                                archive_meta = jsonb_set(
+                                            SET archive_meta = jsonb_set(
                                                     COALESCE(archive_meta, '{}'::jsonb),
-                                                    '{stats}',
-                                                    $2::jsonb
+                                                    '{job}',
+                                                    $1::jsonb
                                                 )
-                                            WHERE id = $3
+                                            WHERE id = $2
                                             "#,
-                                            stats.successful_sends,
-                                            &stats_json,
-                                            email.campaign_id
                                         )
+                                        .bind(&job_json)
+                                        .bind(email.campaign_id)
                                         .execute(&**pool)
                                         .await {
-                                            Ok(_) => tracing::info!("Campaign stats updated successfully"),
-                                            Err(e) => tracing::error!("Failed to update campaign stats: {}", e),
+                                            Ok(_) => tracing::info!("Queued {} emails for campaign {} as job {}", job.queued, email.campaign_id, job.job_id),
+                                            Err(e) => tracing::error!("Failed to update campaign job: {}", e),
                                         }
                                     }
                                     Err(e) => tracing::error!("Failed to deactivate sequence email: {}", e),
                                 }
                             }
                             Err(e) => {
-                                tracing::error!("Failed to send emails: {}", e);
+                                tracing::error!("Failed to queue emails: {}", e);
                                 // Mettre à jour le statut en 'failed'
                                 if let Err(e) = sequence_repo.update_status(email.id, SequenceEmailStatus::Failed).await {
                                     tracing::error!("Failed to update email status to failed: {}", e);
@@ -284,10 +396,20 @@ async fn main() -> std::io::Result<()> {
     let pool = web::Data::new(pool);
 
     // Setup email service
-    let email_service = setup_email_service().await;
+    let dkim = setup_dkim(&pool).await;
+    let unsubscribe_links = UnsubscribeLinks::from_env().expect("Invalid unsubscribe configuration");
+    let bounce_config = BounceConfig::from_env();
+    let email_service = setup_email_service(email_metrics.clone(), dkim)
+        .await
+        .with_unsubscribe_links(unsubscribe_links.clone())
+        .with_verp(bounce_config.verp())
+        .with_suppressions(SuppressionRepository::new(pool.get_ref().clone()));
+
+    // Outbound webhooks, notified by the services below
+    let webhook_service = WebhookService::new(WebhookRepository::new(pool.get_ref().clone()));
 
     // Create services directly without Arc wrapping
-    let email_views_service = EmailViewsService::new(EmailViewsRepository::new(pool.get_ref().clone()));
+    let email_views_service = EmailViewsService::new(EmailViewsRepository::new(pool.get_ref().clone()), webhook_service.clone());
     let subscriber_service = SubscriberService::new(SubscriberRepository::new(pool.get_ref().clone()));
     let list_service = ListService::new(ListsRepository::new(pool.get_ref().clone(), db_metrics.clone()));
     let template_service = TemplateService::new(TemplateRepository::new(pool.get_ref().clone()));
@@ -297,11 +419,27 @@ async fn main() -> std::io::Result<()> {
     let sequence_email_service = SequenceEmailService::new(SequenceEmailRepository::new(pool.get_ref().clone()));
     let campaign_stats_service = CampaignStatsService::new(web::Data::new(CampaignStatsRepository::new(pool.get_ref().clone())));
     let global_stats_service = GlobalStatsService::new(GlobalStatsRepository::new(pool.get_ref().clone()));
+    let delivery_service = DeliveryService::new(DeliveryRepository::new(pool.get_ref().clone()));
+    let complaint_service = ComplaintService::new(
+        ComplaintRepository::new(pool.get_ref().clone()),
+        SubscriberListRepository::new(pool.get_ref().clone()),
+        ComplaintConfig::from_env(),
+        bounce_config.verp(),
+        webhook_service.clone(),
+    );
+    let suppression_service = SuppressionService::new(SuppressionRepository::new(pool.get_ref().clone()));
+    let bounce_service = BounceService::new(BounceRepository::new(pool.get_ref().clone()), bounce_config, webhook_service.clone());
+    let subscription_service = SubscriptionService::new(
+        SubscriberListRepository::new(pool.get_ref().clone()),
+        unsubscribe_links.clone(),
+        webhook_service.clone(),
+    );
     
     // Create SequenceOptinService with the correct arguments
     let sequence_optin_service = SequenceOptinService::new(
         pool.get_ref().clone(),
-        email_service.clone()
+        email_service.clone(),
+        webhook_service.clone(),
     );
 
     // Wrap services in web::Data for the HTTP server - only one layer of wrapping
@@ -315,8 +453,57 @@ async fn main() -> std::io::Result<()> {
     let sequence_email_service_data = web::Data::new(sequence_email_service);
     let campaign_stats_service_data = web::Data::new(campaign_stats_service);
     let global_stats_service_data = web::Data::new(global_stats_service);
+    let delivery_service_data = web::Data::new(delivery_service);
+    let bounce_service_data = web::Data::new(bounce_service);
+    let complaint_service_data = web::Data::new(complaint_service);
+    let suppression_service_data = web::Data::new(suppression_service);
+    let inbound_webhook_service_data = web::Data::new(InboundWebhookService::new(
+        ProviderEventRepository::new(pool.get_ref().clone()),
+        SubscriberListRepository::new(pool.get_ref().clone()),
+        bounce_service_data.clone().into_inner(),
+        complaint_service_data.clone().into_inner(),
+        webhook_service.clone(),
+        InboundWebhookConfig::from_env(),
+    ));
+    let subscription_service_data = web::Data::new(subscription_service);
+    let preview_service_data = web::Data::new(PreviewService::new(pool.get_ref().clone(), email_service.clone()));
     let email_service_data = web::Data::new(email_service.clone());
+    let email_executor_data = web::Data::new(EmailExecutor::new(
+        email_service_data.clone().into_inner(),
+        pool.get_ref().clone(),
+    ));
+
+    // Workers sending the messages queued in email_outbox
+    OutboxWorker::new(
+        email_service_data.clone().into_inner(),
+        pool.get_ref().clone(),
+        OutboxConfig::from_env(),
+    ).spawn();
+
+    // Workers posting the events queued in webhook_deliveries
+    WebhookWorker::new(pool.get_ref().clone(), WebhookConfig::from_env()).spawn();
+
+    // BOUNCE_MAILDIR is read for DSN reports, POST /api/bounces takes them over HTTP
+    bounce_service_data.clone().into_inner().spawn_maildir_poller();
+    // Same for feedback loop reports, COMPLAINT_MAILDIR and POST /api/complaints
+    complaint_service_data.clone().into_inner().spawn_maildir_poller();
     let sequence_optin_service_data = web::Data::new(sequence_optin_service);
+    let double_optin_service_data = web::Data::new(DoubleOptinService::new(
+        pool.get_ref().clone(),
+        ListsRepository::new(pool.get_ref().clone(), db_metrics.clone()),
+        sequence_optin_service_data.clone().into_inner(),
+        email_service.clone(),
+        unsubscribe_links.clone(),
+    ));
+    // Lists ticked in the preference center go through the opt-in service
+    let preference_service_data = web::Data::new(PreferenceService::new(
+        SubscriberRepository::new(pool.get_ref().clone()),
+        SubscriberListRepository::new(pool.get_ref().clone()),
+        unsubscribe_links,
+        webhook_service.clone(),
+        double_optin_service_data.clone().into_inner(),
+    ));
+    let webhook_service_data = web::Data::new(webhook_service);
 
     // Setup GeoIP reader
     let geoip_reader = Reader::open_readfile("GeoIP2-City.mmdb")
@@ -327,6 +514,7 @@ async fn main() -> std::io::Result<()> {
     // Setup the scheduler with wrapped repositories
     if let Err(e) = setup_campaign_scheduler(
         email_service_data.clone(),
+        email_executor_data.clone(),
         pool.clone(),
         web::Data::new(SequenceEmailRepository::new(pool.get_ref().clone())),
     ).await {
@@ -342,24 +530,6 @@ async fn main() -> std::io::Result<()> {
         error!("Failed to setup sequence scheduler: {}", e);
     }
 
-    // Add this after setting up email_service in main()
-    info!("Testing email service connection...");
-    let smtp_host = std::env::var("SMTP_HOST").expect("SMTP_HOST must be set");
-    let smtp_username = std::env::var("SMTP_USERNAME").expect("SMTP_USERNAME must be set");
-    let from_email = std::env::var("FROM_EMAIL").expect("FROM_EMAIL must be set");
-
-    info!("SMTP Configuration: host={}, username={}, from={}", smtp_host, smtp_username, from_email);
-
-    if let Err(e) = email_service.send_email(
-        "your.test@email.com",
-        "Test Email Connection",
-        "<h1>SMTP Test</h1><p>This is a test email to verify SMTP connection is working.</p>"
-    ).await {
-        error!("Failed to send test email: {:?}", e);
-    } else {
-        info!("✅ SMTP test email sent successfully");
-    }
-
     // Configurer le middleware Prometheus avec le registre de métriques
     let prometheus = PrometheusMetricsBuilder::new("api")
         .registry(metrics.registry.clone())
@@ -404,11 +574,22 @@ async fn main() -> std::io::Result<()> {
             .app_data(campaign_service_data.clone())
             .app_data(campaign_list_service_data.clone())
             .app_data(email_service_data.clone())
+            .app_data(email_executor_data.clone())
             .app_data(sequence_email_service_data.clone())
             .app_data(sequence_optin_service_data.clone())
             .app_data(campaign_stats_service_data.clone())
             .app_data(geoip_reader_data.clone())
             .app_data(global_stats_service_data.clone())
+            .app_data(delivery_service_data.clone())
+            .app_data(bounce_service_data.clone())
+            .app_data(complaint_service_data.clone())
+            .app_data(suppression_service_data.clone())
+            .app_data(inbound_webhook_service_data.clone())
+            .app_data(webhook_service_data.clone())
+            .app_data(subscription_service_data.clone())
+            .app_data(preference_service_data.clone())
+            .app_data(preview_service_data.clone())
+            .app_data(double_optin_service_data.clone())
             .app_data(web::Data::new(db_metrics.clone()))
             .app_data(web::Data::new(email_metrics.clone()))
             
@@ -56,6 +56,10 @@ where
         let public_paths = vec![
             "/api/email-views/",
             "/api/email-views",
+            "/api/unsubscribe/",
+            "/api/preferences/",
+            "/api/optin/",
+            "/api/webhooks/inbound/",
             "/health",
             "/metrics",
         ];
@@ -0,0 +1,53 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+/// Hard bounces are permanent (unknown mailbox), soft ones may go away (mailbox full, greylisting)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "bounce_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BounceType {
+    Hard,
+    Soft,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Bounce {
+    pub id: i64,
+    pub subscriber_id: Option<i32>,
+    pub delivery_id: Option<i64>,
+    pub campaign_id: Option<i32>,
+    pub email: String,
+    pub bounce_type: BounceType,
+    pub status_code: Option<String>,
+    pub diagnostic: Option<String>,
+    pub source: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A bounce to record, matched to its delivery by outbox id (VERP) or Message-ID
+#[derive(Debug, Clone)]
+pub struct NewBounce {
+    pub email: String,
+    pub bounce_type: BounceType,
+    pub status_code: Option<String>,
+    pub diagnostic: Option<String>,
+    pub source: String,
+    pub outbox_id: Option<i64>,
+    pub message_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct BounceFilter {
+    pub subscriber_id: Option<i32>,
+    pub campaign_id: Option<i32>,
+    pub bounce_type: Option<BounceType>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BounceResponse {
+    pub items: Vec<Bounce>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
@@ -234,4 +234,17 @@ pub struct CampaignResponse<T> {
     pub items: Vec<T>,
     pub page: i64,
     pub per_page: i64,
+}
+
+/// Layout template the sequence emails of a campaign are wrapped in
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct CampaignLayout {
+    pub campaign_id: i32,
+    // The default template is used when unset
+    pub layout_template_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetCampaignLayoutDto {
+    pub template_id: Option<i32>,
 }
\ No newline at end of file
@@ -16,6 +16,16 @@ pub struct CampaignStats {
     pub unique_opens: i64,
     pub open_rate: f64,
     pub unopened_count: i64,
+    // Lists left by subscribers themselves (one-click link, preference center or provider link)
+    pub voluntary_unsubscribes: i64,
+    pub unsubscribe_rate: f64,
+    // Spam complaints from feedback loops
+    pub complaints: i64,
+    pub complaint_rate: f64,
+    // Reported by provider webhooks
+    pub delivered: i64,
+    pub unique_clicks: i64,
+    pub click_rate: f64,
     
     // Sequence Stats
     pub total_sequence_emails: i64,
@@ -94,6 +104,7 @@ pub struct SequenceEmailDetailedStats {
     pub status: String,
     pub sent_at: Option<DateTime<Utc>>,
     pub total_subscribers: i64,
+    pub total_sent: i64,
     pub total_opens: i64,
     pub unique_opens: i64,
     pub open_rate: f64,
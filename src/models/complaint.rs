@@ -0,0 +1,41 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Complaint {
+    pub id: i64,
+    pub subscriber_id: Option<i32>,
+    pub delivery_id: Option<i64>,
+    pub campaign_id: Option<i32>,
+    pub email: Option<String>,
+    pub feedback_type: String,
+    pub user_agent: Option<String>,
+    pub source: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A complaint to record, matched to its delivery by outbox id (VERP) or Message-ID
+#[derive(Debug, Clone)]
+pub struct NewComplaint {
+    pub email: Option<String>,
+    pub feedback_type: String,
+    pub user_agent: Option<String>,
+    pub source: String,
+    pub outbox_id: Option<i64>,
+    pub message_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ComplaintFilter {
+    pub subscriber_id: Option<i32>,
+    pub campaign_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComplaintResponse {
+    pub items: Vec<Complaint>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
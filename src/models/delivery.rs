@@ -0,0 +1,73 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Queued,
+    Sent,
+    Failed,
+    Bounced,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Delivery {
+    pub id: i64,
+    pub campaign_id: i32,
+    pub sequence_email_id: i32,
+    pub subscriber_id: i32,
+    pub messenger: Option<String>,
+    // Route or messenger that accepted the message
+    pub provider: Option<String>,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub message_id: Option<String>,
+    pub smtp_response: Option<String>,
+    pub queued_at: Option<DateTime<Utc>>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub bounced_at: Option<DateTime<Utc>>,
+    // Reported delivered by the provider webhook
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct DeliveryFilter {
+    pub campaign_id: Option<i32>,
+    pub sequence_email_id: Option<i32>,
+    pub subscriber_id: Option<i32>,
+    pub status: Option<DeliveryStatus>,
+    pub message_id: Option<String>,
+    pub provider: Option<String>,
+    // Queued at or after
+    pub from: Option<DateTime<Utc>>,
+    // Queued before
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveryPagination {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    50
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeliveryResponse {
+    pub items: Vec<Delivery>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
@@ -0,0 +1,34 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use crate::email_service::config::{default_dkim_headers, DkimAlgorithm, DkimDomainConfig};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DkimKey {
+    pub id: i32,
+    pub domain: String,
+    pub selector: String,
+    pub algorithm: String,
+    #[serde(skip_serializing)]
+    pub private_key: String,
+    pub headers: Option<Vec<String>>,
+    pub enabled: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<DkimKey> for DkimDomainConfig {
+    fn from(key: DkimKey) -> Self {
+        Self {
+            domain: key.domain,
+            selector: key.selector,
+            algorithm: match key.algorithm.as_str() {
+                "ed25519" => DkimAlgorithm::Ed25519,
+                _ => DkimAlgorithm::Rsa,
+            },
+            private_key: Some(key.private_key),
+            private_key_path: None,
+            headers: key.headers.unwrap_or_else(default_dkim_headers),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use sqlx::types::Json;
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use crate::email_service::models::{EmailRequest, SendStrategy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "email_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmailJobStatus {
+    Scheduled,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Outcome of a job run, stored with when the job is due next
+#[derive(Debug, Clone)]
+pub struct EmailJobRun {
+    pub status: EmailJobStatus,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub runs_completed: i32,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub last_result: JsonValue,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct EmailJob {
+    pub id: i32,
+    pub strategy: Json<SendStrategy>,
+    pub requests: Json<Vec<EmailRequest>>,
+    pub messenger: Option<String>,
+    pub status: EmailJobStatus,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub runs_completed: i32,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub last_result: Option<JsonValue>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,57 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use sqlx::types::Json;
+use chrono::{DateTime, Utc};
+use crate::email_service::models::EmailRequest;
+use crate::models::email_job::EmailJobStatus;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "outbox_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    Queued,
+    Sending,
+    Sent,
+    Failed,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct OutboxMessage {
+    pub id: i64,
+    pub job_id: Option<i32>,
+    pub campaign_id: Option<i32>,
+    pub sequence_email_id: Option<i32>,
+    pub subscriber_id: Option<i32>,
+    pub messenger: Option<String>,
+    pub request: Json<EmailRequest>,
+    pub status: OutboxStatus,
+    pub attempts: i32,
+    pub available_at: DateTime<Utc>,
+    pub message_id: Option<String>,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// A message to add to the outbox
+#[derive(Debug, Clone, Default)]
+pub struct NewOutboxMessage {
+    pub request: EmailRequest,
+    pub messenger: Option<String>,
+    pub campaign_id: Option<i32>,
+    pub sequence_email_id: Option<i32>,
+    pub subscriber_id: Option<i32>,
+}
+
+/// Progress of a queued job, counted from its outbox rows
+#[derive(Debug, Serialize, FromRow)]
+pub struct EmailJobProgress {
+    pub job_id: i32,
+    pub status: EmailJobStatus,
+    pub total: i64,
+    pub queued: i64,
+    pub sending: i64,
+    pub sent: i64,
+    pub failed: i64,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
@@ -9,4 +9,16 @@ pub mod email_views;
 pub mod sequence_email;
 pub mod campaign_stats;
 pub mod global_stats;
-pub mod subscriber_sequence_progress;
\ No newline at end of file
+pub mod subscriber_sequence_progress;
+pub mod email_job;
+pub mod email_outbox;
+pub mod delivery;
+pub mod dkim_key;
+pub mod subscription_event;
+pub mod preference;
+pub mod bounce;
+pub mod complaint;
+pub mod suppression;
+pub mod provider_event;
+pub mod webhook;
+pub mod preview;
\ No newline at end of file
@@ -0,0 +1,37 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use sqlx::types::JsonValue;
+use crate::models::subscriber_list::SubscriptionStatus;
+
+/// A public list as seen from the preference center
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ListPreference {
+    pub list_id: i32,
+    pub name: String,
+    pub description: String,
+    // None when the subscriber was never on the list
+    pub status: Option<SubscriptionStatus>,
+}
+
+impl ListPreference {
+    pub fn subscribed(&self) -> bool {
+        matches!(self.status, Some(SubscriptionStatus::Unconfirmed | SubscriptionStatus::Confirmed))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubscriberPreferences {
+    pub email: String,
+    pub name: Option<String>,
+    // Only the attributes subscribers may edit
+    pub attribs: serde_json::Map<String, JsonValue>,
+    pub lists: Vec<ListPreference>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct UpdatePreferencesDto {
+    pub name: Option<String>,
+    pub attribs: Option<serde_json::Map<String, JsonValue>>,
+    // Public lists to be on, the other public lists are unsubscribed
+    pub list_ids: Option<Vec<i32>>,
+}
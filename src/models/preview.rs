@@ -0,0 +1,43 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+/// Who a template or sequence email is rendered for
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PreviewDto {
+    // Existing subscriber, its fields, attribs and lists are used
+    pub subscriber_id: Option<i32>,
+    // Sample subscriber used when no subscriber_id is given
+    pub email: Option<String>,
+    pub name: Option<String>,
+    // Attribs of the sample subscriber, merged over those of subscriber_id
+    pub attribs: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewResponse {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Rendered message sent to seed addresses, outside of any campaign stats
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestSendDto {
+    #[serde(flatten)]
+    pub preview: PreviewDto,
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestSendResult {
+    pub to: String,
+    pub message_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestSendResponse {
+    pub sent: usize,
+    pub failed: usize,
+    pub results: Vec<TestSendResult>,
+}
@@ -0,0 +1,37 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+/// Delivery events reported by provider webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "provider_event_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderEventType {
+    Delivered,
+    // Temporary failure, the provider keeps retrying
+    Deferred,
+    Bounce,
+    Complaint,
+    Open,
+    Click,
+    Unsubscribe,
+    // Not attempted by the provider (its own suppression list, invalid address)
+    Dropped,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ProviderEvent {
+    pub id: i64,
+    pub provider: String,
+    pub event_id: Option<String>,
+    pub event_type: ProviderEventType,
+    pub email: Option<String>,
+    pub message_id: Option<String>,
+    pub delivery_id: Option<i64>,
+    pub subscriber_id: Option<i32>,
+    pub campaign_id: Option<i32>,
+    pub url: Option<String>,
+    pub diagnostic: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
+}
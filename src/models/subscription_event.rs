@@ -0,0 +1,43 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "subscription_event_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionEventType {
+    Subscribed,
+    Unsubscribed,
+}
+
+/// Where a subscription change came from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "subscription_event_source", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionEventSource {
+    OneClick,
+    PreferenceCenter,
+    Api,
+    // Spam complaint from a feedback loop
+    Complaint,
+    // Unsubscribe link of a sending provider, reported by its webhook
+    Provider,
+}
+
+impl SubscriptionEventSource {
+    /// Changes made by the subscriber themselves
+    pub fn is_voluntary(&self) -> bool {
+        matches!(self, Self::OneClick | Self::PreferenceCenter | Self::Provider)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SubscriptionEvent {
+    pub id: i64,
+    pub subscriber_id: i32,
+    pub list_id: Option<i32>,
+    pub campaign_id: Option<i32>,
+    pub event: SubscriptionEventType,
+    pub source: SubscriptionEventSource,
+    pub created_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,103 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "suppression_reason", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SuppressionReason {
+    Bounce,
+    Complaint,
+    #[default]
+    Manual,
+    Legal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Suppression {
+    pub id: i64,
+    // Lowercased address, or domain when is_domain
+    pub value: String,
+    pub is_domain: bool,
+    pub reason: SuppressionReason,
+    pub note: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// An address (user@example.com) or a whole domain (example.com or @example.com)
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateSuppressionDto {
+    pub value: String,
+    #[serde(default)]
+    pub reason: SuppressionReason,
+    pub note: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSuppressionDto {
+    pub reason: Option<SuppressionReason>,
+    pub note: Option<String>,
+    // Replaces the expiry, null or missing makes the entry permanent
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Many addresses or domains sharing a reason, existing entries are updated
+#[derive(Debug, Deserialize)]
+pub struct ImportSuppressionsDto {
+    pub values: Vec<String>,
+    #[serde(default)]
+    pub reason: SuppressionReason,
+    pub note: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSuppressionsResponse {
+    pub imported: u64,
+    // Values that are neither an address nor a domain
+    pub invalid: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct SuppressionFilter {
+    // Substring of the address or domain
+    pub query: Option<String>,
+    pub reason: Option<SuppressionReason>,
+    pub is_domain: Option<bool>,
+    // Include entries past their expiry
+    #[serde(default)]
+    pub include_expired: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuppressionResponse {
+    pub items: Vec<Suppression>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+//
+//** Normalize a suppression value
+//** Return : (value, is_domain), None when it is neither an address nor a domain
+//
+pub fn normalize_value(value: &str) -> Option<(String, bool)> {
+    let value = value.trim().trim_start_matches('<').trim_end_matches('>').trim().to_lowercase();
+    let valid_domain = |domain: &str| {
+        domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.')
+            && domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    };
+
+    match value.rsplit_once('@') {
+        Some(("", domain)) => valid_domain(domain).then(|| (domain.to_string(), true)),
+        Some((local, domain)) if !local.contains(char::is_whitespace) && valid_domain(domain) => Some((value.clone(), false)),
+        Some(_) => None,
+        None => valid_domain(&value).then(|| (value.clone(), true)),
+    }
+}
+
@@ -0,0 +1,132 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_event_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    Open,
+    Click,
+    Unsubscribe,
+    Bounce,
+    Complaint,
+    SequenceCompleted,
+    // Sent by the test endpoint, whatever the filters of the webhook
+    Test,
+}
+
+impl PgHasArrayType for WebhookEventType {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_webhook_event_type")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Sending,
+    Success,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Webhook {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    pub secret: String,
+    // Empty for every event type
+    pub event_types: Vec<WebhookEventType>,
+    pub enabled: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookDto {
+    pub name: String,
+    pub url: String,
+    // Generated when missing
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub event_types: Vec<WebhookEventType>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookDto {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub event_types: Option<Vec<WebhookEventType>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i32,
+    pub event_type: WebhookEventType,
+    pub payload: JsonValue,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub available_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub last_status_code: Option<i32>,
+    pub last_response: Option<String>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A delivery claimed by a worker, with where and how to send it
+#[derive(Debug, Clone, FromRow)]
+pub struct ClaimedWebhookDelivery {
+    #[sqlx(flatten)]
+    pub delivery: WebhookDelivery,
+    pub url: String,
+    pub secret: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct WebhookDeliveryFilter {
+    pub webhook_id: Option<i32>,
+    pub event_type: Option<WebhookEventType>,
+    pub status: Option<WebhookDeliveryStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryResponse {
+    pub items: Vec<WebhookDelivery>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+/// Something that happened to a subscriber, sent to every webhook listening to its type
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub event_type: WebhookEventType,
+    pub subscriber_id: Option<i32>,
+    pub campaign_id: Option<i32>,
+    // Event specific details
+    pub data: JsonValue,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl WebhookEvent {
+    pub fn new(event_type: WebhookEventType, subscriber_id: Option<i32>, campaign_id: Option<i32>, data: JsonValue) -> Self {
+        Self { event_type, subscriber_id, campaign_id, data, occurred_at: Utc::now() }
+    }
+}
@@ -27,6 +27,8 @@ pub struct Metrics {
     pub database_query_duration: GaugeVec,
     pub email_sent_total: IntCounter,
     pub email_failed_total: IntCounter,
+    pub email_throttle_tokens: GaugeVec,
+    pub email_throttled_total: IntCounterVec,
 }
 
 impl Metrics {
@@ -87,6 +89,17 @@ impl Metrics {
             opts!("email_failed_total", "Total number of failed email sends")
         ).unwrap();
         
+        // Limites d'envoi par provider et par domaine
+        let email_throttle_tokens = register_gauge_vec!(
+            opts!("email_throttle_tokens", "Messages that can be sent right now under a rate limit"),
+            &["scope", "key"]
+        ).unwrap();
+
+        let email_throttled_total = register_int_counter_vec!(
+            opts!("email_throttled_total", "Total number of sends delayed by a rate limit"),
+            &["scope", "key"]
+        ).unwrap();
+        
         // Enregistrer toutes les métriques dans le registre
         registry.register(Box::new(cpu_usage.clone())).unwrap();
         registry.register(Box::new(memory_usage.clone())).unwrap();
@@ -100,6 +113,8 @@ impl Metrics {
         registry.register(Box::new(database_query_duration.clone())).unwrap();
         registry.register(Box::new(email_sent_total.clone())).unwrap();
         registry.register(Box::new(email_failed_total.clone())).unwrap();
+        registry.register(Box::new(email_throttle_tokens.clone())).unwrap();
+        registry.register(Box::new(email_throttled_total.clone())).unwrap();
         
         Metrics {
             registry,
@@ -115,6 +130,8 @@ impl Metrics {
             database_query_duration,
             email_sent_total,
             email_failed_total,
+            email_throttle_tokens,
+            email_throttled_total,
         }
     }
     
@@ -309,6 +326,14 @@ impl EmailMetrics {
     pub fn record_failed_email(&self) {
         self.metrics.email_failed_total.inc();
     }
+
+    pub fn record_throttle_tokens(&self, scope: &str, key: &str, tokens: f64) {
+        self.metrics.email_throttle_tokens.with_label_values(&[scope, key]).set(tokens);
+    }
+
+    pub fn record_throttled(&self, scope: &str, key: &str) {
+        self.metrics.email_throttled_total.with_label_values(&[scope, key]).inc();
+    }
 }
 
 impl Clone for EmailMetrics {
@@ -0,0 +1,124 @@
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use crate::{
+    error::ApiError,
+    models::bounce::{Bounce, BounceFilter, BounceResponse, NewBounce},
+    models::delivery::DeliveryPagination,
+};
+
+// Upper bound of per_page, a page is loaded in memory
+const MAX_PER_PAGE: i64 = 500;
+
+#[derive(Clone)]
+pub struct BounceRepository {
+    pool: PgPool
+}
+
+impl BounceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    //
+    //** Record a bounce against its delivery and subscriber
+    //** The delivery is found by outbox id (VERP) first, then by Message-ID, the subscriber by
+    //** delivery or else by address. The delivery is marked bounced, and the subscriber is
+    //** blocklisted once it reaches hard_bounce_limit hard bounces, its address suppressed
+    //** Params : bounce , hard_bounce_limit
+    //** Return : Result<Bounce, ApiError>
+    //
+    pub async fn record(&self, bounce: NewBounce, hard_bounce_limit: i64) -> Result<Bounce, ApiError> {
+        let recorded = sqlx::query_as::<_, Bounce>(
+            r#"
+            WITH target AS (
+                SELECT d.id, d.subscriber_id, d.campaign_id
+                FROM deliveries d
+                WHERE d.id = (SELECT delivery_id FROM email_outbox WHERE id = $1)
+                OR d.message_id IN ($2, '<' || $2 || '>')
+                ORDER BY (d.id = (SELECT delivery_id FROM email_outbox WHERE id = $1)) DESC NULLS LAST
+                LIMIT 1
+            ),
+            subscriber AS (
+                SELECT COALESCE(
+                    (SELECT subscriber_id FROM target),
+                    (SELECT id FROM subscribers WHERE LOWER(email) = LOWER($3) LIMIT 1)
+                ) AS id
+            ),
+            delivery AS (
+                UPDATE deliveries d
+                SET status = 'bounced', bounced_at = NOW(), smtp_response = COALESCE($6, d.smtp_response),
+                    updated_at = NOW()
+                FROM target
+                WHERE d.id = target.id
+            ),
+            blocklist AS (
+                UPDATE subscribers s
+                SET status = 'blocklisted', updated_at = NOW()
+                FROM subscriber
+                WHERE s.id = subscriber.id
+                AND $4 = 'hard'
+                AND s.status <> 'blocklisted'
+                -- The bounce inserted below is not visible yet, hence the + 1
+                AND (SELECT COUNT(*) FROM bounces b WHERE b.subscriber_id = s.id AND b.bounce_type = 'hard') + 1 >= $8
+            ),
+            suppress AS (
+                INSERT INTO suppressions (value, reason, note)
+                SELECT LOWER($3), 'bounce', $6
+                WHERE $4 = 'hard'
+                AND (SELECT COUNT(*) FROM bounces b WHERE LOWER(b.email) = LOWER($3) AND b.bounce_type = 'hard') + 1 >= $8
+                ON CONFLICT (value) DO NOTHING
+            )
+            INSERT INTO bounces (subscriber_id, delivery_id, campaign_id, email, bounce_type, status_code, diagnostic, source)
+            SELECT (SELECT id FROM subscriber), (SELECT id FROM target), (SELECT campaign_id FROM target),
+                   $3, $4, $5, $6, $7
+            RETURNING *
+            "#,
+        )
+        .bind(bounce.outbox_id)
+        .bind(bounce.message_id)
+        .bind(bounce.email)
+        .bind(bounce.bounce_type)
+        .bind(bounce.status_code)
+        .bind(bounce.diagnostic)
+        .bind(bounce.source)
+        .bind(hard_bounce_limit)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(recorded)
+    }
+
+    /// List bounces, newest first
+    pub async fn find_all(&self, filter: BounceFilter, pagination: DeliveryPagination) -> Result<BounceResponse, ApiError> {
+        let page = pagination.page.max(1);
+        let per_page = pagination.per_page.clamp(1, MAX_PER_PAGE);
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM bounces");
+        push_filter(&mut count, &filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM bounces");
+        push_filter(&mut query, &filter);
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        query.push_bind(per_page);
+        query.push(" OFFSET ");
+        query.push_bind((page - 1) * per_page);
+
+        let items = query.build_query_as::<Bounce>().fetch_all(&self.pool).await?;
+
+        Ok(BounceResponse { items, page, per_page, total })
+    }
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &BounceFilter) {
+    query.push(" WHERE TRUE");
+
+    if let Some(subscriber_id) = filter.subscriber_id {
+        query.push(" AND subscriber_id = ").push_bind(subscriber_id);
+    }
+    if let Some(campaign_id) = filter.campaign_id {
+        query.push(" AND campaign_id = ").push_bind(campaign_id);
+    }
+    if let Some(bounce_type) = filter.bounce_type {
+        query.push(" AND bounce_type = ").push_bind(bounce_type);
+    }
+}
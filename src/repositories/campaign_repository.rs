@@ -4,7 +4,7 @@ use chrono::{DateTime, Utc};
 
 
 use crate::{
-    models::campaign::{Campaign,  CampaignStatus, CampaignType, CreateCampaignDto, DeleteCampaignDto, PaginationParams, CampaignFilter, UpdateCampaignDto , CampaignResponse},
+    models::campaign::{Campaign,  CampaignStatus, CampaignType, CreateCampaignDto, DeleteCampaignDto, PaginationParams, CampaignFilter, UpdateCampaignDto , CampaignResponse, CampaignLayout},
     error::ApiError,
 };
 
@@ -353,4 +353,34 @@ impl CampaignRepository {
 
         Ok(())
     }
+
+    pub async fn find_layout(&self, id: i32) -> Result<Option<CampaignLayout>, ApiError> {
+        let layout = sqlx::query_as::<_, CampaignLayout>(
+            "SELECT id AS campaign_id, layout_template_id FROM campaigns WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(layout)
+    }
+
+    /// Set or clear the layout of a campaign, None when the template does not exist
+    pub async fn set_layout(&self, id: i32, template_id: Option<i32>) -> Result<Option<CampaignLayout>, ApiError> {
+        let layout = sqlx::query_as::<_, CampaignLayout>(
+            r#"
+            UPDATE campaigns
+            SET layout_template_id = $2, updated_at = NOW()
+            WHERE id = $1
+            AND ($2::int IS NULL OR EXISTS (SELECT 1 FROM templates WHERE id = $2))
+            RETURNING id AS campaign_id, layout_template_id
+            "#,
+        )
+        .bind(id)
+        .bind(template_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(layout)
+    }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use sqlx::PgPool;
+use std::collections::HashMap;
 use crate::error::ApiError;
 use crate::models::campaign_stats::*;
 
@@ -12,6 +13,72 @@ impl CampaignStatsRepository {
         Self { pool }
     }
 
+    //
+    //** Messages accepted by the provider per sequence email (0 = sent without sequence email)
+    //** Empty for campaigns sent before deliveries were logged
+    //
+    async fn delivered_counts(&self, campaign_id: i32) -> Result<HashMap<i32, i64>, ApiError> {
+        let rows = sqlx::query_as::<_, (i32, i64)>(
+            r#"
+            SELECT sequence_email_id, COUNT(*)
+            FROM deliveries
+            WHERE campaign_id = $1 AND status IN ('sent', 'bounced')
+            GROUP BY sequence_email_id
+            "#,
+        )
+        .bind(campaign_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Subscribers who unsubscribed themselves from the campaign
+    async fn voluntary_unsubscribes(&self, campaign_id: i32) -> Result<i64, ApiError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(DISTINCT subscriber_id)
+            FROM subscription_events
+            WHERE campaign_id = $1
+            AND event = 'unsubscribed'
+            AND source IN ('one_click', 'preference_center', 'provider')
+            "#,
+        )
+        .bind(campaign_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Deliveries confirmed by provider webhooks, and subscribers who clicked a link
+    async fn provider_activity(&self, campaign_id: i32) -> Result<(i64, i64), ApiError> {
+        let activity = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM deliveries WHERE campaign_id = $1 AND delivered_at IS NOT NULL),
+                (SELECT COUNT(DISTINCT subscriber_id) FROM provider_events WHERE campaign_id = $1 AND event_type = 'click')
+            "#,
+        )
+        .bind(campaign_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(activity)
+    }
+
+    /// Subscribers who reported the campaign as spam
+    async fn complaints(&self, campaign_id: i32) -> Result<i64, ApiError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(DISTINCT subscriber_id) FROM complaints WHERE campaign_id = $1"
+        )
+        .bind(campaign_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
     pub async fn get_campaign_stats(&self, campaign_id: i32) -> Result<CampaignStats, ApiError> {
         // Get campaign info
         let campaign = sqlx::query!(
@@ -266,8 +333,20 @@ impl CampaignStatsRepository {
         .fetch_all(&self.pool)
         .await?;
 
+        // Prefer the delivery log over campaigns.sent when the campaign has one
+        let delivered = self.delivered_counts(campaign_id).await?;
+        let mut sequence_stats = sequence_stats;
+        apply_delivered_counts(&mut sequence_stats, &delivered);
+        let voluntary_unsubscribes = self.voluntary_unsubscribes(campaign_id).await?;
+        let complaints = self.complaints(campaign_id).await?;
+        let (provider_delivered, unique_clicks) = self.provider_activity(campaign_id).await?;
+
         let unopened_count = unopened_subscribers.len() as i64;
-        let total_sent = overall_stats.total_sent.unwrap_or(0);
+        let total_sent = if delivered.is_empty() {
+            overall_stats.total_sent.unwrap_or(0)
+        } else {
+            delivered.values().sum()
+        };
         let unique_opens = overall_stats.unique_opens.unwrap_or(0);
         let total_opens = overall_stats.total_opens.unwrap_or(0);
 
@@ -287,6 +366,25 @@ impl CampaignStatsRepository {
             total_opens,
             unique_opens,
             open_rate,
+            voluntary_unsubscribes,
+            unsubscribe_rate: if total_sent > 0 {
+                (voluntary_unsubscribes as f64 / total_sent as f64) * 100.0
+            } else {
+                0.0
+            },
+            complaints,
+            complaint_rate: if total_sent > 0 {
+                (complaints as f64 / total_sent as f64) * 100.0
+            } else {
+                0.0
+            },
+            delivered: provider_delivered,
+            unique_clicks,
+            click_rate: if total_sent > 0 {
+                (unique_clicks as f64 / total_sent as f64) * 100.0
+            } else {
+                0.0
+            },
             total_sequence_emails: sequence_stats.len() as i64,
             sequence_stats,
             country_stats,
@@ -423,13 +521,27 @@ impl CampaignStatsRepository {
         .fetch_all(&self.pool)
         .await?;
 
+        let delivered = self.delivered_counts(campaign_id).await?;
+        let mut sequence_stats = sequence_stats;
+        apply_delivered_counts(&mut sequence_stats, &delivered);
+        let voluntary_unsubscribes = self.voluntary_unsubscribes(campaign_id).await?;
+        let complaints = self.complaints(campaign_id).await?;
+        let (provider_delivered, unique_clicks) = self.provider_activity(campaign_id).await?;
+
         let total_subscribers = base_stats.total_subscribers.unwrap_or(0);
         let unopened_count = unopened_subscribers.len() as i64;
         let unique_opens = base_stats.unique_opens.unwrap_or(0);
         let total_opens = base_stats.total_opens.unwrap_or(0);
 
-        let open_rate = if total_subscribers > 0 {
-            (unique_opens as f64 / total_subscribers as f64) * 100.0
+        // Without a delivery log, total subscribers is our total sent for sequence campaigns
+        let total_sent = if delivered.is_empty() {
+            total_subscribers
+        } else {
+            delivered.values().sum()
+        };
+
+        let open_rate = if total_sent > 0 {
+            (unique_opens as f64 / total_sent as f64) * 100.0
         } else {
             0.0
         };
@@ -440,10 +552,29 @@ impl CampaignStatsRepository {
             status: campaign.status,
             start_date: campaign.started_at,
             total_subscribers,
-            total_sent: total_subscribers, // Total subscribers is our total sent for sequence campaigns
+            total_sent,
             total_opens,
             unique_opens,
             open_rate,
+            voluntary_unsubscribes,
+            unsubscribe_rate: if total_sent > 0 {
+                (voluntary_unsubscribes as f64 / total_sent as f64) * 100.0
+            } else {
+                0.0
+            },
+            complaints,
+            complaint_rate: if total_sent > 0 {
+                (complaints as f64 / total_sent as f64) * 100.0
+            } else {
+                0.0
+            },
+            delivered: provider_delivered,
+            unique_clicks,
+            click_rate: if total_sent > 0 {
+                (unique_clicks as f64 / total_sent as f64) * 100.0
+            } else {
+                0.0
+            },
             unopened_count,
             total_sequence_emails: sequence_stats.len() as i64,
             sequence_stats,
@@ -535,8 +666,13 @@ impl CampaignStatsRepository {
         .fetch_all(&self.pool)
         .await?;
 
-        let open_rate = if base_stats.total_subscribers > 0 {
-            (base_stats.unique_opens as f64 / base_stats.total_subscribers as f64) * 100.0
+        let total_sent = match self.delivered_counts(campaign_id).await?.get(&sequence_id) {
+            Some(delivered) => *delivered,
+            None => base_stats.total_subscribers,
+        };
+
+        let open_rate = if total_sent > 0 {
+            (base_stats.unique_opens as f64 / total_sent as f64) * 100.0
         } else {
             0.0
         };
@@ -549,6 +685,7 @@ impl CampaignStatsRepository {
             status: base_stats.status,
             sent_at: base_stats.sent_at,
             total_subscribers: base_stats.total_subscribers,
+            total_sent,
             total_opens: base_stats.total_opens,
             unique_opens: base_stats.unique_opens,
             open_rate,
@@ -558,4 +695,20 @@ impl CampaignStatsRepository {
     }
 }
 
+/// Replace the list-based sent counts of sequence emails with the logged deliveries
+fn apply_delivered_counts(sequence_stats: &mut [SequenceEmailStats], delivered: &HashMap<i32, i64>) {
+    if delivered.is_empty() {
+        return;
+    }
+
+    for stats in sequence_stats.iter_mut() {
+        stats.total_sent = delivered.get(&stats.sequence_email_id).copied().unwrap_or(0);
+        stats.open_rate = if stats.total_sent > 0 {
+            (stats.unique_opens as f64 / stats.total_sent as f64) * 100.0
+        } else {
+            0.0
+        };
+    }
+}
+
 // ... rest of the implementation ... 
\ No newline at end of file
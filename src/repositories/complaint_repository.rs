@@ -0,0 +1,108 @@
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use crate::{
+    error::ApiError,
+    models::complaint::{Complaint, ComplaintFilter, ComplaintResponse, NewComplaint},
+    models::delivery::DeliveryPagination,
+};
+
+// Upper bound of per_page, a page is loaded in memory
+const MAX_PER_PAGE: i64 = 500;
+
+#[derive(Clone)]
+pub struct ComplaintRepository {
+    pool: PgPool
+}
+
+impl ComplaintRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    //
+    //** Record a complaint against its delivery, blocklist the subscriber and suppress the address
+    //** The delivery is found by outbox id (VERP) first, then by Message-ID, the subscriber by
+    //** delivery or else by address
+    //** Params : complaint
+    //** Return : Result<Complaint, ApiError>
+    //
+    pub async fn record(&self, complaint: NewComplaint) -> Result<Complaint, ApiError> {
+        let recorded = sqlx::query_as::<_, Complaint>(
+            r#"
+            WITH target AS (
+                SELECT d.id, d.subscriber_id, d.campaign_id
+                FROM deliveries d
+                WHERE d.id = (SELECT delivery_id FROM email_outbox WHERE id = $1)
+                OR d.message_id IN ($2, '<' || $2 || '>')
+                ORDER BY (d.id = (SELECT delivery_id FROM email_outbox WHERE id = $1)) DESC NULLS LAST
+                LIMIT 1
+            ),
+            subscriber AS (
+                SELECT COALESCE(
+                    (SELECT subscriber_id FROM target),
+                    (SELECT id FROM subscribers WHERE LOWER(email) = LOWER($3) LIMIT 1)
+                ) AS id
+            ),
+            blocklist AS (
+                UPDATE subscribers s
+                SET status = 'blocklisted', updated_at = NOW()
+                FROM subscriber
+                WHERE s.id = subscriber.id
+                AND s.status <> 'blocklisted'
+            ),
+            suppress AS (
+                INSERT INTO suppressions (value, reason)
+                SELECT LOWER(address), 'complaint'
+                FROM (SELECT COALESCE($3, (SELECT email FROM subscribers WHERE id = (SELECT id FROM subscriber))) AS address) complainant
+                WHERE address IS NOT NULL
+                ON CONFLICT (value) DO NOTHING
+            )
+            INSERT INTO complaints (subscriber_id, delivery_id, campaign_id, email, feedback_type, user_agent, source)
+            SELECT (SELECT id FROM subscriber), (SELECT id FROM target), (SELECT campaign_id FROM target),
+                   COALESCE($3, (SELECT email FROM subscribers WHERE id = (SELECT id FROM subscriber))), $4, $5, $6
+            RETURNING *
+            "#,
+        )
+        .bind(complaint.outbox_id)
+        .bind(complaint.message_id)
+        .bind(complaint.email)
+        .bind(complaint.feedback_type)
+        .bind(complaint.user_agent)
+        .bind(complaint.source)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(recorded)
+    }
+
+    /// List complaints, newest first
+    pub async fn find_all(&self, filter: ComplaintFilter, pagination: DeliveryPagination) -> Result<ComplaintResponse, ApiError> {
+        let page = pagination.page.max(1);
+        let per_page = pagination.per_page.clamp(1, MAX_PER_PAGE);
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM complaints");
+        push_filter(&mut count, &filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM complaints");
+        push_filter(&mut query, &filter);
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        query.push_bind(per_page);
+        query.push(" OFFSET ");
+        query.push_bind((page - 1) * per_page);
+
+        let items = query.build_query_as::<Complaint>().fetch_all(&self.pool).await?;
+
+        Ok(ComplaintResponse { items, page, per_page, total })
+    }
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &ComplaintFilter) {
+    query.push(" WHERE TRUE");
+
+    if let Some(subscriber_id) = filter.subscriber_id {
+        query.push(" AND subscriber_id = ").push_bind(subscriber_id);
+    }
+    if let Some(campaign_id) = filter.campaign_id {
+        query.push(" AND campaign_id = ").push_bind(campaign_id);
+    }
+}
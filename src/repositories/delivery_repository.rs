@@ -0,0 +1,82 @@
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use crate::{
+    error::ApiError,
+    models::delivery::{Delivery, DeliveryFilter, DeliveryPagination, DeliveryResponse},
+};
+
+// Upper bound of per_page, a page is loaded in memory
+const MAX_PER_PAGE: i64 = 500;
+
+#[derive(Clone)]
+pub struct DeliveryRepository {
+    pool: PgPool
+}
+
+impl DeliveryRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_id(&self, id: i64) -> Result<Option<Delivery>, ApiError> {
+        let delivery = sqlx::query_as::<_, Delivery>("SELECT * FROM deliveries WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(delivery)
+    }
+
+    //
+    //** List deliveries, newest first
+    //** Params : filter , pagination
+    //** Return : Result<DeliveryResponse, ApiError>
+    //
+    pub async fn find_all(&self, filter: DeliveryFilter, pagination: DeliveryPagination) -> Result<DeliveryResponse, ApiError> {
+        let page = pagination.page.max(1);
+        let per_page = pagination.per_page.clamp(1, MAX_PER_PAGE);
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM deliveries");
+        push_filter(&mut count, &filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM deliveries");
+        push_filter(&mut query, &filter);
+        query.push(" ORDER BY queued_at DESC, id DESC LIMIT ");
+        query.push_bind(per_page);
+        query.push(" OFFSET ");
+        query.push_bind((page - 1) * per_page);
+
+        let items = query.build_query_as::<Delivery>().fetch_all(&self.pool).await?;
+
+        Ok(DeliveryResponse { items, page, per_page, total })
+    }
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &DeliveryFilter) {
+    query.push(" WHERE TRUE");
+
+    if let Some(campaign_id) = filter.campaign_id {
+        query.push(" AND campaign_id = ").push_bind(campaign_id);
+    }
+    if let Some(sequence_email_id) = filter.sequence_email_id {
+        query.push(" AND sequence_email_id = ").push_bind(sequence_email_id);
+    }
+    if let Some(subscriber_id) = filter.subscriber_id {
+        query.push(" AND subscriber_id = ").push_bind(subscriber_id);
+    }
+    if let Some(status) = filter.status {
+        query.push(" AND status = ").push_bind(status);
+    }
+    if let Some(message_id) = &filter.message_id {
+        query.push(" AND message_id = ").push_bind(message_id.clone());
+    }
+    if let Some(provider) = &filter.provider {
+        query.push(" AND provider = ").push_bind(provider.clone());
+    }
+    if let Some(from) = filter.from {
+        query.push(" AND queued_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.to {
+        query.push(" AND queued_at < ").push_bind(to);
+    }
+}
@@ -0,0 +1,22 @@
+use sqlx::PgPool;
+use crate::{error::ApiError, models::dkim_key::DkimKey};
+
+#[derive(Clone)]
+pub struct DkimKeyRepository {
+    pool: PgPool
+}
+
+impl DkimKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Keys used to sign outgoing messages
+    pub async fn find_enabled(&self) -> Result<Vec<DkimKey>, ApiError> {
+        let keys = sqlx::query_as::<_, DkimKey>("SELECT * FROM dkim_keys WHERE enabled ORDER BY domain")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(keys)
+    }
+}
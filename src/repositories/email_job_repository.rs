@@ -0,0 +1,153 @@
+use sqlx::PgPool;
+use sqlx::types::Json;
+use chrono::{DateTime, Utc};
+use crate::{
+    error::ApiError,
+    email_service::models::{EmailRequest, SendStrategy},
+    models::email_job::{EmailJob, EmailJobRun},
+};
+
+const JOB_COLUMNS: &str = "id, strategy, requests, messenger, status, next_run_at, runs_completed, attempts, last_error, last_result, created_at, updated_at";
+
+#[derive(Clone)]
+pub struct EmailJobRepository {
+    pool: PgPool
+}
+
+impl EmailJobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        strategy: &SendStrategy,
+        requests: &[EmailRequest],
+        messenger: Option<&str>,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> Result<EmailJob, ApiError> {
+        let job = sqlx::query_as::<_, EmailJob>(&format!(
+            r#"
+            INSERT INTO email_jobs (strategy, requests, messenger, status, next_run_at)
+            VALUES ($1, $2, $3, 'scheduled', $4)
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(Json(strategy))
+        .bind(Json(requests))
+        .bind(messenger)
+        .bind(next_run_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<Option<EmailJob>, ApiError> {
+        let job = sqlx::query_as::<_, EmailJob>(&format!("SELECT {} FROM email_jobs WHERE id = $1", JOB_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(job)
+    }
+
+    //
+    //** Mark due jobs as running and return them
+    //** SKIP LOCKED lets several instances poll the table without running a job twice
+    //** Params : now , limit , lock_timeout_secs
+    //** Return : Result<Vec<EmailJob>, ApiError>
+    //
+    pub async fn claim_due(&self, now: DateTime<Utc>, limit: i64, lock_timeout_secs: i64) -> Result<Vec<EmailJob>, ApiError> {
+        let jobs = sqlx::query_as::<_, EmailJob>(&format!(
+            r#"
+            UPDATE email_jobs
+            SET status = 'running', updated_at = NOW()
+            WHERE id IN (
+                SELECT id FROM email_jobs
+                WHERE (status = 'scheduled' AND next_run_at <= $1)
+                -- Left running by a worker that died mid-run
+                OR (status = 'running' AND updated_at < $1 - make_interval(secs => $3))
+                ORDER BY next_run_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit)
+        .bind(lock_timeout_secs as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// Store the outcome of a run and when the job is due next
+    pub async fn record_run(&self, id: i32, run: EmailJobRun) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            UPDATE email_jobs
+            SET status = $2,
+                next_run_at = $3,
+                runs_completed = $4,
+                attempts = $5,
+                last_error = $6,
+                last_result = $7,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(run.status)
+        .bind(run.next_run_at)
+        .bind(run.runs_completed)
+        .bind(run.attempts)
+        .bind(run.last_error)
+        .bind(run.last_result)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    //
+    //** Release a job whose run failed before its outcome was stored
+    //** Recurring jobs are due again in a minute, scheduled ones are failed rather than sent twice
+    //** Params : id , retry , error
+    //
+    pub async fn release(&self, id: i32, retry: bool, error: &str) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            UPDATE email_jobs
+            SET status = CASE WHEN $2 THEN 'scheduled' ELSE 'failed' END::email_job_status,
+                next_run_at = CASE WHEN $2 THEN NOW() + INTERVAL '1 minute' ELSE NULL END,
+                attempts = attempts + 1,
+                last_error = $3,
+                updated_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(id)
+        .bind(retry)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn cancel(&self, id: i32) -> Result<bool, ApiError> {
+        let result = sqlx::query(
+            "UPDATE email_jobs SET status = 'cancelled', updated_at = NOW() WHERE id = $1 AND status = 'scheduled'"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,412 @@
+use sqlx::PgPool;
+use sqlx::types::Json;
+use chrono::{DateTime, Utc};
+use crate::{
+    error::ApiError,
+    email_service::{error::FailureKind, merge::MergeContext, provider::SendReceipt},
+    email_service::models::{QueuedEmailJob, SendStrategy},
+    models::email_outbox::{EmailJobProgress, NewOutboxMessage, OutboxMessage},
+};
+
+const OUTBOX_COLUMNS: &str = "id, job_id, campaign_id, sequence_email_id, subscriber_id, messenger, request, status, attempts, available_at, message_id, last_error, sent_at";
+
+#[derive(Clone)]
+pub struct EmailOutboxRepository {
+    pool: PgPool
+}
+
+impl EmailOutboxRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    //
+    //** Queue one message per confirmed, enabled subscriber of the lists
+    //** The job row and its messages are created in the same transaction
+    //** Params : list_ids , subject , body , campaign_id , sequence_email_id , messenger
+    //** Return : Result<QueuedEmailJob, ApiError>
+    //
+    pub async fn enqueue_lists(
+        &self,
+        list_ids: &[i32],
+        subject: &str,
+        body: &str,
+        campaign_id: i32,
+        sequence_email_id: i32,
+        messenger: Option<&str>,
+    ) -> Result<QueuedEmailJob, ApiError> {
+        let mut tx = self.pool.begin().await?;
+
+        let job_id: i32 = sqlx::query_scalar(
+            "INSERT INTO email_jobs (strategy, messenger, status) VALUES ($1, $2, 'running') RETURNING id"
+        )
+        .bind(Json(SendStrategy::Immediate))
+        .bind(messenger)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // One delivery per subscriber, reset when the same email is sent again
+        let queued = sqlx::query(
+            r#"
+            WITH targets AS (
+                SELECT s.id, s.email
+                FROM subscribers s
+                WHERE s.status = 'enabled'
+                AND EXISTS (
+                    SELECT 1 FROM subscriber_lists sl
+                    WHERE sl.subscriber_id = s.id
+                    AND sl.list_id = ANY($7)
+                    AND sl.status = 'confirmed'
+                )
+            ),
+            delivery AS (
+                INSERT INTO deliveries (campaign_id, sequence_email_id, subscriber_id, messenger)
+                SELECT $2, $3, t.id, $4 FROM targets t
+                ON CONFLICT (campaign_id, sequence_email_id, subscriber_id) DO UPDATE
+                SET status = 'queued', messenger = EXCLUDED.messenger, attempts = 0, message_id = NULL,
+                    smtp_response = NULL, provider = NULL, queued_at = NOW(), sent_at = NULL, failed_at = NULL,
+                    bounced_at = NULL, updated_at = NOW()
+                RETURNING id, subscriber_id
+            )
+            INSERT INTO email_outbox (job_id, campaign_id, sequence_email_id, subscriber_id, delivery_id, messenger, request)
+            SELECT $1, $2, $3, t.id, d.id, $4,
+                   jsonb_build_object('to', t.email, 'subject', $5::text, 'body', $6::text)
+            FROM targets t
+            JOIN delivery d ON d.subscriber_id = t.id
+            ORDER BY t.id
+            "#,
+        )
+        .bind(job_id)
+        .bind(campaign_id)
+        .bind(sequence_email_id)
+        .bind(messenger)
+        .bind(subject)
+        .bind(body)
+        .bind(list_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        // Nothing to send, the job is already done
+        if queued == 0 {
+            self.complete_job_if_drained(job_id).await?;
+        }
+
+        Ok(QueuedEmailJob { job_id, queued: queued as i64 })
+    }
+
+    /// Queue a single message outside of any job, logged as a delivery when it targets a campaign subscriber
+    pub async fn enqueue(&self, message: NewOutboxMessage) -> Result<i64, ApiError> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH delivery AS (
+                INSERT INTO deliveries (campaign_id, sequence_email_id, subscriber_id, messenger)
+                SELECT $1::int, COALESCE($2::int, 0), $3::int, $4::text
+                WHERE $1::int IS NOT NULL AND $3::int IS NOT NULL
+                ON CONFLICT (campaign_id, sequence_email_id, subscriber_id) DO UPDATE
+                SET status = 'queued', messenger = EXCLUDED.messenger, attempts = 0, message_id = NULL,
+                    smtp_response = NULL, provider = NULL, queued_at = NOW(), sent_at = NULL, failed_at = NULL,
+                    bounced_at = NULL, updated_at = NOW()
+                RETURNING id
+            )
+            INSERT INTO email_outbox (campaign_id, sequence_email_id, subscriber_id, messenger, request, delivery_id)
+            VALUES ($1, $2, $3, $4, $5, (SELECT id FROM delivery))
+            RETURNING id
+            "#,
+        )
+        .bind(message.campaign_id)
+        .bind(message.sequence_email_id)
+        .bind(message.subscriber_id)
+        .bind(message.messenger)
+        .bind(Json(message.request))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    //
+    //** Lock a batch of messages for a worker
+    //** Messages stuck in 'sending' longer than lock_timeout_secs (crashed worker) are claimed again
+    //** Params : limit , lock_timeout_secs
+    //** Return : Result<Vec<OutboxMessage>, ApiError>
+    //
+    pub async fn claim(&self, limit: i64, lock_timeout_secs: i64) -> Result<Vec<OutboxMessage>, ApiError> {
+        let messages = sqlx::query_as::<_, OutboxMessage>(&format!(
+            r#"
+            UPDATE email_outbox
+            SET status = 'sending', locked_at = NOW(), attempts = attempts + 1, updated_at = NOW()
+            WHERE id IN (
+                SELECT id FROM email_outbox
+                WHERE (status = 'queued' AND available_at <= NOW())
+                OR (status = 'sending' AND locked_at < NOW() - make_interval(secs => $2))
+                ORDER BY available_at, id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            OUTBOX_COLUMNS
+        ))
+        .bind(limit)
+        .bind(lock_timeout_secs as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    //
+    //** Subscriber, campaign and lists a campaign message is rendered with
+    //** Lists are those of the campaign the subscriber is on, URLs are added by the caller
+    //** Return : Result<Option<MergeContext>, ApiError> (None when the subscriber is gone)
+    //
+    pub async fn merge_context(&self, subscriber_id: i32, campaign_id: i32) -> Result<Option<MergeContext>, ApiError> {
+        let context = sqlx::query_scalar::<_, serde_json::Value>(
+            r#"
+            SELECT jsonb_build_object(
+                'subscriber', jsonb_build_object(
+                    'id', s.id, 'uuid', s.uuid, 'email', s.email, 'name', s.name,
+                    'attribs', s.attribs, 'status', s.status, 'created_at', s.created_at
+                ),
+                'campaign', (
+                    SELECT jsonb_build_object(
+                        'id', c.id, 'uuid', c.uuid, 'name', c.name, 'subject', c.subject,
+                        'from_email', c.from_email, 'tags', c.tags
+                    )
+                    FROM campaigns c WHERE c.id = $2
+                ),
+                'lists', COALESCE((
+                    SELECT jsonb_agg(jsonb_build_object('id', l.id, 'uuid', l.uuid, 'name', l.name, 'description', l.description) ORDER BY l.id)
+                    FROM lists l
+                    JOIN subscriber_lists sl ON sl.list_id = l.id AND sl.subscriber_id = s.id
+                    JOIN campaign_lists cl ON cl.list_id = l.id AND cl.campaign_id = $2
+                ), '[]'::jsonb)
+            )
+            FROM subscribers s
+            WHERE s.id = $1
+            "#,
+        )
+        .bind(subscriber_id)
+        .bind(campaign_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(context) = context else {
+            return Ok(None);
+        };
+        let mut context: MergeContext = serde_json::from_value(context)?;
+        context.list = context.lists.first().cloned();
+
+        Ok(Some(context))
+    }
+
+    //
+    //** Merge context of a campaign without subscriber, for previews with a sample subscriber
+    //** Every list of the campaign is available, the first one being {{ list }}
+    //** Params : campaign_id
+    //** Return : Result<MergeContext, ApiError> (subscriber left null)
+    //
+    pub async fn sample_merge_context(&self, campaign_id: i32) -> Result<MergeContext, ApiError> {
+        let context = sqlx::query_scalar::<_, serde_json::Value>(
+            r#"
+            SELECT jsonb_build_object(
+                'campaign', (
+                    SELECT jsonb_build_object(
+                        'id', c.id, 'uuid', c.uuid, 'name', c.name, 'subject', c.subject,
+                        'from_email', c.from_email, 'tags', c.tags
+                    )
+                    FROM campaigns c WHERE c.id = $1
+                ),
+                'lists', COALESCE((
+                    SELECT jsonb_agg(jsonb_build_object('id', l.id, 'uuid', l.uuid, 'name', l.name, 'description', l.description) ORDER BY l.id)
+                    FROM lists l
+                    JOIN campaign_lists cl ON cl.list_id = l.id AND cl.campaign_id = $1
+                ), '[]'::jsonb)
+            )
+            "#,
+        )
+        .bind(campaign_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let mut context: MergeContext = serde_json::from_value(context)?;
+        context.list = context.lists.first().cloned();
+
+        Ok(context)
+    }
+
+    /// Record a delivered message on its delivery and count it on its campaign
+    pub async fn mark_sent(&self, id: i64, receipt: &SendReceipt) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            WITH done AS (
+                UPDATE email_outbox
+                SET status = 'sent', message_id = $2, provider = $4, sent_at = NOW(), locked_at = NULL, last_error = NULL,
+                    updated_at = NOW()
+                WHERE id = $1
+                RETURNING campaign_id, delivery_id, attempts
+            ),
+            delivery AS (
+                UPDATE deliveries d
+                SET status = 'sent', message_id = $2, smtp_response = $3, provider = $4, attempts = done.attempts,
+                    sent_at = NOW(), updated_at = NOW()
+                FROM done
+                WHERE d.id = done.delivery_id
+            )
+            UPDATE campaigns SET sent = sent + 1
+            WHERE id = (SELECT campaign_id FROM done)
+            "#,
+        )
+        .bind(id)
+        .bind(&receipt.message_id)
+        .bind(&receipt.response)
+        .bind(&receipt.provider)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Put a throttled message back in the queue, the claim does not count as an attempt
+    pub async fn defer(&self, id: i64, available_at: DateTime<Utc>) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            UPDATE email_outbox
+            SET status = 'queued', attempts = GREATEST(attempts - 1, 0),
+                available_at = $2, locked_at = NULL, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(available_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Put a message back in the queue after a transient failure
+    pub async fn mark_retry(
+        &self,
+        id: i64,
+        error: &str,
+        error_code: Option<u16>,
+        available_at: DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            WITH retry AS (
+                UPDATE email_outbox
+                SET status = 'queued', last_error = $2, last_error_code = $3, failure_kind = 'transient',
+                    available_at = $4, locked_at = NULL, updated_at = NOW()
+                WHERE id = $1
+                RETURNING delivery_id, attempts
+            )
+            UPDATE deliveries d
+            SET attempts = retry.attempts, smtp_response = $2, updated_at = NOW()
+            FROM retry
+            WHERE d.id = retry.delivery_id
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(error_code.map(i32::from))
+        .bind(available_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    //
+    //** Give up on a message and record the failure against its subscriber
+    //** Params : id , error , error_code (SMTP reply) , kind
+    //
+    pub async fn mark_failed(
+        &self,
+        id: i64,
+        error: &str,
+        error_code: Option<u16>,
+        kind: FailureKind,
+    ) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            WITH failed AS (
+                UPDATE email_outbox
+                SET status = 'failed', last_error = $2, last_error_code = $3, failure_kind = $4,
+                    locked_at = NULL, updated_at = NOW()
+                WHERE id = $1
+                RETURNING id, subscriber_id, campaign_id, delivery_id, attempts
+            ),
+            delivery AS (
+                UPDATE deliveries d
+                SET status = 'failed', attempts = failed.attempts, smtp_response = $2,
+                    failed_at = NOW(), updated_at = NOW()
+                FROM failed
+                WHERE d.id = failed.delivery_id
+            )
+            INSERT INTO subscriber_failures (subscriber_id, outbox_id, campaign_id, failure_kind, error, smtp_code, attempts)
+            SELECT subscriber_id, id, campaign_id, $4, $2, $3, attempts
+            FROM failed
+            WHERE subscriber_id IS NOT NULL
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(error_code.map(i32::from))
+        .bind(kind.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a job completed once none of its messages is waiting anymore
+    pub async fn complete_job_if_drained(&self, job_id: i32) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            UPDATE email_jobs
+            SET status = 'completed', updated_at = NOW()
+            WHERE id = $1
+            AND status = 'running'
+            AND NOT EXISTS (
+                SELECT 1 FROM email_outbox
+                WHERE job_id = $1 AND status IN ('queued', 'sending')
+            )
+            "#,
+        )
+        .bind(job_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn job_progress(&self, job_id: i32) -> Result<Option<EmailJobProgress>, ApiError> {
+        let progress = sqlx::query_as::<_, EmailJobProgress>(
+            r#"
+            SELECT j.id AS job_id,
+                   j.status,
+                   COUNT(o.id) AS total,
+                   COUNT(o.id) FILTER (WHERE o.status = 'queued') AS queued,
+                   COUNT(o.id) FILTER (WHERE o.status = 'sending') AS sending,
+                   COUNT(o.id) FILTER (WHERE o.status = 'sent') AS sent,
+                   COUNT(o.id) FILTER (WHERE o.status = 'failed') AS failed,
+                   j.created_at,
+                   j.updated_at
+            FROM email_jobs j
+            LEFT JOIN email_outbox o ON o.job_id = j.id
+            WHERE j.id = $1
+            GROUP BY j.id
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(progress)
+    }
+}
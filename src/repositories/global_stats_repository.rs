@@ -166,6 +166,27 @@ impl GlobalStatsRepository {
             0.0
         };
 
+        // Bounced and complained deliveries out of every delivery that left the outbox
+        let (bounced, delivered, complaints): (i64, i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FILTER (WHERE status = 'bounced'),
+                   COUNT(*) FILTER (WHERE status IN ('sent', 'bounced')),
+                   (SELECT COUNT(*) FROM complaints)
+            FROM deliveries
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let (bounce_rate, complaint_rate) = if delivered > 0 {
+            (
+                (bounced as f64 / delivered as f64) * 100.0,
+                (complaints as f64 / delivered as f64) * 100.0,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
         Ok(GlobalStats {
             total_subscribers: overall.total_subscribers.unwrap_or(0),
             active_subscribers: overall.active_subscribers.unwrap_or(0),
@@ -208,8 +229,8 @@ impl GlobalStatsRepository {
             total_active_lists: overall.active_lists.unwrap_or(0),
 
             average_delivery_time: 0.0,
-            bounce_rate: 0.0,
-            complaint_rate: 0.0,
+            bounce_rate,
+            complaint_rate,
         })
     }
 } 
\ No newline at end of file
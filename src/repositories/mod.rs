@@ -8,4 +8,13 @@ pub mod email_views_repository;
 pub mod sequence_email_repository;
 pub mod campaign_stats_repository;
 pub mod global_stats_repository;
-pub mod subscriber_sequence_progress_repository;
\ No newline at end of file
+pub mod subscriber_sequence_progress_repository;
+pub mod email_job_repository;
+pub mod email_outbox_repository;
+pub mod delivery_repository;
+pub mod dkim_key_repository;
+pub mod bounce_repository;
+pub mod complaint_repository;
+pub mod suppression_repository;
+pub mod provider_event_repository;
+pub mod webhook_repository;
\ No newline at end of file
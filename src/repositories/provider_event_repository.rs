@@ -0,0 +1,96 @@
+use sqlx::PgPool;
+use crate::{
+    email_service::inbound::InboundEvent,
+    error::ApiError,
+    models::provider_event::ProviderEvent,
+};
+
+#[derive(Clone)]
+pub struct ProviderEventRepository {
+    pool: PgPool
+}
+
+impl ProviderEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    //
+    //** Record a provider event against its delivery
+    //** The delivery is found by outbox id first, then by Message-ID, the subscriber by delivery or
+    //** else by address. Deliveries are marked delivered, and opens are added to email_views
+    //** Params : event
+    //** Return : Result<Option<ProviderEvent>, ApiError> (None when the provider event id was already recorded)
+    //
+    pub async fn record(&self, event: &InboundEvent) -> Result<Option<ProviderEvent>, ApiError> {
+        let recorded = sqlx::query_as::<_, ProviderEvent>(
+            r#"
+            WITH seen AS (
+                SELECT EXISTS (SELECT 1 FROM provider_events WHERE provider = $10 AND event_id = $11) AS duplicate
+            ),
+            target AS (
+                SELECT d.id, d.subscriber_id, d.campaign_id, d.sequence_email_id
+                FROM deliveries d
+                WHERE d.id = (SELECT delivery_id FROM email_outbox WHERE id = $1)
+                OR d.message_id IN ($2, '<' || $2 || '>')
+                ORDER BY (d.id = (SELECT delivery_id FROM email_outbox WHERE id = $1)) DESC NULLS LAST
+                LIMIT 1
+            ),
+            subscriber AS (
+                SELECT COALESCE(
+                    (SELECT subscriber_id FROM target),
+                    (SELECT id FROM subscribers WHERE LOWER(email) = LOWER($3) LIMIT 1)
+                ) AS id
+            ),
+            delivered AS (
+                UPDATE deliveries d
+                SET delivered_at = COALESCE(d.delivered_at, $9), updated_at = NOW()
+                FROM target
+                WHERE d.id = target.id
+                AND $4 = 'delivered'
+                AND NOT (SELECT duplicate FROM seen)
+            ),
+            opened AS (
+                INSERT INTO email_views (sequence_email_id, subscriber_id, campaign_id, opened_at, ip_address, user_agent, metadata)
+                SELECT NULLIF(target.sequence_email_id, 0), target.subscriber_id, target.campaign_id, $9, $7, $8,
+                       jsonb_build_object('source', $10::text)
+                FROM target
+                WHERE $4 = 'open'
+                AND NOT (SELECT duplicate FROM seen)
+                ON CONFLICT (subscriber_id, sequence_email_id, campaign_id)
+                DO UPDATE SET opened_at = EXCLUDED.opened_at
+            )
+            INSERT INTO provider_events (provider, event_id, event_type, email, message_id, delivery_id, subscriber_id, campaign_id, url, diagnostic, occurred_at)
+            SELECT $10, $11, $4, $3, $2, (SELECT id FROM target), (SELECT id FROM subscriber), (SELECT campaign_id FROM target),
+                   $5, $6, $9
+            ON CONFLICT (provider, event_id) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(event.outbox_id)
+        .bind(event.message_id.as_deref())
+        .bind(&event.email)
+        .bind(event.event_type)
+        .bind(event.url.as_deref())
+        .bind(event.diagnostic.as_deref())
+        .bind(event.ip_address.as_deref())
+        .bind(event.user_agent.as_deref())
+        .bind(event.occurred_at)
+        .bind(event.provider)
+        .bind(event.event_id.as_deref())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(recorded)
+    }
+
+    /// Drop a recorded event so a redelivery of it is processed again
+    pub async fn forget(&self, id: i64) -> Result<(), ApiError> {
+        sqlx::query("DELETE FROM provider_events WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
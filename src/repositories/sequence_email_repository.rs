@@ -364,6 +364,23 @@ impl SequenceEmailRepository {
         .fetch_all(&self.pool)
         .await?;
 
+        Ok(sequence_email)
+    }
+    pub async fn find_by_id(&self, id: i32) -> Result<Option<SequenceEmail>, ApiError> {
+        let sequence_email = sqlx::query_as::<_, SequenceEmail>(
+            r#"
+            SELECT id, campaign_id, position, subject, body, template_id, content_type, status,
+                COALESCE(metadata, '{}'::jsonb) AS metadata, COALESCE(is_active, true) AS is_active, send_at,
+                delay_type, delay_value, delay_unit,
+                COALESCE(created_at, NOW()) AS created_at, COALESCE(updated_at, NOW()) AS updated_at
+            FROM sequence_emails
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
         Ok(sequence_email)
     }
 } 
\ No newline at end of file
@@ -7,6 +7,8 @@ use crate::{
         PaginationDto,
         SubscriptionStatus
     },
+    models::subscription_event::SubscriptionEventSource,
+    models::preference::ListPreference,
     error::ApiError,
 };
 
@@ -218,4 +220,138 @@ impl SubscriberListRepository {
 
         Ok((result.rows_affected() > 0).then_some(()))
     }
+
+    //
+    //** Unsubscribe from lists and stop the sequences of those lists, recording one event per list
+    //** Params : subscriber_id , list_ids (None for every list) , campaign_id (origin of the request) , source
+    //** Return : Result<Vec<i32>, ApiError> (lists newly unsubscribed from)
+    //
+    pub async fn unsubscribe(
+        &self,
+        subscriber_id: i32,
+        list_ids: Option<&[i32]>,
+        campaign_id: Option<i32>,
+        source: SubscriptionEventSource,
+    ) -> Result<Vec<i32>, ApiError> {
+        let lists = sqlx::query_scalar::<_, i32>(
+            r#"
+            WITH changed AS (
+                UPDATE subscriber_lists
+                SET status = 'unsubscribed', updated_at = NOW()
+                WHERE subscriber_id = $1
+                AND status <> 'unsubscribed'
+                AND ($2::int[] IS NULL OR list_id = ANY($2))
+                RETURNING list_id
+            ),
+            stopped AS (
+                UPDATE subscriber_sequence_progress
+                SET completed = TRUE, next_email_scheduled_at = NULL, updated_at = NOW()
+                WHERE subscriber_id = $1
+                AND completed = FALSE
+                AND ($2::int[] IS NULL OR list_id = ANY($2))
+            ),
+            events AS (
+                INSERT INTO subscription_events (subscriber_id, list_id, campaign_id, event, source)
+                SELECT $1, list_id, $3, 'unsubscribed', $4 FROM changed
+            )
+            SELECT list_id FROM changed
+            "#,
+        )
+        .bind(subscriber_id)
+        .bind(list_ids)
+        .bind(campaign_id)
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lists)
+    }
+
+    /// One-click unsubscribe from every list a campaign was sent to
+    pub async fn unsubscribe_from_campaign(&self, subscriber_id: i32, campaign_id: i32) -> Result<Vec<i32>, ApiError> {
+        let list_ids = sqlx::query_scalar::<_, i32>(
+            "SELECT list_id FROM campaign_lists WHERE campaign_id = $1 AND list_id IS NOT NULL"
+        )
+        .bind(campaign_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.unsubscribe(subscriber_id, Some(&list_ids), Some(campaign_id), SubscriptionEventSource::OneClick).await
+    }
+
+    //
+    //** (Re)subscribe to lists, double opt-in lists start unconfirmed
+    //** Lists the subscriber is already on are left as they are
+    //** Return : Result<Vec<i32>, ApiError> (lists newly subscribed to)
+    //
+    pub async fn subscribe(
+        &self,
+        subscriber_id: i32,
+        list_ids: &[i32],
+        source: SubscriptionEventSource,
+    ) -> Result<Vec<i32>, ApiError> {
+        let lists = sqlx::query_scalar::<_, i32>(
+            r#"
+            WITH changed AS (
+                INSERT INTO subscriber_lists (subscriber_id, list_id, status)
+                SELECT $1, l.id,
+                       CASE WHEN l.optin = 'double' THEN 'unconfirmed' ELSE 'confirmed' END::subscription_status
+                FROM lists l
+                WHERE l.id = ANY($2)
+                ON CONFLICT (subscriber_id, list_id) DO UPDATE
+                SET status = EXCLUDED.status, updated_at = NOW()
+                WHERE subscriber_lists.status = 'unsubscribed'
+                RETURNING list_id
+            ),
+            events AS (
+                INSERT INTO subscription_events (subscriber_id, list_id, event, source)
+                SELECT $1, list_id, 'subscribed', $3 FROM changed
+            )
+            SELECT list_id FROM changed
+            "#,
+        )
+        .bind(subscriber_id)
+        .bind(list_ids)
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lists)
+    }
+
+    /// Confirm a pending double opt-in subscription, false when there was none to confirm
+    pub async fn confirm(&self, subscriber_id: i32, list_id: i32) -> Result<bool, ApiError> {
+        let confirmed = sqlx::query(
+            r#"
+            UPDATE subscriber_lists
+            SET status = 'confirmed', updated_at = NOW()
+            WHERE subscriber_id = $1 AND list_id = $2 AND status = 'unconfirmed'
+            "#,
+        )
+        .bind(subscriber_id)
+        .bind(list_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(confirmed > 0)
+    }
+
+    /// Public lists with the subscription status of a subscriber
+    pub async fn find_public_preferences(&self, subscriber_id: i32) -> Result<Vec<ListPreference>, ApiError> {
+        let lists = sqlx::query_as::<_, ListPreference>(
+            r#"
+            SELECT l.id AS list_id, l.name, l.description, sl.status
+            FROM lists l
+            LEFT JOIN subscriber_lists sl ON sl.list_id = l.id AND sl.subscriber_id = $1
+            WHERE l.type = 'public'
+            ORDER BY l.name
+            "#,
+        )
+        .bind(subscriber_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lists)
+    }
 }
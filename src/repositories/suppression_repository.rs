@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use crate::{
+    error::ApiError,
+    models::suppression::{
+        normalize_value, CreateSuppressionDto, ImportSuppressionsDto, ImportSuppressionsResponse,
+        Suppression, SuppressionFilter, SuppressionResponse, UpdateSuppressionDto,
+    },
+    models::delivery::DeliveryPagination,
+};
+
+// Upper bound of per_page, a page is loaded in memory
+const MAX_PER_PAGE: i64 = 500;
+
+#[derive(Clone)]
+pub struct SuppressionRepository {
+    pool: PgPool
+}
+
+impl SuppressionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Add an address or domain, updating the entry when it is already suppressed
+    pub async fn create(&self, dto: CreateSuppressionDto) -> Result<Suppression, ApiError> {
+        let (value, is_domain) = normalize_value(&dto.value)
+            .ok_or_else(|| ApiError::BadRequest(format!("Not an email address or domain: {}", dto.value)))?;
+
+        let suppression = sqlx::query_as::<_, Suppression>(
+            r#"
+            INSERT INTO suppressions (value, is_domain, reason, note, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (value) DO UPDATE
+            SET reason = EXCLUDED.reason, note = EXCLUDED.note, expires_at = EXCLUDED.expires_at, updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(value)
+        .bind(is_domain)
+        .bind(dto.reason)
+        .bind(dto.note)
+        .bind(dto.expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(suppression)
+    }
+
+    //
+    //** Add many addresses or domains in one statement
+    //** Params : dto (values sharing a reason, note and expiry)
+    //** Return : Result<ImportSuppressionsResponse, ApiError> (number of rows written and rejected values)
+    //
+    pub async fn import(&self, dto: ImportSuppressionsDto) -> Result<ImportSuppressionsResponse, ApiError> {
+        let mut invalid = Vec::new();
+        // Deduplicated, one row cannot be upserted twice by the same statement
+        let mut entries = BTreeMap::new();
+        for raw in dto.values {
+            match normalize_value(&raw) {
+                Some((value, is_domain)) => { entries.insert(value, is_domain); }
+                None if raw.trim().is_empty() => {}
+                None => invalid.push(raw),
+            }
+        }
+
+        if entries.is_empty() {
+            return Ok(ImportSuppressionsResponse { imported: 0, invalid });
+        }
+
+        let (values, domains): (Vec<String>, Vec<bool>) = entries.into_iter().unzip();
+        let result = sqlx::query(
+            r#"
+            INSERT INTO suppressions (value, is_domain, reason, note, expires_at)
+            SELECT entry.value, entry.is_domain, $3, $4, $5
+            FROM UNNEST($1::text[], $2::boolean[]) AS entry(value, is_domain)
+            ON CONFLICT (value) DO UPDATE
+            SET reason = EXCLUDED.reason, note = EXCLUDED.note, expires_at = EXCLUDED.expires_at, updated_at = NOW()
+            "#,
+        )
+        .bind(values)
+        .bind(domains)
+        .bind(dto.reason)
+        .bind(dto.note)
+        .bind(dto.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ImportSuppressionsResponse { imported: result.rows_affected(), invalid })
+    }
+
+    pub async fn find_by_id(&self, id: i64) -> Result<Option<Suppression>, ApiError> {
+        let suppression = sqlx::query_as::<_, Suppression>("SELECT * FROM suppressions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(suppression)
+    }
+
+    /// List suppressions, newest first
+    pub async fn find_all(&self, filter: SuppressionFilter, pagination: DeliveryPagination) -> Result<SuppressionResponse, ApiError> {
+        let page = pagination.page.max(1);
+        let per_page = pagination.per_page.clamp(1, MAX_PER_PAGE);
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM suppressions");
+        push_filter(&mut count, &filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM suppressions");
+        push_filter(&mut query, &filter);
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        query.push_bind(per_page);
+        query.push(" OFFSET ");
+        query.push_bind((page - 1) * per_page);
+
+        let items = query.build_query_as::<Suppression>().fetch_all(&self.pool).await?;
+
+        Ok(SuppressionResponse { items, page, per_page, total })
+    }
+
+    pub async fn update(&self, id: i64, dto: UpdateSuppressionDto) -> Result<Option<Suppression>, ApiError> {
+        let suppression = sqlx::query_as::<_, Suppression>(
+            r#"
+            UPDATE suppressions
+            SET reason = COALESCE($2, reason), note = COALESCE($3, note), expires_at = $4, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(dto.reason)
+        .bind(dto.note)
+        .bind(dto.expires_at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(suppression)
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<Option<Suppression>, ApiError> {
+        let suppression = sqlx::query_as::<_, Suppression>("DELETE FROM suppressions WHERE id = $1 RETURNING *")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(suppression)
+    }
+
+    //
+    //** Find which addresses are suppressed, by address or by domain
+    //** Params : addresses (lowercased)
+    //** Return : Result<Vec<String>, ApiError> (the suppressed ones, expired entries ignored)
+    //
+    pub async fn suppressed(&self, addresses: &[String]) -> Result<Vec<String>, ApiError> {
+        let suppressed = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT recipient.address
+            FROM UNNEST($1::text[]) AS recipient(address)
+            WHERE EXISTS (
+                SELECT 1 FROM suppressions s
+                WHERE (s.expires_at IS NULL OR s.expires_at > NOW())
+                AND s.value = CASE WHEN s.is_domain THEN split_part(recipient.address, '@', 2) ELSE recipient.address END
+            )
+            "#,
+        )
+        .bind(addresses)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(suppressed)
+    }
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &SuppressionFilter) {
+    query.push(" WHERE TRUE");
+
+    if let Some(search) = filter.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        query.push(" AND value LIKE ").push_bind(contains_pattern(&search.to_lowercase())).push(r" ESCAPE '\'");
+    }
+    if let Some(reason) = filter.reason {
+        query.push(" AND reason = ").push_bind(reason);
+    }
+    if let Some(is_domain) = filter.is_domain {
+        query.push(" AND is_domain = ").push_bind(is_domain);
+    }
+    if !filter.include_expired {
+        query.push(" AND (expires_at IS NULL OR expires_at > NOW())");
+    }
+}
+
+/// LIKE pattern matching values that contain `search`, its own `%` and `_` taken literally
+fn contains_pattern(search: &str) -> String {
+    let escaped = search.replace('\\', r"\\").replace('%', r"\%").replace('_', r"\_");
+    format!("%{}%", escaped)
+}
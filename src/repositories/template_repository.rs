@@ -134,4 +134,31 @@ impl TemplateRepository {
 
         Ok(template)
     }
+
+    //
+    //** Layout a sequence email is wrapped in
+    //** Its own template first, then the layout of its campaign, then the default template
+    //** Params : template_id (of the sequence email) , campaign_id
+    //** Return : Result<Option<Template>, ApiError> (None when no layout applies)
+    //
+    pub async fn find_layout(&self, template_id: Option<i32>, campaign_id: i32) -> Result<Option<Template>, ApiError> {
+        let template = sqlx::query_as::<_, Template>(
+            r#"
+            SELECT t.id, t.name, t.type, t.subject, t.body, t.is_default, t.created_at, t.updated_at
+            FROM templates t
+            WHERE t.id = $1
+            OR t.id = (SELECT c.layout_template_id FROM campaigns c WHERE c.id = $2)
+            -- The default template is only a layout when it has a placeholder
+            OR (t.is_default AND t.body ~ '\{\{\s*content\s*\}\}')
+            ORDER BY CASE WHEN t.id = $1 THEN 0 WHEN t.is_default THEN 2 ELSE 1 END
+            LIMIT 1
+            "#,
+        )
+        .bind(template_id)
+        .bind(campaign_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(template)
+    }
 }
\ No newline at end of file
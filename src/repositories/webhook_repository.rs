@@ -0,0 +1,260 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use crate::{
+    error::ApiError,
+    models::webhook::{
+        ClaimedWebhookDelivery, CreateWebhookDto, UpdateWebhookDto, Webhook, WebhookDelivery,
+        WebhookDeliveryFilter, WebhookDeliveryResponse, WebhookDeliveryStatus, WebhookEvent,
+    },
+    models::delivery::DeliveryPagination,
+};
+
+// Upper bound of per_page, a page is loaded in memory
+const MAX_PER_PAGE: i64 = 500;
+
+#[derive(Clone)]
+pub struct WebhookRepository {
+    pool: PgPool
+}
+
+impl WebhookRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, dto: CreateWebhookDto, secret: String) -> Result<Webhook, ApiError> {
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"
+            INSERT INTO webhooks (name, url, secret, event_types, enabled)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(dto.name)
+        .bind(dto.url)
+        .bind(secret)
+        .bind(dto.event_types)
+        .bind(dto.enabled)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<Option<Webhook>, ApiError> {
+        let webhook = sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(webhook)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<Webhook>, ApiError> {
+        let webhooks = sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(webhooks)
+    }
+
+    pub async fn update(&self, id: i32, dto: UpdateWebhookDto) -> Result<Option<Webhook>, ApiError> {
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"
+            UPDATE webhooks
+            SET name = COALESCE($2, name),
+                url = COALESCE($3, url),
+                secret = COALESCE($4, secret),
+                event_types = COALESCE($5, event_types),
+                enabled = COALESCE($6, enabled),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(dto.name)
+        .bind(dto.url)
+        .bind(dto.secret)
+        .bind(dto.event_types)
+        .bind(dto.enabled)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<Option<Webhook>, ApiError> {
+        let webhook = sqlx::query_as::<_, Webhook>("DELETE FROM webhooks WHERE id = $1 RETURNING *")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(webhook)
+    }
+
+    //
+    //** Queue an event for every enabled webhook listening to its type
+    //** The payload is built once, with the subscriber id, email and name when there is one
+    //** Params : event , webhook_id (only this webhook, whatever its filters, for test events)
+    //** Return : Result<u64, ApiError> (number of deliveries queued)
+    //
+    pub async fn enqueue(&self, event: &WebhookEvent, webhook_id: Option<i32>) -> Result<u64, ApiError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, event_type, payload)
+            SELECT w.id, $1, jsonb_build_object(
+                'event', $1::text,
+                'occurred_at', $2::timestamptz,
+                'subscriber', (SELECT jsonb_build_object('id', s.id, 'email', s.email, 'name', s.name) FROM subscribers s WHERE s.id = $3),
+                'campaign_id', $4::int,
+                'data', $5::jsonb
+            )
+            FROM webhooks w
+            WHERE w.enabled
+            AND CASE
+                WHEN $6::int IS NOT NULL THEN w.id = $6
+                ELSE cardinality(w.event_types) = 0 OR $1 = ANY(w.event_types)
+            END
+            "#,
+        )
+        .bind(event.event_type)
+        .bind(event.occurred_at)
+        .bind(event.subscriber_id)
+        .bind(event.campaign_id)
+        .bind(&event.data)
+        .bind(webhook_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    //
+    //** Claim deliveries due for an attempt, with the url and secret of their webhook
+    //** Deliveries stuck in 'sending' longer than lock_timeout_secs (crashed worker) are claimed again,
+    //** those of disabled webhooks wait until it is enabled again
+    //** Params : limit , lock_timeout_secs
+    //** Return : Result<Vec<ClaimedWebhookDelivery>, ApiError>
+    //
+    pub async fn claim(&self, limit: i64, lock_timeout_secs: i64) -> Result<Vec<ClaimedWebhookDelivery>, ApiError> {
+        let deliveries = sqlx::query_as::<_, ClaimedWebhookDelivery>(
+            r#"
+            UPDATE webhook_deliveries d
+            SET status = 'sending', locked_at = NOW(), attempts = d.attempts + 1, updated_at = NOW()
+            FROM webhooks w
+            WHERE w.id = d.webhook_id
+            AND d.id IN (
+                SELECT pending.id FROM webhook_deliveries pending
+                JOIN webhooks enabled ON enabled.id = pending.webhook_id AND enabled.enabled
+                WHERE (pending.status = 'pending' AND pending.available_at <= NOW())
+                OR (pending.status = 'sending' AND pending.locked_at < NOW() - make_interval(secs => $2))
+                ORDER BY pending.available_at, pending.id
+                LIMIT $1
+                FOR UPDATE OF pending SKIP LOCKED
+            )
+            RETURNING d.*, w.url, w.secret
+            "#,
+        )
+        .bind(limit)
+        .bind(lock_timeout_secs as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Record the outcome of an attempt, back to pending for a retry at `retry_at`
+    pub async fn record_attempt(
+        &self,
+        id: i64,
+        status: WebhookDeliveryStatus,
+        status_code: Option<i32>,
+        response: Option<&str>,
+        error: Option<&str>,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ApiError> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2, last_status_code = $3, last_response = $4, last_error = $5,
+                available_at = COALESCE($6, available_at), locked_at = NULL,
+                delivered_at = CASE WHEN $2 = 'success' THEN NOW() ELSE delivered_at END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(status_code)
+        .bind(response)
+        .bind(error)
+        .bind(retry_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Queue a delivery again, as a new series of attempts
+    pub async fn redeliver(&self, id: i64) -> Result<Option<WebhookDelivery>, ApiError> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'pending', attempts = 0, available_at = NOW(), locked_at = NULL, updated_at = NOW()
+            WHERE id = $1
+            AND status <> 'sending'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    pub async fn find_delivery(&self, id: i64) -> Result<Option<WebhookDelivery>, ApiError> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>("SELECT * FROM webhook_deliveries WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(delivery)
+    }
+
+    /// List deliveries, newest first
+    pub async fn find_deliveries(&self, filter: WebhookDeliveryFilter, pagination: DeliveryPagination) -> Result<WebhookDeliveryResponse, ApiError> {
+        let page = pagination.page.max(1);
+        let per_page = pagination.per_page.clamp(1, MAX_PER_PAGE);
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM webhook_deliveries");
+        push_filter(&mut count, &filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM webhook_deliveries");
+        push_filter(&mut query, &filter);
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        query.push_bind(per_page);
+        query.push(" OFFSET ");
+        query.push_bind((page - 1) * per_page);
+
+        let items = query.build_query_as::<WebhookDelivery>().fetch_all(&self.pool).await?;
+
+        Ok(WebhookDeliveryResponse { items, page, per_page, total })
+    }
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &WebhookDeliveryFilter) {
+    query.push(" WHERE TRUE");
+
+    if let Some(webhook_id) = filter.webhook_id {
+        query.push(" AND webhook_id = ").push_bind(webhook_id);
+    }
+    if let Some(event_type) = filter.event_type {
+        query.push(" AND event_type = ").push_bind(event_type);
+    }
+    if let Some(status) = filter.status {
+        query.push(" AND status = ").push_bind(status);
+    }
+}
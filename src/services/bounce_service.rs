@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::{
+    email_service::{bounce::{parse_dsn, Verp}, config::BounceConfig, maildir::MaildirReader},
+    models::bounce::{Bounce, BounceFilter, BounceResponse, BounceType, NewBounce},
+    models::delivery::DeliveryPagination,
+    models::webhook::{WebhookEvent, WebhookEventType},
+    repositories::bounce_repository::BounceRepository,
+    services::webhook_service::WebhookService,
+    error::ApiError
+};
+
+pub struct BounceService {
+    repository: BounceRepository,
+    config: BounceConfig,
+    verp: Option<Verp>,
+    webhooks: WebhookService,
+}
+
+impl BounceService {
+    pub fn new(repository: BounceRepository, config: BounceConfig, webhooks: WebhookService) -> Self {
+        let verp = config.verp();
+        Self { repository, config, verp, webhooks }
+    }
+
+    /// Record one bounce, blocklisting the subscriber past the hard bounce limit
+    pub async fn record(&self, bounce: NewBounce) -> Result<Bounce, ApiError> {
+        let bounce_type = bounce.bounce_type;
+        let recorded = self.repository.record(bounce, self.config.hard_bounce_limit).await?;
+
+        tracing::info!(
+            "Recorded {:?} bounce for {} (subscriber {:?}, delivery {:?})",
+            bounce_type, recorded.email, recorded.subscriber_id, recorded.delivery_id
+        );
+
+        self.webhooks.emit(WebhookEvent::new(
+            WebhookEventType::Bounce,
+            recorded.subscriber_id,
+            recorded.campaign_id,
+            serde_json::json!({
+                "email": recorded.email,
+                "bounce_type": recorded.bounce_type,
+                "status_code": recorded.status_code,
+                "diagnostic": recorded.diagnostic,
+                "source": recorded.source,
+            }),
+        )).await;
+
+        Ok(recorded)
+    }
+
+    //
+    //** Ingest a raw RFC 3464 delivery status notification
+    //** Params : raw (full MIME message)
+    //** Return : Result<Vec<Bounce>, ApiError> (one bounce per failed recipient, delayed ones are left out)
+    //
+    pub async fn ingest(&self, raw: &[u8]) -> Result<Vec<Bounce>, ApiError> {
+        let report = parse_dsn(raw, self.verp.as_ref()).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+        let mut bounces = Vec::with_capacity(report.recipients.len());
+        for recipient in report.recipients {
+            bounces.push(self.record(NewBounce {
+                email: recipient.email,
+                bounce_type: recipient.bounce_type,
+                status_code: recipient.status,
+                diagnostic: recipient.diagnostic,
+                source: "dsn".to_string(),
+                outbox_id: report.outbox_id,
+                message_id: report.message_id.clone(),
+            }).await?);
+        }
+
+        Ok(bounces)
+    }
+
+    pub async fn get_bounces(&self, filter: BounceFilter, pagination: DeliveryPagination) -> Result<BounceResponse, ApiError> {
+        self.repository.find_all(filter, pagination).await
+    }
+
+    //
+    //** Poll the configured Maildir for bounce reports
+    //** Messages are moved from new/ to cur/ once read, reports that cannot be parsed included
+    //** Return : None when no Maildir is configured
+    //
+    pub fn spawn_maildir_poller(self: Arc<Self>) -> Option<JoinHandle<()>> {
+        let maildir = MaildirReader::new(self.config.maildir.clone()?);
+        let interval = Duration::from_secs(self.config.maildir_poll_secs);
+        tracing::info!("Polling {} for bounces every {}s", maildir.dir().display(), interval.as_secs());
+
+        Some(tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.poll_maildir(&maildir).await {
+                    tracing::error!("Failed to read bounces from {}: {}", maildir.dir().display(), e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        }))
+    }
+
+    async fn poll_maildir(&self, maildir: &MaildirReader) -> std::io::Result<()> {
+        for path in maildir.unread().await? {
+            let raw = tokio::fs::read(&path).await?;
+
+            match self.ingest(&raw).await {
+                Ok(bounces) => {
+                    let hard = bounces.iter().filter(|b| b.bounce_type == BounceType::Hard).count();
+                    tracing::debug!("{}: {} bounces ({} hard)", path.display(), bounces.len(), hard);
+                }
+                // Not a DSN (auto-replies, spam), nothing to retry
+                Err(ApiError::BadRequest(e)) => tracing::warn!("Skipping {}: {}", path.display(), e),
+                // Left in new/ to be read again on the next poll
+                Err(e) => {
+                    tracing::error!("Failed to record bounces of {}: {}", path.display(), e);
+                    continue;
+                }
+            }
+
+            maildir.mark_read(&path).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ use serde_json::Value;
 
 use crate::{
     error::ApiError,
-    models::campaign::{Campaign, CreateCampaignDto, DeleteCampaignDto,  CampaignResponse, PaginationParams, CampaignFilter, UpdateCampaignDto},
+    models::campaign::{Campaign, CreateCampaignDto, DeleteCampaignDto,  CampaignResponse, PaginationParams, CampaignFilter, UpdateCampaignDto, CampaignLayout},
     repositories::campaign_repository::CampaignRepository
 };
 
@@ -40,4 +40,21 @@ impl CampaignService {
         println!("Deleting campaign: {:?}", dto);
         self.repository.delete(dto).await
     }
+
+    pub async fn get_layout(&self, id: i32) -> Result<CampaignLayout, ApiError> {
+        self.repository.find_layout(id).await?.ok_or(ApiError::NotFound)
+    }
+
+    //
+    //** Set the layout template the sequence emails of the campaign are wrapped in
+    //** Params : id , template_id (None to fall back to the default template)
+    //** Return : Result<CampaignLayout, ApiError>
+    //
+    pub async fn set_layout(&self, id: i32, template_id: Option<i32>) -> Result<CampaignLayout, ApiError> {
+        self.get_layout(id).await?;
+        self.repository
+            .set_layout(id, template_id)
+            .await?
+            .ok_or_else(|| ApiError::BadRequest(format!("Template {} does not exist", template_id.unwrap_or_default())))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::{
+    email_service::{bounce::Verp, complaint::parse_arf, config::ComplaintConfig, maildir::MaildirReader},
+    models::complaint::{Complaint, ComplaintFilter, ComplaintResponse, NewComplaint},
+    models::delivery::DeliveryPagination,
+    models::subscription_event::SubscriptionEventSource,
+    models::webhook::{WebhookEvent, WebhookEventType},
+    repositories::complaint_repository::ComplaintRepository,
+    repositories::subscriber_list_repository::SubscriberListRepository,
+    services::webhook_service::WebhookService,
+    error::ApiError
+};
+
+pub struct ComplaintService {
+    repository: ComplaintRepository,
+    lists: SubscriberListRepository,
+    config: ComplaintConfig,
+    verp: Option<Verp>,
+    webhooks: WebhookService,
+}
+
+impl ComplaintService {
+    pub fn new(
+        repository: ComplaintRepository,
+        lists: SubscriberListRepository,
+        config: ComplaintConfig,
+        verp: Option<Verp>,
+        webhooks: WebhookService,
+    ) -> Self {
+        Self { repository, lists, config, verp, webhooks }
+    }
+
+    //
+    //** Record a spam complaint
+    //** The subscriber is blocklisted and unsubscribed from every list
+    //** Params : complaint
+    //** Return : Result<Complaint, ApiError>
+    //
+    pub async fn record(&self, complaint: NewComplaint) -> Result<Complaint, ApiError> {
+        let recorded = self.repository.record(complaint).await?;
+
+        match recorded.subscriber_id {
+            Some(subscriber_id) => {
+                let lists = self.lists
+                    .unsubscribe(subscriber_id, None, recorded.campaign_id, SubscriptionEventSource::Complaint)
+                    .await?;
+                tracing::info!(
+                    "Subscriber {} complained (campaign {:?}), blocklisted and unsubscribed from {:?}",
+                    subscriber_id, recorded.campaign_id, lists
+                );
+            }
+            None => tracing::warn!("Complaint {} matches no subscriber", recorded.id),
+        }
+
+        self.webhooks.emit(WebhookEvent::new(
+            WebhookEventType::Complaint,
+            recorded.subscriber_id,
+            recorded.campaign_id,
+            serde_json::json!({
+                "email": recorded.email,
+                "feedback_type": recorded.feedback_type,
+                "user_agent": recorded.user_agent,
+                "source": recorded.source,
+            }),
+        )).await;
+
+        Ok(recorded)
+    }
+
+    //
+    //** Ingest a raw ARF (RFC 5965) feedback report
+    //** Params : raw (full MIME message)
+    //** Return : Result<Option<Complaint>, ApiError> (None for reports that are not spam complaints)
+    //
+    pub async fn ingest(&self, raw: &[u8]) -> Result<Option<Complaint>, ApiError> {
+        let report = parse_arf(raw, self.verp.as_ref()).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+        if report.feedback_type != "abuse" {
+            tracing::info!("Ignoring '{}' feedback report", report.feedback_type);
+            return Ok(None);
+        }
+
+        self.record(NewComplaint {
+            email: report.email,
+            feedback_type: report.feedback_type,
+            user_agent: report.user_agent,
+            source: "arf".to_string(),
+            outbox_id: report.outbox_id,
+            message_id: report.message_id,
+        }).await.map(Some)
+    }
+
+    pub async fn get_complaints(&self, filter: ComplaintFilter, pagination: DeliveryPagination) -> Result<ComplaintResponse, ApiError> {
+        self.repository.find_all(filter, pagination).await
+    }
+
+    //
+    //** Poll the configured feedback loop Maildir
+    //** Messages are moved from new/ to cur/ once read, reports that cannot be parsed included
+    //** Return : None when no Maildir is configured
+    //
+    pub fn spawn_maildir_poller(self: Arc<Self>) -> Option<JoinHandle<()>> {
+        let maildir = MaildirReader::new(self.config.maildir.clone()?);
+        let interval = Duration::from_secs(self.config.maildir_poll_secs);
+        tracing::info!("Polling {} for complaints every {}s", maildir.dir().display(), interval.as_secs());
+
+        Some(tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.poll_maildir(&maildir).await {
+                    tracing::error!("Failed to read complaints from {}: {}", maildir.dir().display(), e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        }))
+    }
+
+    async fn poll_maildir(&self, maildir: &MaildirReader) -> std::io::Result<()> {
+        for path in maildir.unread().await? {
+            let raw = tokio::fs::read(&path).await?;
+
+            match self.ingest(&raw).await {
+                Ok(_) => {}
+                // Not a feedback report, nothing to retry
+                Err(ApiError::BadRequest(e)) => tracing::warn!("Skipping {}: {}", path.display(), e),
+                // Left in new/ to be read again on the next poll
+                Err(e) => {
+                    tracing::error!("Failed to record complaint of {}: {}", path.display(), e);
+                    continue;
+                }
+            }
+
+            maildir.mark_read(&path).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{
+    models::delivery::{Delivery, DeliveryFilter, DeliveryPagination, DeliveryResponse},
+    repositories::delivery_repository::DeliveryRepository,
+    error::ApiError
+};
+
+pub struct DeliveryService {
+    repository: DeliveryRepository
+}
+
+impl DeliveryService {
+    pub fn new(repository: DeliveryRepository) -> Self {
+        Self { repository }
+    }
+
+    pub async fn get_deliveries(&self, filter: DeliveryFilter, pagination: DeliveryPagination) -> Result<DeliveryResponse, ApiError> {
+        self.repository.find_all(filter, pagination).await
+    }
+
+    pub async fn get_delivery(&self, id: i64) -> Result<Delivery, ApiError> {
+        self.repository.find_by_id(id).await?.ok_or(ApiError::NotFound)
+    }
+}
@@ -0,0 +1,266 @@
+use std::sync::Arc;
+use sqlx::PgPool;
+use serde::Serialize;
+use crate::{
+    email_service::{EmailService, EmailRequest, error::EmailError, merge, unsubscribe::UnsubscribeLinks},
+    repositories::{
+        list_repository::ListsRepository,
+        subscriber_list_repository::SubscriberListRepository,
+        template_repository::TemplateRepository,
+    },
+    models::email_outbox::NewOutboxMessage,
+    models::list::ListOptin,
+    models::subscriber::Subscriber,
+    models::subscriber_list::SubscriptionStatus,
+    models::subscriber_sequence_progress::SubscriberSequenceProgress,
+    models::subscription_event::SubscriptionEventSource,
+    services::sequence_optin_service::SequenceOptinService,
+    error::ApiError
+};
+
+const DEFAULT_SUBJECT: &str = "Confirm your subscription to {{ list_name }}";
+const DEFAULT_BODY: &str = r#"<p>Hello {{ name }},</p>
+<p>Please confirm your subscription to <strong>{{ list_name }}</strong> by clicking the link below.</p>
+<p><a href="{{ confirm_url }}">Confirm my subscription</a></p>
+<p>If you did not ask for this, you can ignore this email.</p>"#;
+
+/// What a subscription still needs once its row is in place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptinStep {
+    SendConfirmation,
+    // Pending from before the list switched to single opt-in
+    ConfirmAndStart,
+    StartSequences,
+    Nothing,
+}
+
+fn next_step(optin: &ListOptin, status: &SubscriptionStatus) -> OptinStep {
+    match (optin, status) {
+        (ListOptin::Double, SubscriptionStatus::Unconfirmed) => OptinStep::SendConfirmation,
+        (ListOptin::Single, SubscriptionStatus::Unconfirmed) => OptinStep::ConfirmAndStart,
+        (_, SubscriptionStatus::Confirmed) => OptinStep::StartSequences,
+        (_, SubscriptionStatus::Unsubscribed) => OptinStep::Nothing,
+    }
+}
+
+/// Outcome of adding a subscriber to a list
+#[derive(Debug, Serialize)]
+pub struct SubscribeOutcome {
+    pub status: SubscriptionStatus,
+    // True when a confirmation email was queued
+    pub confirmation_sent: bool,
+    // Sequences started, only once the subscription is confirmed
+    pub sequences: Vec<SubscriberSequenceProgress>,
+}
+
+//
+//** Subscriptions honouring the opt-in mode of the list
+//** Double opt-in lists stay unconfirmed until the signed link sent by email is followed
+//** OPTIN_TEMPLATE_ID selects the template of the confirmation email, a built-in one is used when unset
+//** The template may use {{ list_name }} , {{ confirm_url }} , {{ email }} and {{ name }}
+//
+pub struct DoubleOptinService {
+    pool: PgPool,
+    lists: ListsRepository,
+    subscriber_lists: SubscriberListRepository,
+    templates: TemplateRepository,
+    sequences: Arc<SequenceOptinService>,
+    email_service: EmailService,
+    links: UnsubscribeLinks,
+    template_id: Option<i32>,
+}
+
+impl DoubleOptinService {
+    pub fn new(
+        pool: PgPool,
+        lists: ListsRepository,
+        sequences: Arc<SequenceOptinService>,
+        email_service: EmailService,
+        links: UnsubscribeLinks,
+    ) -> Self {
+        let template_id = std::env::var("OPTIN_TEMPLATE_ID").ok().and_then(|id| id.parse().ok());
+
+        Self {
+            subscriber_lists: SubscriberListRepository::new(pool.clone()),
+            templates: TemplateRepository::new(pool.clone()),
+            pool,
+            lists,
+            sequences,
+            email_service,
+            links,
+            template_id,
+        }
+    }
+
+    //
+    //** Add a subscriber to a list
+    //** Single opt-in lists are confirmed and start their sequences right away
+    //** Double opt-in lists get a confirmation email, sent again while the subscription is pending
+    //** Params : subscriber , list_id , source (recorded in subscription_events)
+    //** Return : Result<SubscribeOutcome, ApiError>
+    //
+    pub async fn subscribe(
+        &self,
+        subscriber: &Subscriber,
+        list_id: i32,
+        source: SubscriptionEventSource,
+    ) -> Result<SubscribeOutcome, ApiError> {
+        let list = self.lists.find_by_id(list_id).await?.ok_or(ApiError::NotFound)?;
+
+        self.subscriber_lists
+            .subscribe(subscriber.id, &[list_id], source)
+            .await?;
+
+        let status = self.subscriber_lists
+            .find_by_subscriber_id_and_list_id(subscriber.id, list_id)
+            .await?
+            .map(|subscription| subscription.status)
+            .ok_or(ApiError::NotFound)?;
+
+        match next_step(&list.optin, &status) {
+            OptinStep::SendConfirmation => {
+                self.send_confirmation(subscriber, list_id, &list.name).await?;
+                Ok(SubscribeOutcome {
+                    status: SubscriptionStatus::Unconfirmed,
+                    confirmation_sent: true,
+                    sequences: Vec::new(),
+                })
+            }
+            OptinStep::ConfirmAndStart => {
+                self.subscriber_lists.confirm(subscriber.id, list_id).await?;
+                Ok(SubscribeOutcome {
+                    status: SubscriptionStatus::Confirmed,
+                    confirmation_sent: false,
+                    sequences: self.sequences.initialize_sequences_for_subscriber(subscriber.id, list_id).await?,
+                })
+            }
+            OptinStep::StartSequences => Ok(SubscribeOutcome {
+                sequences: self.sequences.initialize_sequences_for_subscriber(subscriber.id, list_id).await?,
+                status,
+                confirmation_sent: false,
+            }),
+            OptinStep::Nothing => Ok(SubscribeOutcome {
+                status,
+                confirmation_sent: false,
+                sequences: Vec::new(),
+            }),
+        }
+    }
+
+    /// Queue the confirmation email of a pending subscription
+    async fn send_confirmation(&self, subscriber: &Subscriber, list_id: i32, list_name: &str) -> Result<(), ApiError> {
+        let (subject, body) = match self.template_id {
+            Some(id) => {
+                let template = self.templates.find_by_id(id).await?;
+                (template.subject, template.body)
+            }
+            None => (DEFAULT_SUBJECT.to_string(), DEFAULT_BODY.to_string()),
+        };
+
+        let confirm_url = self.links.optin_url(subscriber.id, list_id);
+        let request = confirmation_request(subscriber, list_name, &confirm_url, subject, body)?;
+
+        self.email_service.queue_email(&self.pool, NewOutboxMessage {
+            request,
+            subscriber_id: Some(subscriber.id),
+            ..Default::default()
+        }).await?;
+
+        tracing::info!("Queued opt-in confirmation for subscriber {} on list {}", subscriber.id, list_id);
+        Ok(())
+    }
+
+    //
+    //** Confirm a double opt-in subscription from its emailed link, then start the list sequences
+    //** Params : token
+    //** Return : Result<Vec<SubscriberSequenceProgress>, ApiError> (empty when already confirmed)
+    //
+    pub async fn confirm(&self, token: &str) -> Result<Vec<SubscriberSequenceProgress>, ApiError> {
+        let token = self.links
+            .verify_optin(token)
+            .ok_or_else(|| ApiError::BadRequest("Invalid or expired confirmation link".to_string()))?;
+
+        if !self.subscriber_lists.confirm(token.subscriber_id, token.list_id).await? {
+            return Ok(Vec::new());
+        }
+
+        tracing::info!("Subscriber {} confirmed list {}", token.subscriber_id, token.list_id);
+        self.sequences.initialize_sequences_for_subscriber(token.subscriber_id, token.list_id).await
+    }
+}
+
+//
+//** Render the confirmation email of a subscriber through the merge engine
+//** Tags : {{ name }} (email when unset) , {{ email }} , {{ list_name }} , {{ confirm_url }}
+//** Values are HTML escaped in the body like campaign merge tags
+//
+fn confirmation_request(
+    subscriber: &Subscriber,
+    list_name: &str,
+    confirm_url: &str,
+    subject: String,
+    body: String,
+) -> Result<EmailRequest, EmailError> {
+    let context = serde_json::json!({
+        "name": subscriber.name.as_deref().unwrap_or(&subscriber.email),
+        "email": subscriber.email,
+        "list_name": list_name,
+        "confirm_url": confirm_url,
+    });
+
+    let mut request = EmailRequest {
+        to: subscriber.email.clone(),
+        subject,
+        body,
+        ..Default::default()
+    };
+    merge::render(&mut request, &context)?;
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_optin_lists_wait_for_confirmation() {
+        assert_eq!(next_step(&ListOptin::Double, &SubscriptionStatus::Unconfirmed), OptinStep::SendConfirmation);
+        assert_eq!(next_step(&ListOptin::Double, &SubscriptionStatus::Confirmed), OptinStep::StartSequences);
+        assert_eq!(next_step(&ListOptin::Double, &SubscriptionStatus::Unsubscribed), OptinStep::Nothing);
+    }
+
+    #[test]
+    fn single_optin_lists_start_sequences_right_away() {
+        assert_eq!(next_step(&ListOptin::Single, &SubscriptionStatus::Confirmed), OptinStep::StartSequences);
+        assert_eq!(next_step(&ListOptin::Single, &SubscriptionStatus::Unconfirmed), OptinStep::ConfirmAndStart);
+        assert_eq!(next_step(&ListOptin::Single, &SubscriptionStatus::Unsubscribed), OptinStep::Nothing);
+    }
+
+    #[test]
+    fn confirmation_values_are_escaped_in_the_body() {
+        let subscriber = Subscriber {
+            id: 1,
+            uuid: uuid::Uuid::nil(),
+            email: "ada@example.com".to_string(),
+            name: Some("<a href=\"https://evil.example\">Ada</a>".to_string()),
+            attribs: serde_json::json!({}),
+            status: crate::models::subscriber::SubscriberStatus::Enabled,
+            created_at: None,
+            updated_at: None,
+        };
+
+        let request = confirmation_request(
+            &subscriber,
+            "News & Deals",
+            "https://example.com/optin?t=a&b",
+            DEFAULT_SUBJECT.to_string(),
+            DEFAULT_BODY.to_string(),
+        ).unwrap();
+
+        assert_eq!(request.to, "ada@example.com");
+        assert_eq!(request.subject, "Confirm your subscription to News & Deals");
+        assert!(request.body.contains("Hello &lt;a href=&quot;https://evil.example&quot;&gt;Ada&lt;/a&gt;,"));
+        assert!(request.body.contains("<strong>News &amp; Deals</strong>"));
+        assert!(request.body.contains("<a href=\"https://example.com/optin?t=a&amp;b\">"));
+    }
+}
@@ -5,7 +5,9 @@ use crate::{
         GetEmailViewDto,
         PaginationDto
     },
+    models::webhook::{WebhookEvent, WebhookEventType},
     repositories::email_views_repository::EmailViewsRepository,
+    services::webhook_service::WebhookService,
     error::ApiError
 };
 use actix_web::HttpRequest;
@@ -14,17 +16,32 @@ use tracing::info;
 use serde_json;
 
 pub struct EmailViewsService {
-    repository: EmailViewsRepository
+    repository: EmailViewsRepository,
+    webhooks: WebhookService,
 }
 
 impl EmailViewsService {
-    pub fn new(repository: EmailViewsRepository) -> Self {
-        Self { repository }
+    pub fn new(repository: EmailViewsRepository, webhooks: WebhookService) -> Self {
+        Self { repository, webhooks }
     }
 
     pub async fn create_email_view(&self, dto: CreateEmailViewDto) -> Result<EmailView, ApiError> {
         info!("Creating email view: {:?}", dto);
-        self.repository.create(dto).await
+        let view = self.repository.create(dto).await?;
+
+        self.webhooks.emit(WebhookEvent::new(
+            WebhookEventType::Open,
+            view.subscriber_id,
+            view.campaign_id,
+            serde_json::json!({
+                "sequence_email_id": view.sequence_email_id,
+                "ip_address": view.ip_address,
+                "user_agent": view.user_agent,
+                "country": view.country,
+            }),
+        )).await;
+
+        Ok(view)
     }
 
     pub async fn get_email_view(
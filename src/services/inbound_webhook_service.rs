@@ -0,0 +1,199 @@
+use std::sync::Arc;
+use crate::{
+    email_service::{
+        config::InboundWebhookConfig,
+        error::EmailError,
+        inbound::{mailgun::MailgunWebhook, sendgrid, sns::{SnsMessage, SnsVerifier}, InboundEvent},
+    },
+    models::bounce::{BounceType, NewBounce},
+    models::complaint::NewComplaint,
+    models::provider_event::{ProviderEvent, ProviderEventType},
+    models::subscription_event::SubscriptionEventSource,
+    models::webhook::{WebhookEvent, WebhookEventType},
+    repositories::provider_event_repository::ProviderEventRepository,
+    repositories::subscriber_list_repository::SubscriberListRepository,
+    services::{bounce_service::BounceService, complaint_service::ComplaintService, webhook_service::WebhookService},
+    error::ApiError
+};
+
+//
+//** Delivery events posted by providers (SES through SNS, SendGrid, Mailgun)
+//** Every request is checked against the provider signature scheme before anything is recorded.
+//** Bounces and complaints go through the bounce and complaint services (blocklist, suppressions),
+//** every event is kept in provider_events
+//
+pub struct InboundWebhookService {
+    events: ProviderEventRepository,
+    lists: SubscriberListRepository,
+    bounces: Arc<BounceService>,
+    complaints: Arc<ComplaintService>,
+    webhooks: WebhookService,
+    config: InboundWebhookConfig,
+    sns: SnsVerifier,
+}
+
+impl InboundWebhookService {
+    pub fn new(
+        events: ProviderEventRepository,
+        lists: SubscriberListRepository,
+        bounces: Arc<BounceService>,
+        complaints: Arc<ComplaintService>,
+        webhooks: WebhookService,
+        config: InboundWebhookConfig,
+    ) -> Self {
+        Self { events, lists, bounces, complaints, webhooks, config, sns: SnsVerifier::default() }
+    }
+
+    //
+    //** Ingest an SNS message carrying SES notifications
+    //** Subscription confirmations are followed when SNS_AUTO_CONFIRM is on
+    //** Return : Result<usize, ApiError> (number of events recorded)
+    //
+    pub async fn ingest_sns(&self, body: &[u8]) -> Result<usize, ApiError> {
+        let message = SnsMessage::parse(body).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+        if !self.config.sns_topic_arns.is_empty() && !self.config.sns_topic_arns.contains(&message.topic_arn) {
+            return Err(EmailError::InvalidSignature(format!("Unexpected SNS topic {}", message.topic_arn)).into());
+        }
+        self.sns.verify(&message, self.config.max_age_secs).await?;
+
+        match message.kind.as_str() {
+            "Notification" => {
+                let events = message.events().map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                self.process(events).await
+            }
+            _ if message.is_subscription_confirmation() => {
+                if self.config.sns_auto_confirm {
+                    self.sns.confirm_subscription(&message).await?;
+                } else {
+                    tracing::warn!("SNS subscription to {} awaiting manual confirmation", message.topic_arn);
+                }
+                Ok(0)
+            }
+            kind => {
+                tracing::info!("Ignoring SNS {} message for {}", kind, message.topic_arn);
+                Ok(0)
+            }
+        }
+    }
+
+    /// Ingest a batch of SendGrid events, from its signed event webhook
+    pub async fn ingest_sendgrid(&self, signature: Option<&str>, timestamp: Option<&str>, body: &[u8]) -> Result<usize, ApiError> {
+        let public_key = self.config.sendgrid_public_key.as_deref()
+            .ok_or_else(|| EmailError::InvalidSignature("SENDGRID_WEBHOOK_PUBLIC_KEY is not set".to_string()))?;
+        let (Some(signature), Some(timestamp)) = (signature, timestamp) else {
+            return Err(EmailError::InvalidSignature("Missing SendGrid signature headers".to_string()).into());
+        };
+        sendgrid::verify(public_key, signature, timestamp, body, self.config.max_age_secs)?;
+
+        let events = sendgrid::parse(body).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        self.process(events).await
+    }
+
+    /// Ingest one Mailgun webhook event
+    pub async fn ingest_mailgun(&self, body: &[u8]) -> Result<usize, ApiError> {
+        let signing_key = self.config.mailgun_signing_key.as_deref()
+            .ok_or_else(|| EmailError::InvalidSignature("MAILGUN_WEBHOOK_SIGNING_KEY is not set".to_string()))?;
+        let webhook = MailgunWebhook::parse(body).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        webhook.verify(signing_key, self.config.max_age_secs)?;
+
+        self.process(webhook.event().into_iter().collect()).await
+    }
+
+    //
+    //** Apply a batch of events, providers retry the whole request when one fails
+    //** Return : Result<usize, ApiError> (number of events not seen before)
+    //
+    async fn process(&self, events: Vec<InboundEvent>) -> Result<usize, ApiError> {
+        let mut count = 0;
+        for event in events {
+            let Some(recorded) = self.events.record(&event).await? else {
+                tracing::debug!("Skipping {} event {:?}, already recorded", event.provider, event.event_id);
+                continue;
+            };
+
+            // Forgotten on failure, the redelivered event gets its side effects applied again
+            let id = recorded.id;
+            if let Err(e) = self.process_event(event, recorded).await {
+                self.events.forget(id).await?;
+                return Err(e);
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    //
+    //** Apply one recorded event
+    //** Bounces and complaints update the delivery, the subscriber and the suppression list through their
+    //** services, provider unsubscribes leave every list, the rest only updates the delivery log
+    //
+    async fn process_event(&self, event: InboundEvent, recorded: ProviderEvent) -> Result<(), ApiError> {
+        tracing::debug!(
+            "{} {:?} event for {} (delivery {:?})",
+            event.provider, event.event_type, event.email, recorded.delivery_id
+        );
+
+        match event.event_type {
+            ProviderEventType::Bounce => {
+                self.bounces.record(NewBounce {
+                    email: event.email,
+                    bounce_type: event.bounce_type.unwrap_or(BounceType::Hard),
+                    status_code: event.status_code,
+                    diagnostic: event.diagnostic,
+                    source: event.provider.to_string(),
+                    outbox_id: event.outbox_id,
+                    message_id: event.message_id,
+                }).await?;
+            }
+            ProviderEventType::Complaint => {
+                self.complaints.record(NewComplaint {
+                    email: Some(event.email),
+                    feedback_type: "abuse".to_string(),
+                    user_agent: event.user_agent,
+                    source: event.provider.to_string(),
+                    outbox_id: event.outbox_id,
+                    message_id: event.message_id,
+                }).await?;
+            }
+            ProviderEventType::Unsubscribe => {
+                // Provider unsubscribes are global on their side, mirror that
+                if let Some(subscriber_id) = recorded.subscriber_id {
+                    let lists = self.lists
+                        .unsubscribe(subscriber_id, None, recorded.campaign_id, SubscriptionEventSource::Provider)
+                        .await?;
+                    tracing::info!("Subscriber {} unsubscribed through {} from {:?}", subscriber_id, event.provider, lists);
+
+                    self.webhooks.emit(WebhookEvent::new(
+                        WebhookEventType::Unsubscribe,
+                        Some(subscriber_id),
+                        recorded.campaign_id,
+                        serde_json::json!({ "list_ids": lists, "source": event.provider }),
+                    )).await;
+                }
+            }
+            // Bounces and complaints are sent on by their own services
+            ProviderEventType::Open | ProviderEventType::Click => {
+                let event_type = match event.event_type {
+                    ProviderEventType::Open => WebhookEventType::Open,
+                    _ => WebhookEventType::Click,
+                };
+                self.webhooks.emit(WebhookEvent::new(
+                    event_type,
+                    recorded.subscriber_id,
+                    recorded.campaign_id,
+                    serde_json::json!({
+                        "email": recorded.email,
+                        "url": recorded.url,
+                        "user_agent": event.user_agent,
+                        "ip_address": event.ip_address,
+                        "source": event.provider,
+                    }),
+                )).await;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
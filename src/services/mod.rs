@@ -8,4 +8,15 @@ pub mod email_views_service;
 pub mod sequence_email_service;
 pub mod campaign_stats_service;
 pub mod global_stats_service;
-pub mod sequence_optin_service;
\ No newline at end of file
+pub mod sequence_optin_service;
+pub mod delivery_service;
+pub mod subscription_service;
+pub mod preference_service;
+pub mod double_optin_service;
+pub mod bounce_service;
+pub mod complaint_service;
+pub mod suppression_service;
+pub mod inbound_webhook_service;
+pub mod webhook_service;
+pub mod webhook_worker;
+pub mod preview_service;
\ No newline at end of file
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+use sqlx::types::JsonValue;
+use crate::{
+    email_service::unsubscribe::UnsubscribeLinks,
+    repositories::{
+        subscriber_repository::SubscriberRepository,
+        subscriber_list_repository::SubscriberListRepository,
+    },
+    models::preference::{SubscriberPreferences, UpdatePreferencesDto},
+    models::subscription_event::SubscriptionEventSource,
+    models::webhook::{WebhookEvent, WebhookEventType},
+    services::{double_optin_service::DoubleOptinService, webhook_service::WebhookService},
+    error::ApiError
+};
+
+//
+//** Hosted preference center, reached through the signed link sent in campaigns
+//** PREFERENCE_ATTRIBS lists the attribute keys subscribers may edit (comma separated)
+//
+pub struct PreferenceService {
+    subscribers: SubscriberRepository,
+    lists: SubscriberListRepository,
+    links: UnsubscribeLinks,
+    editable_attribs: Vec<String>,
+    webhooks: WebhookService,
+    optin: Arc<DoubleOptinService>,
+}
+
+impl PreferenceService {
+    pub fn new(
+        subscribers: SubscriberRepository,
+        lists: SubscriberListRepository,
+        links: UnsubscribeLinks,
+        webhooks: WebhookService,
+        optin: Arc<DoubleOptinService>,
+    ) -> Self {
+        let editable_attribs = std::env::var("PREFERENCE_ATTRIBS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+            .collect();
+
+        Self { subscribers, lists, links, editable_attribs, webhooks, optin }
+    }
+
+    async fn emit_unsubscribe(&self, subscriber_id: i32, lists: &[i32]) {
+        self.webhooks.emit(WebhookEvent::new(
+            WebhookEventType::Unsubscribe,
+            Some(subscriber_id),
+            None,
+            serde_json::json!({ "list_ids": lists, "source": "preference_center" }),
+        )).await;
+    }
+
+    pub fn editable_attribs(&self) -> &[String] {
+        &self.editable_attribs
+    }
+
+    /// Absolute URL of the HTML preference center of the token's subscriber
+    pub fn page_url(&self, token: &str) -> Result<String, ApiError> {
+        Ok(self.links.preferences_url(self.subscriber_id(token)?))
+    }
+
+    fn subscriber_id(&self, token: &str) -> Result<i32, ApiError> {
+        self.links
+            .verify_preferences(token)
+            .map(|token| token.subscriber_id)
+            .ok_or_else(|| ApiError::BadRequest("Invalid preferences token".to_string()))
+    }
+
+    //
+    //** Preferences of the subscriber the token was issued for
+    //** Params : token
+    //** Return : Result<SubscriberPreferences, ApiError>
+    //
+    pub async fn get(&self, token: &str) -> Result<SubscriberPreferences, ApiError> {
+        let subscriber_id = self.subscriber_id(token)?;
+        let subscriber = self.subscribers
+            .find_by_id(subscriber_id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+
+        let attribs = match subscriber.attribs {
+            JsonValue::Object(attribs) => attribs.into_iter()
+                .filter(|(key, _)| self.editable_attribs.contains(key))
+                .collect(),
+            _ => serde_json::Map::new(),
+        };
+
+        Ok(SubscriberPreferences {
+            email: subscriber.email,
+            name: subscriber.name,
+            attribs,
+            lists: self.lists.find_public_preferences(subscriber_id).await?,
+        })
+    }
+
+    //
+    //** Update name, editable attributes and public list subscriptions
+    //** Attributes that are not editable are ignored, list changes are recorded as preference center events
+    //** Params : token , UpdatePreferencesDto
+    //** Return : Result<SubscriberPreferences, ApiError> (preferences after the update)
+    //
+    pub async fn update(&self, token: &str, update: UpdatePreferencesDto) -> Result<SubscriberPreferences, ApiError> {
+        let subscriber_id = self.subscriber_id(token)?;
+
+        if update.name.is_some() || update.attribs.is_some() {
+            let mut subscriber = self.subscribers
+                .find_by_id(subscriber_id)
+                .await?
+                .ok_or(ApiError::NotFound)?;
+
+            if let Some(name) = update.name {
+                let name = name.trim().to_string();
+                subscriber.name = (!name.is_empty()).then_some(name);
+            }
+
+            if let Some(attribs) = update.attribs {
+                if !subscriber.attribs.is_object() {
+                    subscriber.attribs = JsonValue::Object(serde_json::Map::new());
+                }
+                let current = subscriber.attribs.as_object_mut().expect("attribs is an object");
+                for (key, value) in attribs {
+                    if self.editable_attribs.contains(&key) {
+                        current.insert(key, value);
+                    }
+                }
+            }
+
+            self.subscribers.update_by_id(subscriber_id, subscriber).await?;
+        }
+
+        if let Some(wanted) = update.list_ids {
+            let lists = self.lists.find_public_preferences(subscriber_id).await?;
+
+            let subscribe: Vec<i32> = lists.iter()
+                .filter(|list| !list.subscribed() && wanted.contains(&list.list_id))
+                .map(|list| list.list_id)
+                .collect();
+            let unsubscribe: Vec<i32> = lists.iter()
+                .filter(|list| list.subscribed() && !wanted.contains(&list.list_id))
+                .map(|list| list.list_id)
+                .collect();
+
+            if !subscribe.is_empty() {
+                // Through the opt-in service: double opt-in lists get their confirmation email, others start their sequences
+                let subscriber = self.subscribers.find_by_id(subscriber_id).await?.ok_or(ApiError::NotFound)?;
+                for list_id in subscribe {
+                    self.optin.subscribe(&subscriber, list_id, SubscriptionEventSource::PreferenceCenter).await?;
+                }
+            }
+            if !unsubscribe.is_empty() {
+                let lists = self.lists
+                    .unsubscribe(subscriber_id, Some(&unsubscribe), None, SubscriptionEventSource::PreferenceCenter)
+                    .await?;
+                if !lists.is_empty() {
+                    self.emit_unsubscribe(subscriber_id, &lists).await;
+                }
+            }
+        }
+
+        self.get(token).await
+    }
+
+    /// Unsubscribe from every list, public or not
+    pub async fn unsubscribe_all(&self, token: &str) -> Result<Vec<i32>, ApiError> {
+        let subscriber_id = self.subscriber_id(token)?;
+        let lists = self.lists
+            .unsubscribe(subscriber_id, None, None, SubscriptionEventSource::PreferenceCenter)
+            .await?;
+
+        tracing::info!("Subscriber {} unsubscribed from every list ({:?})", subscriber_id, lists);
+        if !lists.is_empty() {
+            self.emit_unsubscribe(subscriber_id, &lists).await;
+        }
+        Ok(lists)
+    }
+}
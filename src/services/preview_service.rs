@@ -0,0 +1,202 @@
+use sqlx::PgPool;
+use serde_json::Value as JsonValue;
+use crate::{
+    email_service::{content, merge::{self, MergeContext}, EmailRequest, EmailService},
+    models::preview::{PreviewDto, PreviewResponse, TestSendDto, TestSendResponse, TestSendResult},
+    repositories::email_outbox_repository::EmailOutboxRepository,
+    repositories::sequence_email_repository::SequenceEmailRepository,
+    repositories::template_repository::TemplateRepository,
+    error::ApiError,
+};
+
+// Prepended to the subject of test sends
+const TEST_SUBJECT_PREFIX: &str = "[TEST] ";
+
+// Seed addresses accepted by a single test send
+const MAX_SEED_ADDRESSES: usize = 20;
+
+/// Subject and body of a message before its merge tags are rendered
+struct Draft {
+    subject: String,
+    body: String,
+    campaign_id: Option<i32>,
+    messenger: Option<String>,
+}
+
+pub struct PreviewService {
+    pool: PgPool,
+    templates: TemplateRepository,
+    sequence_emails: SequenceEmailRepository,
+    outbox: EmailOutboxRepository,
+    email_service: EmailService,
+}
+
+impl PreviewService {
+    pub fn new(pool: PgPool, email_service: EmailService) -> Self {
+        Self {
+            templates: TemplateRepository::new(pool.clone()),
+            sequence_emails: SequenceEmailRepository::new(pool.clone()),
+            outbox: EmailOutboxRepository::new(pool.clone()),
+            pool,
+            email_service,
+        }
+    }
+
+    pub async fn preview_template(&self, id: i32, dto: &PreviewDto) -> Result<PreviewResponse, ApiError> {
+        let draft = self.template_draft(id).await?;
+        self.render(&draft, dto, false).await
+    }
+
+    pub async fn preview_sequence_email(&self, id: i32, dto: &PreviewDto) -> Result<PreviewResponse, ApiError> {
+        let draft = self.sequence_email_draft(id).await?;
+        self.render(&draft, dto, false).await
+    }
+
+    pub async fn test_send_template(&self, id: i32, dto: &TestSendDto) -> Result<TestSendResponse, ApiError> {
+        let draft = self.template_draft(id).await?;
+        self.test_send(&draft, dto).await
+    }
+
+    pub async fn test_send_sequence_email(&self, id: i32, dto: &TestSendDto) -> Result<TestSendResponse, ApiError> {
+        let draft = self.sequence_email_draft(id).await?;
+        self.test_send(&draft, dto).await
+    }
+
+    async fn template_draft(&self, id: i32) -> Result<Draft, ApiError> {
+        let template = self.templates.find_by_id(id).await?;
+
+        Ok(Draft {
+            subject: template.subject,
+            body: self.email_service.normalize_html(&template.body),
+            campaign_id: None,
+            messenger: None,
+        })
+    }
+
+    /// Sequence email as it is queued: rendered from its content type, wrapped in its layout and normalized
+    async fn sequence_email_draft(&self, id: i32) -> Result<Draft, ApiError> {
+        let email = self.sequence_emails.find_by_id(id).await?.ok_or(ApiError::NotFound)?;
+
+        let body = self.email_service.prepare_sequence_body(&self.pool, &email).await?;
+
+        Ok(Draft {
+            subject: email.subject,
+            body,
+            campaign_id: Some(email.campaign_id),
+            messenger: self.email_service.campaign_messenger(&self.pool, email.campaign_id).await?,
+        })
+    }
+
+    //
+    //** Merge context of a preview, for an existing subscriber or a sample one
+    //** Params : campaign_id , dto
+    //** Return : Err(ApiError::NotFound) when subscriber_id is unknown
+    //
+    async fn context(&self, campaign_id: Option<i32>, dto: &PreviewDto) -> Result<MergeContext, ApiError> {
+        let campaign_id = campaign_id.unwrap_or(0);
+
+        let mut context = match dto.subscriber_id {
+            Some(subscriber_id) => self.outbox.merge_context(subscriber_id, campaign_id).await?.ok_or(ApiError::NotFound)?,
+            None => {
+                let mut context = self.outbox.sample_merge_context(campaign_id).await?;
+                context.subscriber = serde_json::json!({
+                    "id": 0,
+                    "email": dto.email.clone().unwrap_or_else(|| "subscriber@example.com".to_string()),
+                    "name": dto.name.clone().unwrap_or_else(|| "Sample Subscriber".to_string()),
+                    "attribs": {},
+                    "status": "enabled",
+                });
+                context
+            }
+        };
+
+        match &dto.attribs {
+            None | Some(JsonValue::Null) => {}
+            Some(JsonValue::Object(attribs)) => {
+                if let Some(subscriber) = context.subscriber.as_object_mut() {
+                    match subscriber.get_mut("attribs") {
+                        Some(JsonValue::Object(existing)) => existing.extend(attribs.clone()),
+                        _ => {
+                            subscriber.insert("attribs".to_string(), JsonValue::Object(attribs.clone()));
+                        }
+                    }
+                }
+            }
+            Some(_) => return Err(ApiError::BadRequest("attribs must be a JSON object".to_string())),
+        }
+
+        Ok(context)
+    }
+
+    //
+    //** Render a draft the way the outbox worker would, without tracking
+    //** Test sends get links of subscriber 0, a seed address must not unsubscribe the previewed subscriber
+    //** Params : draft , dto , test_send
+    //** Return : Result<PreviewResponse, ApiError>
+    //
+    async fn render(&self, draft: &Draft, dto: &PreviewDto, test_send: bool) -> Result<PreviewResponse, ApiError> {
+        let mut context = self.context(draft.campaign_id, dto).await?;
+
+        let link_subscriber = if test_send { 0 } else { dto.subscriber_id.unwrap_or(0) };
+        context.unsubscribe_url = self.email_service
+            .unsubscribe_links()
+            .map(|links| links.url(link_subscriber, draft.campaign_id.unwrap_or(0)));
+        context.preferences_url = self.email_service.preferences_url(link_subscriber);
+
+        let mut request = EmailRequest {
+            subject: draft.subject.clone(),
+            body: draft.body.clone(),
+            ..Default::default()
+        };
+        merge::render(&mut request, &context)?;
+
+        Ok(PreviewResponse {
+            text: content::html_to_text(&request.body),
+            subject: request.subject,
+            html: request.body,
+        })
+    }
+
+    //
+    //** Send a rendered draft to seed addresses
+    //** Sent straight through the provider: no outbox, delivery, tracking or campaign counter is touched
+    //** Params : draft , dto
+    //** Return : Result<TestSendResponse, ApiError> (one result per address)
+    //
+    async fn test_send(&self, draft: &Draft, dto: &TestSendDto) -> Result<TestSendResponse, ApiError> {
+        if dto.to.is_empty() {
+            return Err(ApiError::BadRequest("At least one seed address is required".to_string()));
+        }
+        if dto.to.len() > MAX_SEED_ADDRESSES {
+            return Err(ApiError::BadRequest(format!("At most {} seed addresses are allowed", MAX_SEED_ADDRESSES)));
+        }
+
+        let preview = self.render(draft, &dto.preview, true).await?;
+        let mut response = TestSendResponse::default();
+
+        for to in &dto.to {
+            let request = EmailRequest {
+                to: to.clone(),
+                subject: format!("{}{}", TEST_SUBJECT_PREFIX, preview.subject),
+                body: preview.html.clone(),
+                text_body: Some(preview.text.clone()),
+                ..Default::default()
+            };
+
+            let result = match self.email_service.send_via(draft.messenger.as_deref(), request).await {
+                Ok(receipt) => {
+                    response.sent += 1;
+                    TestSendResult { to: to.clone(), message_id: Some(receipt.message_id), error: None }
+                }
+                Err(e) => {
+                    tracing::warn!("Test send to {} failed: {}", to, e);
+                    response.failed += 1;
+                    TestSendResult { to: to.clone(), message_id: None, error: Some(e.to_string()) }
+                }
+            };
+            response.results.push(result);
+        }
+
+        Ok(response)
+    }
+}
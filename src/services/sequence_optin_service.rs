@@ -8,13 +8,16 @@ use crate::models::subscriber_sequence_progress::{
     DelayUnit
 };
 use crate::repositories::subscriber_sequence_progress_repository::SubscriberSequenceProgressRepository;
-use crate::email_service::EmailService;
+use crate::email_service::{EmailService, EmailRequest};
+use crate::models::email_outbox::NewOutboxMessage;
 use crate::error::ApiError;
 use crate::models::sequence_email::{SequenceEmail, SequenceEmailStatus};
 use crate::repositories::sequence_email_repository::SequenceEmailRepository;
 use crate::repositories::campaign_repository::CampaignRepository;
 use crate::repositories::campaign_list_repository::CampaignListRepository;
 use crate::models::campaign::CampaignType;
+use crate::models::webhook::{WebhookEvent, WebhookEventType};
+use crate::services::webhook_service::WebhookService;
 
 pub struct SequenceOptinService {
     sequence_progress_repo: SubscriberSequenceProgressRepository,
@@ -22,6 +25,7 @@ pub struct SequenceOptinService {
     campaign_repo: CampaignRepository,
     campaign_list_repo: CampaignListRepository,
     email_service: EmailService,
+    webhooks: WebhookService,
     pool: PgPool,
 }
 
@@ -29,6 +33,7 @@ impl SequenceOptinService {
     pub fn new(
         pool: PgPool,
         email_service: EmailService,
+        webhooks: WebhookService,
     ) -> Self {
         Self {
             sequence_progress_repo: SubscriberSequenceProgressRepository::new(pool.clone()),
@@ -36,6 +41,7 @@ impl SequenceOptinService {
             campaign_repo: CampaignRepository::new(pool.clone()),
             campaign_list_repo: CampaignListRepository::new(pool.clone()),
             email_service,
+            webhooks,
             pool,
         }
     }
@@ -282,24 +288,33 @@ impl SequenceOptinService {
                 .fetch_one(&self.pool)
                 .await?;
                 
-                tracing::info!("Sending email to subscriber: {}", subscriber.email);
+                tracing::info!("Queueing email to subscriber: {}", subscriber.email);
                 
-                // Ajouter le tracking à l'email
-                let tracked_body = self.email_service.add_tracking_to_email(
-                    &email.body,
-                    campaign_id,
-                    email.id,
-                    progress.subscriber_id
-                );
-                
-                // Envoyer l'email
-                match self.email_service.send_email(
-                    &subscriber.email,
-                    &email.subject,
-                    &tracked_body
+                // Utiliser le messenger configuré sur la campagne
+                let messenger = self.campaign_repo.find_by_id(campaign_id).await?
+                    .map(|campaign| campaign.messenger);
+
+                // Cached by the pipeline, every subscriber reaching this email gets the same body
+                let body = self.email_service.prepare_sequence_body(&self.pool, email).await?;
+
+                // Mettre l'email dans l'outbox, le tracking est ajouté à l'envoi
+                match self.email_service.queue_email(
+                    &self.pool,
+                    NewOutboxMessage {
+                        request: EmailRequest {
+                            to: subscriber.email.clone(),
+                            subject: email.subject.clone(),
+                            body,
+                            ..Default::default()
+                        },
+                        messenger,
+                        campaign_id: Some(campaign_id),
+                        sequence_email_id: Some(email.id),
+                        subscriber_id: Some(progress.subscriber_id),
+                    }
                 ).await {
-                    Ok(_) => {
-                        tracing::info!("Email sent successfully!");
+                    Ok(outbox_id) => {
+                        tracing::info!("Email queued as outbox message {}", outbox_id);
                         let now = Utc::now();
                         
                         // Mettre à jour la progression
@@ -340,12 +355,26 @@ impl SequenceOptinService {
                         // Mettre à jour la progression
                         let updated_progress = self.sequence_progress_repo.update(progress.id, update_dto).await?;
                         tracing::debug!("Progress updated, new position: {}", updated_progress.current_position);
+
+                        if updated_progress.completed {
+                            self.webhooks.emit(WebhookEvent::new(
+                                WebhookEventType::SequenceCompleted,
+                                Some(updated_progress.subscriber_id),
+                                Some(updated_progress.campaign_id),
+                                serde_json::json!({
+                                    "list_id": updated_progress.list_id,
+                                    "joined_at": updated_progress.joined_at,
+                                    "last_position": position,
+                                }),
+                            )).await;
+                        }
                         
                         sent_count += 1;
                     },
                     Err(e) => {
-                        tracing::error!("Failed to send sequence email: {}", e);
-                        // On pourrait implémenter des tentatives supplémentaires ici
+                        tracing::error!("Failed to queue sequence email: {}", e);
+                        // La progression reste inchangée, l'email sera remis en file au prochain passage.
+                        // Les échecs d'envoi sont retentés par les workers de l'outbox
                     }
                 }
             } else {
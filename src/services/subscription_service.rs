@@ -0,0 +1,49 @@
+use crate::{
+    email_service::unsubscribe::UnsubscribeLinks,
+    models::webhook::{WebhookEvent, WebhookEventType},
+    repositories::subscriber_list_repository::SubscriberListRepository,
+    services::webhook_service::WebhookService,
+    error::ApiError
+};
+
+pub struct SubscriptionService {
+    repository: SubscriberListRepository,
+    links: UnsubscribeLinks,
+    webhooks: WebhookService,
+}
+
+impl SubscriptionService {
+    pub fn new(repository: SubscriberListRepository, links: UnsubscribeLinks, webhooks: WebhookService) -> Self {
+        Self { repository, links, webhooks }
+    }
+
+    //
+    //** One-click unsubscribe from the lists of the campaign the token was sent with
+    //** Params : token (from the List-Unsubscribe URL)
+    //** Return : Result<Vec<i32>, ApiError> (lists unsubscribed from, empty when already done)
+    //
+    pub async fn unsubscribe(&self, token: &str) -> Result<Vec<i32>, ApiError> {
+        let token = self.links
+            .verify(token)
+            .ok_or_else(|| ApiError::BadRequest("Invalid unsubscribe token".to_string()))?;
+
+        let lists = self.repository
+            .unsubscribe_from_campaign(token.subscriber_id, token.campaign_id)
+            .await?;
+
+        tracing::info!(
+            "Subscriber {} unsubscribed from lists {:?} (campaign {})",
+            token.subscriber_id, lists, token.campaign_id
+        );
+
+        if !lists.is_empty() {
+            self.webhooks.emit(WebhookEvent::new(
+                WebhookEventType::Unsubscribe,
+                Some(token.subscriber_id),
+                Some(token.campaign_id),
+                serde_json::json!({ "list_ids": lists, "source": "unsubscribe_link" }),
+            )).await;
+        }
+        Ok(lists)
+    }
+}
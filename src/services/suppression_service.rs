@@ -0,0 +1,47 @@
+use crate::{
+    models::suppression::{
+        CreateSuppressionDto, ImportSuppressionsDto, ImportSuppressionsResponse, Suppression,
+        SuppressionFilter, SuppressionResponse, UpdateSuppressionDto,
+    },
+    models::delivery::DeliveryPagination,
+    repositories::suppression_repository::SuppressionRepository,
+    error::ApiError
+};
+
+pub struct SuppressionService {
+    repository: SuppressionRepository
+}
+
+impl SuppressionService {
+    pub fn new(repository: SuppressionRepository) -> Self {
+        Self { repository }
+    }
+
+    pub async fn get_suppressions(&self, filter: SuppressionFilter, pagination: DeliveryPagination) -> Result<SuppressionResponse, ApiError> {
+        self.repository.find_all(filter, pagination).await
+    }
+
+    pub async fn get_suppression(&self, id: i64) -> Result<Suppression, ApiError> {
+        self.repository.find_by_id(id).await?.ok_or(ApiError::NotFound)
+    }
+
+    pub async fn create_suppression(&self, dto: CreateSuppressionDto) -> Result<Suppression, ApiError> {
+        let suppression = self.repository.create(dto).await?;
+        tracing::info!("Suppressed {} ({:?})", suppression.value, suppression.reason);
+        Ok(suppression)
+    }
+
+    pub async fn import_suppressions(&self, dto: ImportSuppressionsDto) -> Result<ImportSuppressionsResponse, ApiError> {
+        let result = self.repository.import(dto).await?;
+        tracing::info!("Imported {} suppressions, {} invalid values", result.imported, result.invalid.len());
+        Ok(result)
+    }
+
+    pub async fn update_suppression(&self, id: i64, dto: UpdateSuppressionDto) -> Result<Suppression, ApiError> {
+        self.repository.update(id, dto).await?.ok_or(ApiError::NotFound)
+    }
+
+    pub async fn delete_suppression(&self, id: i64) -> Result<Suppression, ApiError> {
+        self.repository.delete(id).await?.ok_or(ApiError::NotFound)
+    }
+}
@@ -0,0 +1,143 @@
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use crate::{
+    models::webhook::{
+        CreateWebhookDto, UpdateWebhookDto, Webhook, WebhookDelivery, WebhookDeliveryFilter,
+        WebhookDeliveryResponse, WebhookEvent, WebhookEventType,
+    },
+    models::delivery::DeliveryPagination,
+    repositories::webhook_repository::WebhookRepository,
+    error::ApiError
+};
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of "{timestamp}.{body}">`
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Unix time the payload was signed at, part of the signed content
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+// Same id on every attempt of a delivery, receivers can use it to drop duplicates
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+//
+//** Sign a webhook payload
+//** Receivers recompute the HMAC over "{timestamp}.{body}" with the webhook secret
+//** and should refuse timestamps too far from their clock
+//** Params : secret , timestamp (unix seconds) , body (exact bytes sent)
+//** Return : String (hex signature, without the sha256= prefix)
+//
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn generate_secret() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    hex::encode(bytes)
+}
+
+fn check_url(url: &str) -> Result<(), ApiError> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        _ => Err(ApiError::BadRequest(format!("Invalid webhook URL: {}", url))),
+    }
+}
+
+//
+//** Webhook endpoints and the events queued for them
+//** Events are only queued here, the WebhookWorker posts them and handles retries
+//
+#[derive(Clone)]
+pub struct WebhookService {
+    repository: WebhookRepository
+}
+
+impl WebhookService {
+    pub fn new(repository: WebhookRepository) -> Self {
+        Self { repository }
+    }
+
+    //
+    //** Queue an event for the webhooks listening to it
+    //** Never fails the caller, the action that raised the event is already done
+    //** Params : event
+    //
+    pub async fn emit(&self, event: WebhookEvent) {
+        match self.repository.enqueue(&event, None).await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!("Queued {:?} event for {} webhooks", event.event_type, count),
+            Err(e) => tracing::error!("Failed to queue {:?} webhook event: {}", event.event_type, e),
+        }
+    }
+
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>, ApiError> {
+        self.repository.find_all().await
+    }
+
+    pub async fn get_webhook(&self, id: i32) -> Result<Webhook, ApiError> {
+        self.repository.find_by_id(id).await?.ok_or(ApiError::NotFound)
+    }
+
+    pub async fn create_webhook(&self, dto: CreateWebhookDto) -> Result<Webhook, ApiError> {
+        check_url(&dto.url)?;
+        let secret = dto.secret.clone().filter(|s| !s.is_empty()).unwrap_or_else(generate_secret);
+
+        let webhook = self.repository.create(dto, secret).await?;
+        tracing::info!("Created webhook {} to {} for {:?}", webhook.id, webhook.url, webhook.event_types);
+        Ok(webhook)
+    }
+
+    pub async fn update_webhook(&self, id: i32, dto: UpdateWebhookDto) -> Result<Webhook, ApiError> {
+        if let Some(url) = &dto.url {
+            check_url(url)?;
+        }
+        if dto.secret.as_deref() == Some("") {
+            return Err(ApiError::BadRequest("Webhook secret cannot be empty".to_string()));
+        }
+        self.repository.update(id, dto).await?.ok_or(ApiError::NotFound)
+    }
+
+    pub async fn delete_webhook(&self, id: i32) -> Result<Webhook, ApiError> {
+        self.repository.delete(id).await?.ok_or(ApiError::NotFound)
+    }
+
+    /// Queue a test event for one webhook, whatever its event types
+    pub async fn test_webhook(&self, id: i32) -> Result<(), ApiError> {
+        let webhook = self.get_webhook(id).await?;
+        let event = WebhookEvent::new(
+            WebhookEventType::Test,
+            None,
+            None,
+            serde_json::json!({ "webhook_id": webhook.id, "name": webhook.name }),
+        );
+
+        if self.repository.enqueue(&event, Some(webhook.id)).await? == 0 {
+            return Err(ApiError::BadRequest("Webhook is disabled".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn get_deliveries(&self, filter: WebhookDeliveryFilter, pagination: DeliveryPagination) -> Result<WebhookDeliveryResponse, ApiError> {
+        self.repository.find_deliveries(filter, pagination).await
+    }
+
+    pub async fn get_delivery(&self, id: i64) -> Result<WebhookDelivery, ApiError> {
+        self.repository.find_delivery(id).await?.ok_or(ApiError::NotFound)
+    }
+
+    /// Send a delivery again, successful or failed, with a fresh series of attempts
+    pub async fn redeliver(&self, id: i64) -> Result<WebhookDelivery, ApiError> {
+        match self.repository.redeliver(id).await? {
+            Some(delivery) => Ok(delivery),
+            None => {
+                // Either unknown or being sent right now
+                self.get_delivery(id).await?;
+                Err(ApiError::BadRequest("Delivery is being sent".to_string()))
+            }
+        }
+    }
+}
+
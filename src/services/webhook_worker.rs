@@ -0,0 +1,276 @@
+use std::time::Duration;
+use chrono::Utc;
+use serde_json::Value as JsonValue;
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use crate::{
+    email_service::config::WebhookConfig,
+    models::webhook::{ClaimedWebhookDelivery, WebhookDeliveryStatus, WebhookEventType},
+    repositories::webhook_repository::WebhookRepository,
+    services::webhook_service::{sign, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    error::ApiError
+};
+
+// Kept from the receiver answer, enough to see what went wrong
+const MAX_RESPONSE_LEN: usize = 1000;
+
+/// Outcome of one POST to a webhook endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    pub status_code: Option<u16>,
+    pub response: Option<String>,
+    // Connection errors, timeouts and non 2xx answers
+    pub error: Option<String>,
+}
+
+impl Attempt {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+//
+//** POST a signed JSON payload to a webhook endpoint
+//** Any 2xx answer is a success, redirects are not followed
+//** Params : client , url , secret , delivery_id , event_type , payload
+//** Return : Attempt
+//
+pub async fn post(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    delivery_id: i64,
+    event_type: WebhookEventType,
+    payload: &JsonValue,
+) -> Attempt {
+    let body = match serde_json::to_vec(payload) {
+        Ok(body) => body,
+        Err(e) => return Attempt { status_code: None, response: None, error: Some(e.to_string()) },
+    };
+    let timestamp = Utc::now().timestamp();
+    let event = serde_json::to_value(event_type).ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    let result = client.post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, timestamp, &body)))
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(EVENT_HEADER, event)
+        .header(DELIVERY_HEADER, delivery_id.to_string())
+        .body(body)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            let text: String = text.chars().take(MAX_RESPONSE_LEN).collect();
+
+            Attempt {
+                status_code: Some(status.as_u16()),
+                response: (!text.is_empty()).then_some(text),
+                error: (!status.is_success()).then(|| format!("Endpoint answered {}", status)),
+            }
+        }
+        Err(e) => Attempt { status_code: None, response: None, error: Some(e.to_string()) },
+    }
+}
+
+//
+//** Worker posting the events queued in webhook_deliveries
+//** Failed attempts are retried with exponential backoff up to retry.max_attempts,
+//** every attempt is recorded on the delivery (status code, response, error)
+//
+#[derive(Clone)]
+pub struct WebhookWorker {
+    repository: WebhookRepository,
+    client: reqwest::Client,
+    config: WebhookConfig,
+}
+
+impl WebhookWorker {
+    pub fn new(pool: PgPool, config: WebhookConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to build the webhook HTTP client");
+
+        Self {
+            repository: WebhookRepository::new(pool),
+            client,
+            config,
+        }
+    }
+
+    /// Start `config.workers` workers on the tokio runtime
+    pub fn spawn(self) -> Vec<JoinHandle<()>> {
+        tracing::info!("Starting {} webhook workers", self.config.workers);
+
+        (0..self.config.workers)
+            .map(|worker_id| {
+                let worker = self.clone();
+                tokio::spawn(async move { worker.run(worker_id).await })
+            })
+            .collect()
+    }
+
+    async fn run(self, worker_id: usize) {
+        let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
+
+        loop {
+            match self.process_batch().await {
+                Ok(0) => tokio::time::sleep(poll_interval).await,
+                Ok(count) => tracing::debug!("Webhook worker {} processed {} deliveries", worker_id, count),
+                Err(e) => {
+                    tracing::error!("Webhook worker {} failed: {}", worker_id, e);
+                    tokio::time::sleep(poll_interval).await;
+                }
+            }
+        }
+    }
+
+    //
+    //** Claim and post one batch of deliveries
+    //** Return : Result<usize, ApiError> (number of deliveries claimed)
+    //
+    pub async fn process_batch(&self) -> Result<usize, ApiError> {
+        let deliveries = self.repository.claim(self.config.batch_size, self.config.lock_timeout_secs).await?;
+        let count = deliveries.len();
+
+        // One failing delivery must not leave the rest of the batch locked
+        for delivery in deliveries {
+            let id = delivery.delivery.id;
+            if let Err(e) = self.deliver(delivery).await {
+                tracing::error!("Failed to record webhook delivery {}: {}", id, e);
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn deliver(&self, claimed: ClaimedWebhookDelivery) -> Result<(), ApiError> {
+        let delivery = claimed.delivery;
+        let attempt = post(&self.client, &claimed.url, &claimed.secret, delivery.id, delivery.event_type, &delivery.payload).await;
+        let status_code = attempt.status_code.map(i32::from);
+
+        if attempt.succeeded() {
+            tracing::debug!("Delivered {:?} event {} to {}", delivery.event_type, delivery.id, claimed.url);
+            return self.repository.record_attempt(
+                delivery.id, WebhookDeliveryStatus::Success, status_code, attempt.response.as_deref(), None, None,
+            ).await;
+        }
+
+        let error = attempt.error.unwrap_or_default();
+        let retry = &self.config.retry;
+
+        if delivery.attempts < retry.max_attempts {
+            let delay = retry.backoff(delivery.attempts as u32);
+            tracing::warn!(
+                "Webhook delivery {} to {} failed (attempt {}/{}), retrying in {}s: {}",
+                delivery.id, claimed.url, delivery.attempts, retry.max_attempts, delay.as_secs(), error
+            );
+
+            let retry_at = Utc::now() + chrono::Duration::seconds(delay.as_secs() as i64);
+            return self.repository.record_attempt(
+                delivery.id, WebhookDeliveryStatus::Pending, status_code, attempt.response.as_deref(), Some(&error), Some(retry_at),
+            ).await;
+        }
+
+        tracing::error!("Webhook delivery {} to {} failed after {} attempts: {}", delivery.id, claimed.url, delivery.attempts, error);
+        self.repository.record_attempt(
+            delivery.id, WebhookDeliveryStatus::Failed, status_code, attempt.response.as_deref(), Some(&error), None,
+        ).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Local receiver answering `status` to one request, returns its url and the raw request
+    async fn receiver(status: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+
+            // Read the headers, then the body announced by Content-Length
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text.lines()
+                        .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok", status);
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (url, handle)
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines()
+            .find_map(|line| line.split_once(':').filter(|(key, _)| key.eq_ignore_ascii_case(name)))
+            .map(|(_, value)| value.trim())
+    }
+
+    #[tokio::test]
+    async fn posts_signed_payloads() {
+        let (url, handle) = receiver("200 OK").await;
+        let payload = serde_json::json!({ "event": "open", "subscriber": { "id": 7 } });
+
+        let attempt = post(&reqwest::Client::new(), &url, "secret", 42, WebhookEventType::Open, &payload).await;
+        assert!(attempt.succeeded());
+        assert_eq!(attempt.status_code, Some(200));
+        assert_eq!(attempt.response.as_deref(), Some("ok"));
+
+        let request = handle.await.unwrap();
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        let timestamp: i64 = header(&request, TIMESTAMP_HEADER).unwrap().parse().unwrap();
+
+        assert!(request.starts_with("POST /hook "));
+        assert_eq!(serde_json::from_str::<JsonValue>(body).unwrap(), payload);
+        assert_eq!(header(&request, EVENT_HEADER), Some("open"));
+        assert_eq!(header(&request, DELIVERY_HEADER), Some("42"));
+        assert_eq!(
+            header(&request, SIGNATURE_HEADER).map(str::to_string),
+            Some(format!("sha256={}", sign("secret", timestamp, body.as_bytes())))
+        );
+        assert!((Utc::now().timestamp() - timestamp).abs() < 5);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_attempts() {
+        let (url, handle) = receiver("503 Service Unavailable").await;
+
+        let attempt = post(&reqwest::Client::new(), &url, "secret", 1, WebhookEventType::Test, &serde_json::json!({})).await;
+        handle.await.unwrap();
+        assert!(!attempt.succeeded());
+        assert_eq!(attempt.status_code, Some(503));
+
+        // Nothing listening anymore
+        let attempt = post(&reqwest::Client::new(), &url, "secret", 1, WebhookEventType::Test, &serde_json::json!({})).await;
+        assert!(!attempt.succeeded());
+        assert_eq!(attempt.status_code, None);
+    }
+}